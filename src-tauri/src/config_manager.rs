@@ -1,12 +1,17 @@
 use log::{debug, error, info};
 use serde::{de, Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{self, Read, Write};
+use std::io::{self, Read};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tauri_plugin_global_shortcut::{Code, Modifiers};
+use tauri_plugin_http::reqwest::header::{HeaderName, HeaderValue};
 use crate::utils::key_map::ShortcutConfig;
-use crate::utils::misc::ApiUrl;
+use crate::meme_server::SortOrder;
+use crate::utils::misc::{app_dirs, write_atomic, ApiUrl, RequestFormat, SearchMethod};
+use crate::utils::concurrency::DEFAULT_CONCURRENCY;
+use crate::utils::network::{DownloadMode, TimeoutPolicy};
 
 impl Default for ShortcutConfig {
     fn default() -> Self {
@@ -18,29 +23,352 @@ impl Default for ShortcutConfig {
     }
 }
 
+// 复制表情包时写入剪贴板的内容
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardMode {
+    // 下载并写入图片
+    #[default]
+    Image,
+    // 只写入图片地址，无需下载
+    Url,
+    // 先写入图片地址再写入图片，剪贴板历史中两者都可粘贴
+    Both,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserPreferences {
     #[serde(default = "default_true")]
     pub copy_to_clipboard: bool,
     #[serde(default)]
+    pub clipboard_mode: ClipboardMode,
+    // 复制后自动模拟粘贴快捷键，需要系统的辅助功能权限，默认关闭
+    #[serde(default)]
+    pub synthetic_paste: bool,
+    #[serde(default)]
     pub shortcuts: ShortcutConfigs,
     #[serde(default)]
     pub api_urls: ApiUrlConfig,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub test_mode: bool,
+    #[serde(default)]
+    pub pause_when_hidden: bool,
+    // 左键单击托盘图标时切换主窗口，关闭后左键单击显示托盘菜单
+    #[serde(default = "default_true")]
+    pub tray_click_toggles: bool,
+    #[serde(default)]
+    pub sort_order: SortOrder,
+    #[serde(default)]
+    pub source_priority: Vec<String>,
+    #[serde(default)]
+    pub download_timeout: TimeoutPolicy,
+    // 备用URL下载的尝试方式，默认按顺序尝试
+    #[serde(default)]
+    pub download_mode: DownloadMode,
+    #[serde(default = "default_manifest_timeout_secs")]
+    pub manifest_timeout_secs: u64,
+    // 社区清单自动刷新间隔（秒），0 表示禁用
+    #[serde(default)]
+    pub manifest_auto_refresh_secs: u64,
+    // 缓存的社区清单的有效期（秒），过期后获取清单时重新下载
+    #[serde(default = "default_manifest_cache_ttl_secs")]
+    pub manifest_cache_ttl_secs: u64,
+    // 无结果时显示的占位图（本地路径或URL）
+    #[serde(default)]
+    pub placeholder_image: Option<String>,
+    #[serde(default = "default_concurrency_limit")]
+    pub concurrency_limit: usize,
+    // 是否只允许运行一个实例，修改后下次启动生效
+    #[serde(default = "default_true")]
+    pub single_instance: bool,
+    // 关键词（去除首尾空白后）短于该长度时不发起搜索
+    #[serde(default = "default_min_keyword_length")]
+    pub min_keyword_length: usize,
+    // 搜索历史保留天数，0 表示永久保留
+    #[serde(default)]
+    pub search_history_retention_days: u64,
+    // 是否记录搜索历史
+    #[serde(default = "default_true")]
+    pub record_history: bool,
+    // 最多保留的搜索历史条数
+    #[serde(default = "default_search_history_limit")]
+    pub search_history_limit: usize,
+    // 强制使用的主题（"light" 或 "dark"），未设置时跟随系统
+    #[serde(default)]
+    pub theme_override: Option<String>,
+    // 本次运行允许的搜索次数，0 表示不限制
+    #[serde(default)]
+    pub session_search_quota: u64,
+    // 复制前是否先解析短链接等重定向，得到最终的图片地址
+    #[serde(default)]
+    pub resolve_redirects: bool,
+    // 是否将搜索结果与同一关键词之前的结果合并，避免后端偶尔返回不完整的结果
+    #[serde(default)]
+    pub merge_cached_results: bool,
+    // 每次搜索请求的结果数量
+    #[serde(default = "default_result_limit")]
+    pub result_limit: usize,
+    // 活跃API连续搜索失败时自动切换到下一个API
+    #[serde(default = "default_true")]
+    pub auto_failover: bool,
+    // 搜索结果缓存有效期（秒），0 表示不缓存
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub search_cache_ttl_secs: u64,
+    // 单次搜索请求的超时（秒），未设置时使用 timeout_seconds
+    #[serde(default)]
+    pub search_timeout_seconds: Option<u64>,
+    // 日志级别：off、error、warn、info、debug 或 trace
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    // 屏蔽词，图片地址或描述中包含任一屏蔽词（忽略大小写）的搜索结果会被隐藏
+    #[serde(default)]
+    pub blocklist: Vec<String>,
+    // 启动后是否用最近的搜索关键词预先填充搜索缓存
+    #[serde(default)]
+    pub warm_cache_on_startup: bool,
+    // 当前版本不认识的顶层字段（例如降级前由新版本写入的设置），
+    // 读取时原样保留并在保存时写回，避免在不同版本间切换时丢失设置
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ShortcutConfigs {
     #[serde(default = "default_toggle_app_shortcut")]
     pub toggle_app: ShortcutConfig,
+    // 重新复制最近一次复制的表情包，默认不绑定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_last: Option<ShortcutConfig>,
+    // 显示窗口并聚焦搜索框，默认不绑定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_search: Option<ShortcutConfig>,
+    // 快速粘贴指定收藏的快捷键
+    #[serde(default)]
+    pub quick_paste: Vec<QuickPasteBinding>,
     // 可以添加更多快捷键配置
 }
 
+// 切换窗口以外、可单独绑定快捷键的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutAction {
+    CopyLast,
+    OpenSearch,
+}
+
+// 快捷键与收藏表情包的绑定
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuickPasteBinding {
+    pub favorite_id: String,
+    pub shortcut: ShortcutConfig,
+}
+
+impl ShortcutConfigs {
+    // 列出所有已配置的快捷键及其配置项名称，新增快捷键时需同步添加
+    pub fn entries(&self) -> Vec<(String, &ShortcutConfig)> {
+        let mut entries = vec![("toggle_app".to_string(), &self.toggle_app)];
+        if let Some(copy_last) = &self.copy_last {
+            entries.push(("copy_last".to_string(), copy_last));
+        }
+        if let Some(open_search) = &self.open_search {
+            entries.push(("open_search".to_string(), open_search));
+        }
+        entries.extend(
+            self.quick_paste
+                .iter()
+                .map(|binding| (format!("quick_paste:{}", binding.favorite_id), &binding.shortcut)),
+        );
+        entries
+    }
+
+    // 查找按键组合对应的动作快捷键
+    pub fn find_action(&self, mods: Modifiers, code: Code) -> Option<ShortcutAction> {
+        let matches = |config: &Option<ShortcutConfig>| {
+            config
+                .as_ref()
+                .is_some_and(|c| c.to_tauri_shortcut() == Ok((mods, code)))
+        };
+        if matches(&self.copy_last) {
+            Some(ShortcutAction::CopyLast)
+        } else if matches(&self.open_search) {
+            Some(ShortcutAction::OpenSearch)
+        } else {
+            None
+        }
+    }
+
+    // 查找已使用该按键组合的快捷键，返回其动作名称
+    pub fn owner_of(&self, mods: Modifiers, code: Code) -> Option<String> {
+        self.entries().into_iter().find_map(|(id, config)| {
+            (config.to_tauri_shortcut() == Ok((mods, code)))
+                .then(|| config.action_name(&id).to_string())
+        })
+    }
+
+    // 查找按键组合对应的快速粘贴绑定
+    pub fn find_quick_paste(&self, mods: Modifiers, code: Code) -> Option<&QuickPasteBinding> {
+        self.quick_paste
+            .iter()
+            .find(|binding| binding.shortcut.to_tauri_shortcut() == Ok((mods, code)))
+    }
+
+    // 校验所有快捷键，出错时在错误信息中注明对应的动作名称
+    pub fn validate(&self) -> Result<(), String> {
+        for (id, config) in self.entries() {
+            config
+                .validate()
+                .map_err(|e| format!("快捷键「{}」无效：{}", config.action_name(&id), e))?;
+        }
+        Ok(())
+    }
+
+    // 检查是否有两个快捷键解析为相同的按键组合，冲突时返回冲突双方的动作名称
+    pub fn check_conflicts(&self) -> Result<(), String> {
+        let entries = self.entries();
+        for (i, (id_a, a)) in entries.iter().enumerate() {
+            for (id_b, b) in &entries[i + 1..] {
+                let (Ok(keys_a), Ok(keys_b)) = (a.to_tauri_shortcut(), b.to_tauri_shortcut()) else {
+                    continue;
+                };
+                if keys_a == keys_b {
+                    return Err(format!(
+                        "快捷键冲突：「{}」与「{}」使用了相同的按键组合",
+                        a.action_name(id_a),
+                        b.action_name(id_b)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ApiUrlConfig {
     #[serde(default = "default_api_urls")]
     pub urls: Vec<ApiUrl>,
     #[serde(default = "default_active_api_index")]
     pub active_index: usize,
+    // 用于搜索和缩略图的预览源，未设置时使用活跃源
+    #[serde(default)]
+    pub preview_index: Option<usize>,
+    // 复制时使用的全尺寸源，未设置时直接使用搜索结果中的URL
+    #[serde(default)]
+    pub full_index: Option<usize>,
+}
+
+impl ApiUrlConfig {
+    // 获取当前活跃的API URL
+    fn active_api(&self) -> ApiUrl {
+        if self.urls.is_empty() {
+            return default_api_urls().remove(0);
+        }
+
+        let index = if self.active_index < self.urls.len() {
+            self.active_index
+        } else {
+            0
+        };
+
+        self.urls[index].clone()
+    }
+
+    fn active_url(&self) -> String {
+        self.active_api().url
+    }
+
+    // 获取用于搜索的预览源
+    fn preview_api(&self) -> ApiUrl {
+        match self.preview_index.and_then(|i| self.urls.get(i)) {
+            Some(api) => api.clone(),
+            None => self.active_api(),
+        }
+    }
+
+    // 获取用于搜索的预览源URL
+    fn preview_url(&self) -> String {
+        self.preview_api().url
+    }
+
+    // 将预览源下的图片URL映射到全尺寸源
+    //
+    // 分级存储的部署中，两个源使用相同的路径结构，只是主机不同，
+    // 因此只需替换URL前缀。未配置全尺寸源或URL不属于预览源时原样返回。
+    fn resolve_full_url(&self, image_url: &str) -> String {
+        let full = match self.full_index.and_then(|i| self.urls.get(i)) {
+            Some(api) => api.url.trim_end_matches('/'),
+            None => return image_url.to_string(),
+        };
+        let preview = self.preview_url();
+        let preview = preview.trim_end_matches('/');
+
+        if preview == full {
+            return image_url.to_string();
+        }
+
+        match image_url.strip_prefix(preview) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", full, rest),
+            _ => image_url.to_string(),
+        }
+    }
+
+    // 修正手动编辑等原因造成的不一致：去除重复的URL并把索引限制在有效范围内
+    //
+    // 重复的URL只保留第一个，指向被去除项的索引改为指向保留的那一项。
+    // 返回是否做了修改。
+    pub fn normalize(&mut self) -> bool {
+        let mut changed = false;
+
+        // 旧索引到去重后新索引的映射
+        let mut mapping = Vec::with_capacity(self.urls.len());
+        let mut kept: Vec<ApiUrl> = Vec::with_capacity(self.urls.len());
+        for api in self.urls.drain(..) {
+            let key = api.url.trim().trim_end_matches('/');
+            match kept.iter().position(|k| k.url.trim().trim_end_matches('/') == key) {
+                Some(existing) => {
+                    debug!("去除重复的API URL: {}", api.url);
+                    mapping.push(existing);
+                    changed = true;
+                }
+                None => {
+                    mapping.push(kept.len());
+                    kept.push(api);
+                }
+            }
+        }
+        self.urls = kept;
+
+        let remap = |index: usize| mapping.get(index).copied();
+        let active_index = remap(self.active_index).unwrap_or(0);
+        let preview_index = self.preview_index.and_then(remap);
+        let full_index = self.full_index.and_then(remap);
+        if (active_index, preview_index, full_index)
+            != (self.active_index, self.preview_index, self.full_index)
+        {
+            changed = true;
+        }
+        self.active_index = active_index;
+        self.preview_index = preview_index;
+        self.full_index = full_index;
+
+        changed
+    }
+
+    // 删除某个URL后修正指向它之后的索引
+    fn shift_indices_after_removal(&mut self, removed: usize) {
+        for index in [&mut self.preview_index, &mut self.full_index] {
+            *index = match *index {
+                Some(i) if i == removed => None,
+                Some(i) if i > removed => Some(i - 1),
+                other => other,
+            };
+        }
+    }
 }
 
 
@@ -51,6 +379,8 @@ impl Default for ApiUrlConfig {
         Self {
             urls: default_api_urls(),
             active_index: 0,
+            preview_index: None,
+            full_index: None,
         }
     }
 }
@@ -74,6 +404,10 @@ fn default_api_urls() -> Vec<ApiUrl> {
         ApiUrl {
             name: "默认API".to_string(),
             url: "https://mememeow.morami.icu".to_string(),
+            request_format: RequestFormat::default(),
+            search_method: SearchMethod::default(),
+            results_key: None,
+            headers: Default::default(),
         }
     ]
 }
@@ -82,6 +416,48 @@ fn default_active_api_index() -> usize {
     0
 }
 
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_manifest_timeout_secs() -> u64 {
+    15
+}
+
+fn default_manifest_cache_ttl_secs() -> u64 {
+    6 * 60 * 60
+}
+
+// 每次搜索结果数量的上限，避免给服务器造成过大压力
+pub const MAX_RESULT_LIMIT: usize = 100;
+
+fn default_result_limit() -> usize {
+    10
+}
+
+// 搜索历史条数的上限
+pub const MAX_SEARCH_HISTORY_LIMIT: usize = 500;
+
+fn default_search_history_limit() -> usize {
+    50
+}
+
+fn default_search_cache_ttl_secs() -> u64 {
+    60
+}
+
+fn default_min_keyword_length() -> usize {
+    1
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_concurrency_limit() -> usize {
+    DEFAULT_CONCURRENCY
+}
+
 
 
 
@@ -89,23 +465,55 @@ impl Default for UserPreferences {
     fn default() -> Self {
         Self {
             copy_to_clipboard: true,
+            clipboard_mode: ClipboardMode::default(),
+            synthetic_paste: false,
             shortcuts: ShortcutConfigs::default(),
             api_urls: ApiUrlConfig::default(),
+            timeout_seconds: default_timeout_seconds(),
+            proxy_url: None,
+            user_agent: None,
+            test_mode: false,
+            pause_when_hidden: false,
+            tray_click_toggles: true,
+            sort_order: SortOrder::default(),
+            source_priority: Vec::new(),
+            download_timeout: TimeoutPolicy::default(),
+            download_mode: DownloadMode::default(),
+            manifest_timeout_secs: default_manifest_timeout_secs(),
+            manifest_auto_refresh_secs: 0,
+            manifest_cache_ttl_secs: default_manifest_cache_ttl_secs(),
+            placeholder_image: None,
+            concurrency_limit: DEFAULT_CONCURRENCY,
+            single_instance: true,
+            min_keyword_length: default_min_keyword_length(),
+            search_history_retention_days: 0,
+            record_history: true,
+            search_history_limit: default_search_history_limit(),
+            theme_override: None,
+            session_search_quota: 0,
+            resolve_redirects: false,
+            merge_cached_results: false,
+            result_limit: default_result_limit(),
+            auto_failover: true,
+            search_cache_ttl_secs: default_search_cache_ttl_secs(),
+            search_timeout_seconds: None,
+            log_level: default_log_level(),
+            blocklist: Vec::new(),
+            warm_cache_on_startup: false,
+            extra: serde_json::Map::new(),
         }
     }
 }
 
 pub struct ConfigManager {
     path: PathBuf,
-    preferences: Arc<Mutex<UserPreferences>>,
+    preferences: Arc<RwLock<UserPreferences>>,
 }
 
 impl ConfigManager {
     pub fn new(app_name: &str) -> Result<Self, io::Error> {
         // 获取或创建配置目录
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(app_name);
+        let config_dir = app_dirs().config_root.join(app_name);
 
         if !config_dir.exists() {
             fs::create_dir_all(&config_dir)?;
@@ -113,7 +521,11 @@ impl ConfigManager {
 
         debug!("配置目录: {:?}", config_dir);
 
-        let config_path = config_dir.join("preferences.json");
+        Ok(Self::open(config_dir.join("preferences.json")))
+    }
+
+    // 从指定的配置文件加载，文件不存在时创建默认配置，加载失败时使用默认配置
+    pub(crate) fn open(config_path: PathBuf) -> Self {
         let preferences = match Self::load_preferences(&config_path) {
             Ok(prefs) => {
                 info!("加载用户配置成功");
@@ -125,10 +537,10 @@ impl ConfigManager {
             }
         };
 
-        Ok(Self {
+        Self {
             path: config_path,
-            preferences: Arc::new(Mutex::new(preferences)),
-        })
+            preferences: Arc::new(RwLock::new(preferences)),
+        }
     }
 
     // 加载偏好设置
@@ -136,9 +548,8 @@ impl ConfigManager {
         if !path.exists() {
             debug!("配置文件不存在，将创建默认配置: {:?}", path);
             let default_prefs = UserPreferences::default();
-            let mut file = File::create(path)?;
             let json = serde_json::to_string_pretty(&default_prefs)?;
-            file.write_all(json.as_bytes())?;
+            write_atomic(path, json.as_bytes())?;
             return Ok(default_prefs);
         }
 
@@ -146,8 +557,18 @@ impl ConfigManager {
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
-        match serde_json::from_str(&contents) {
-            Ok(prefs) => Ok(prefs),
+        match serde_json::from_str::<UserPreferences>(&contents) {
+            Ok(mut prefs) => {
+                // 修正后写回文件，使配置文件保持一致
+                if prefs.api_urls.normalize() {
+                    info!("API URL配置存在不一致，已修正");
+                    let json = serde_json::to_string_pretty(&prefs)?;
+                    if let Err(err) = write_atomic(path, json.as_bytes()) {
+                        error!("保存修正后的配置失败: {}", err);
+                    }
+                }
+                Ok(prefs)
+            }
             Err(err) => {
                 error!("解析配置文件失败: {}", err);
                 Err(io::Error::new(io::ErrorKind::InvalidData, err))
@@ -157,222 +578,783 @@ impl ConfigManager {
 
     // 保存偏好设置
     fn save_preferences(&self) -> Result<(), io::Error> {
-        let prefs = match self.preferences.try_lock() {
-            Ok(guard) => guard.clone(),
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                return Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"));
-            }
-        };
+        let prefs = self.read_preferences()?.clone();
 
         let json = serde_json::to_string_pretty(&prefs)?;
-        let mut file = File::create(&self.path)?;
-        file.write_all(json.as_bytes())?;
+        write_atomic(&self.path, json.as_bytes())?;
         debug!("配置已保存到: {:?}", self.path);
         Ok(())
     }
 
+    // 获取偏好设置的读锁，读取之间不会互相阻塞
+    fn read_preferences(&self) -> Result<RwLockReadGuard<'_, UserPreferences>, io::Error> {
+        self.preferences.read().map_err(|err| {
+            error!("获取偏好锁失败: {}", err);
+            io::Error::other("获取偏好锁失败")
+        })
+    }
+
+    // 获取偏好设置的写锁，修改并保存期间阻塞其他读写
+    fn write_preferences(&self) -> Result<RwLockWriteGuard<'_, UserPreferences>, io::Error> {
+        self.preferences.write().map_err(|err| {
+            error!("获取偏好锁失败: {}", err);
+            io::Error::other("获取偏好锁失败")
+        })
+    }
+
     fn save_preferences_locked(&self, prefs: &UserPreferences) -> Result<(), io::Error> {
         let json = serde_json::to_string_pretty(prefs)?;
-        let mut file = File::create(&self.path)?;
-        file.write_all(json.as_bytes())?;
+        write_atomic(&self.path, json.as_bytes())?;
         debug!("配置已保存到: {:?}", self.path);
         Ok(())
     }
 
+    // 在锁内修改偏好设置并保存
+    fn modify_preferences<F>(&self, modify: F) -> Result<(), io::Error>
+    where
+        F: FnOnce(&mut UserPreferences),
+    {
+        let mut guard = self.write_preferences()?;
+        modify(&mut guard);
+        self.save_preferences_locked(&guard.clone())
+    }
+
     // 获取偏好设置
     pub fn get_preferences(&self) -> Result<UserPreferences, io::Error> {
-        debug!("尝试获取偏好设置锁");
-        match self.preferences.try_lock() {
-            Ok(guard) => {
-                debug!("成功获取偏好设置锁");
-                Ok(guard.clone())
-            }
-            Err(_) => {
-                error!("无法获取偏好设置锁，可能已被其他线程持有");
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+        Ok(self.read_preferences()?.clone())
     }
 
     // 更新偏好设置
     pub fn update_preferences(&self, new_prefs: UserPreferences) -> Result<(), io::Error> {
-        match self.preferences.try_lock() {
-            Ok(mut guard) => {
-                *guard = new_prefs;
-                self.save_preferences()
-            }
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                // 新增调试信息
-                error!("可能发生死锁或锁被长时间占用");
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
+        // 在持有锁时直接保存，避免 save_preferences 再次加锁失败
+        self.modify_preferences(|prefs| *prefs = new_prefs)
+    }
+
+    // 将偏好设置恢复为默认值，恢复前把当前配置文件备份为 preferences.json.bak
+    //
+    // 配置文件已损坏时同样会被备份，便于之后手动找回设置。
+    pub fn reset_preferences(&self) -> Result<UserPreferences, io::Error> {
+        let mut guard = self.write_preferences()?;
+        if self.path.exists() {
+            let backup_path = self.path.with_file_name("preferences.json.bak");
+            fs::copy(&self.path, &backup_path)?;
+            info!("当前配置已备份到: {:?}", backup_path);
         }
+
+        let defaults = UserPreferences::default();
+        self.save_preferences_locked(&defaults)?;
+        *guard = defaults.clone();
+        info!("偏好设置已恢复为默认值");
+        Ok(defaults)
     }
 
     // 更新剪贴板设置
     pub fn update_clipboard_setting(&self, enabled: bool) -> Result<(), io::Error> {
-        debug!("尝试更新剪贴板设置 a");
-        match self.preferences.try_lock() {
-            Ok(mut guard) => {
-                debug!("成功获取偏好设置锁 b");
-                guard.copy_to_clipboard = enabled;
-                debug!("剪贴板设置已更新: {}", enabled);
-                self.save_preferences_locked(&guard.clone())
+        let mut guard = self.write_preferences()?;
+        guard.copy_to_clipboard = enabled;
+        debug!("剪贴板设置已更新: {}", enabled);
+        self.save_preferences_locked(&guard.clone())
+    }
+
+    // 更新复制表情包时写入剪贴板的内容
+    pub fn update_clipboard_mode(&self, mode: ClipboardMode) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.clipboard_mode = mode)?;
+        debug!("剪贴板复制方式已更新: {:?}", mode);
+        Ok(())
+    }
+
+    // 更新复制后是否自动粘贴
+    pub fn update_synthetic_paste(&self, enabled: bool) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.synthetic_paste = enabled)?;
+        debug!("自动粘贴设置已更新: {}", enabled);
+        Ok(())
+    }
+
+    // 更新测试模式设置
+    pub fn update_test_mode(&self, enabled: bool) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.test_mode = enabled)?;
+        debug!("测试模式设置已更新: {}", enabled);
+        Ok(())
+    }
+
+    // 更新窗口隐藏时暂停后台操作的设置
+    pub fn update_pause_when_hidden(&self, enabled: bool) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.pause_when_hidden = enabled)?;
+        debug!("窗口隐藏时暂停设置已更新: {}", enabled);
+        Ok(())
+    }
+
+    // 更新左键单击托盘图标的行为
+    pub fn update_tray_click_toggles(&self, enabled: bool) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.tray_click_toggles = enabled)?;
+        debug!("托盘左键单击切换窗口设置已更新: {}", enabled);
+        Ok(())
+    }
+
+    // 更新默认排序方式与表情包库优先级
+    pub fn update_sort_settings(
+        &self,
+        sort_order: SortOrder,
+        source_priority: Vec<String>,
+    ) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| {
+            prefs.sort_order = sort_order;
+            prefs.source_priority = source_priority;
+        })?;
+        debug!("排序设置已更新: {:?}", sort_order);
+        Ok(())
+    }
+
+    // 更新备用URL下载的尝试方式
+    pub fn update_download_mode(&self, mode: DownloadMode) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.download_mode = mode)?;
+        debug!("下载尝试方式已更新: {:?}", mode);
+        Ok(())
+    }
+
+    // 更新备用URL下载的超时增长策略
+    pub fn update_download_timeout(&self, policy: TimeoutPolicy) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.download_timeout = policy)?;
+        debug!("下载超时策略已更新: {:?}", policy);
+        Ok(())
+    }
+
+    // 更新代理地址，传入空值时清除代理
+    pub fn update_proxy_url(&self, proxy_url: Option<String>) -> Result<(), io::Error> {
+        let proxy_url = proxy_url
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(url) = &proxy_url {
+            crate::utils::network::parse_proxy(url)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        }
+        self.modify_preferences(|prefs| prefs.proxy_url = proxy_url)?;
+        debug!("代理设置已更新");
+        Ok(())
+    }
+
+    // 更新社区清单下载超时
+    pub fn update_manifest_timeout(&self, secs: u64) -> Result<(), io::Error> {
+        if secs == 0 || secs > 120 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "清单下载超时必须在1到120秒之间",
+            ));
+        }
+        self.modify_preferences(|prefs| prefs.manifest_timeout_secs = secs)?;
+        debug!("清单下载超时已更新: {}秒", secs);
+        Ok(())
+    }
+
+    // 更新单次搜索请求的超时，None 表示使用客户端默认超时
+    pub fn update_search_timeout(&self, secs: Option<u64>) -> Result<(), io::Error> {
+        if let Some(secs) = secs {
+            if secs == 0 || secs > 120 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "搜索超时必须在1到120秒之间",
+                ));
             }
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
+        }
+        self.modify_preferences(|prefs| prefs.search_timeout_seconds = secs)?;
+        debug!("搜索超时已更新: {:?}秒", secs);
+        Ok(())
+    }
+
+    // 添加屏蔽词，已存在（忽略大小写）时不重复添加，返回更新后的屏蔽词列表
+    pub fn add_blocked_term(&self, term: &str) -> Result<Vec<String>, io::Error> {
+        let term = term.trim();
+        if term.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "屏蔽词不能为空"));
+        }
+        let lowered = term.to_lowercase();
+        let mut blocklist = Vec::new();
+        self.modify_preferences(|prefs| {
+            if !prefs.blocklist.iter().any(|t| t.to_lowercase() == lowered) {
+                prefs.blocklist.push(term.to_string());
             }
+            blocklist = prefs.blocklist.clone();
+        })?;
+        debug!("已添加屏蔽词: {}", term);
+        Ok(blocklist)
+    }
+
+    // 移除屏蔽词（忽略大小写），返回更新后的屏蔽词列表
+    pub fn remove_blocked_term(&self, term: &str) -> Result<Vec<String>, io::Error> {
+        let term = term.trim();
+        let lowered = term.to_lowercase();
+        let mut blocklist = Vec::new();
+        self.modify_preferences(|prefs| {
+            prefs.blocklist.retain(|t| t.trim().to_lowercase() != lowered);
+            blocklist = prefs.blocklist.clone();
+        })?;
+        debug!("已移除屏蔽词: {}", term);
+        Ok(blocklist)
+    }
+
+    // 更新日志级别，返回规范化后的级别名
+    pub fn update_log_level(&self, level: &str) -> Result<String, io::Error> {
+        let level = crate::utils::logging::parse_level(level)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+            .as_str()
+            .to_lowercase();
+        self.modify_preferences(|prefs| prefs.log_level = level.clone())?;
+        debug!("日志级别已更新: {}", level);
+        Ok(level)
+    }
+
+    // 更新搜索的最短关键词长度
+    pub fn update_min_keyword_length(&self, length: usize) -> Result<(), io::Error> {
+        if length == 0 || length > 20 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "最短关键词长度必须在1到20之间",
+            ));
         }
+        self.modify_preferences(|prefs| prefs.min_keyword_length = length)?;
+        debug!("最短关键词长度已更新: {}", length);
+        Ok(())
+    }
+
+    // 更新搜索历史保留天数
+    pub fn update_search_history_retention(&self, days: u64) -> Result<(), io::Error> {
+        debug!("搜索历史保留天数已更新: {}", days);
+        self.modify_preferences(|prefs| prefs.search_history_retention_days = days)
+    }
+
+    // 更新是否记录搜索历史
+    pub fn update_record_history(&self, enabled: bool) -> Result<(), io::Error> {
+        debug!("记录搜索历史已更新: {}", enabled);
+        self.modify_preferences(|prefs| prefs.record_history = enabled)
+    }
+
+    // 更新搜索历史最多保留的条数
+    pub fn update_search_history_limit(&self, limit: usize) -> Result<(), io::Error> {
+        if limit == 0 || limit > MAX_SEARCH_HISTORY_LIMIT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("搜索历史条数必须在1到{}之间", MAX_SEARCH_HISTORY_LIMIT),
+            ));
+        }
+        debug!("搜索历史条数上限已更新: {}", limit);
+        self.modify_preferences(|prefs| prefs.search_history_limit = limit)
+    }
+
+    // 更新主题覆盖设置
+    pub fn update_theme_override(&self, theme: Option<String>) -> Result<(), io::Error> {
+        debug!("主题覆盖设置已更新: {:?}", theme);
+        self.modify_preferences(|prefs| prefs.theme_override = theme)
+    }
+
+    // 更新单次运行的搜索次数上限
+    pub fn update_session_search_quota(&self, quota: u64) -> Result<(), io::Error> {
+        debug!("搜索次数上限已更新: {}", quota);
+        self.modify_preferences(|prefs| prefs.session_search_quota = quota)
+    }
+
+    // 更新复制前是否解析重定向
+    pub fn update_resolve_redirects(&self, enabled: bool) -> Result<(), io::Error> {
+        debug!("解析重定向设置已更新: {}", enabled);
+        self.modify_preferences(|prefs| prefs.resolve_redirects = enabled)
+    }
+
+    // 更新是否合并缓存的搜索结果
+    pub fn update_merge_cached_results(&self, enabled: bool) -> Result<(), io::Error> {
+        debug!("合并缓存结果设置已更新: {}", enabled);
+        self.modify_preferences(|prefs| prefs.merge_cached_results = enabled)
+    }
+
+    // 更新启动后是否预热搜索缓存
+    pub fn update_warm_cache_on_startup(&self, enabled: bool) -> Result<(), io::Error> {
+        debug!("启动时预热搜索缓存设置已更新: {}", enabled);
+        self.modify_preferences(|prefs| prefs.warm_cache_on_startup = enabled)
+    }
+
+    // 获取每次搜索的结果数量
+    pub fn get_result_limit(&self) -> Result<usize, io::Error> {
+        let guard = self.read_preferences()?;
+        Ok(guard.result_limit.clamp(1, MAX_RESULT_LIMIT))
+    }
+
+    // 更新每次搜索的结果数量，超出范围的值会被限制在1到MAX_RESULT_LIMIT之间
+    pub fn update_result_limit(&self, limit: usize) -> Result<usize, io::Error> {
+        let limit = limit.clamp(1, MAX_RESULT_LIMIT);
+        self.modify_preferences(|prefs| prefs.result_limit = limit)?;
+        debug!("搜索结果数量已更新: {}", limit);
+        Ok(limit)
+    }
+
+    // 更新是否自动切换API
+    pub fn update_auto_failover(&self, enabled: bool) -> Result<(), io::Error> {
+        debug!("自动切换API设置已更新: {}", enabled);
+        self.modify_preferences(|prefs| prefs.auto_failover = enabled)
+    }
+
+    // 将活跃API切换到下一个，返回新的索引与API
+    //
+    // 只有一个API，或搜索固定使用单独配置的预览源时不切换，返回None。
+    pub fn fail_over_active_api(&self) -> Result<Option<(usize, ApiUrl)>, io::Error> {
+        let mut guard = self.write_preferences()?;
+        let len = guard.api_urls.urls.len();
+        if len < 2 || guard.api_urls.preview_index.is_some() {
+            return Ok(None);
+        }
+
+        let next = (guard.api_urls.active_index + 1) % len;
+        guard.api_urls.active_index = next;
+        let api = guard.api_urls.urls[next].clone();
+        self.save_preferences_locked(&guard.clone())?;
+        Ok(Some((next, api)))
+    }
+
+    // 更新搜索结果缓存有效期
+    pub fn update_search_cache_ttl(&self, secs: u64) -> Result<(), io::Error> {
+        debug!("搜索缓存有效期已更新: {}秒", secs);
+        self.modify_preferences(|prefs| prefs.search_cache_ttl_secs = secs)
+    }
+
+    // 更新社区清单自动刷新间隔
+    pub fn update_manifest_auto_refresh(&self, secs: u64) -> Result<(), io::Error> {
+        if secs != 0 && secs < 600 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "自动刷新间隔不能小于600秒（设为0可禁用）",
+            ));
+        }
+        self.modify_preferences(|prefs| prefs.manifest_auto_refresh_secs = secs)?;
+        debug!("清单自动刷新间隔已更新: {}秒", secs);
+        Ok(())
+    }
+
+    // 更新社区清单缓存有效期，0 表示每次都重新下载
+    pub fn update_manifest_cache_ttl(&self, secs: u64) -> Result<(), io::Error> {
+        self.modify_preferences(|prefs| prefs.manifest_cache_ttl_secs = secs)?;
+        debug!("清单缓存有效期已更新: {}秒", secs);
+        Ok(())
+    }
+
+    // 更新占位图设置
+    pub fn update_placeholder_image(&self, source: Option<String>) -> Result<(), io::Error> {
+        debug!("占位图设置已更新: {:?}", source);
+        self.modify_preferences(|prefs| prefs.placeholder_image = source)
+    }
+
+    // 更新并发请求上限
+    pub fn update_concurrency_limit(&self, limit: usize) -> Result<(), io::Error> {
+        debug!("并发请求上限已更新: {}", limit);
+        self.modify_preferences(|prefs| prefs.concurrency_limit = limit)
+    }
+
+    // 更新单实例设置
+    pub fn update_single_instance(&self, enabled: bool) -> Result<(), io::Error> {
+        debug!("单实例设置已更新: {}", enabled);
+        self.modify_preferences(|prefs| prefs.single_instance = enabled)
     }
 
     // 更新快捷键设置
     pub fn update_shortcuts(&self, shortcuts: ShortcutConfigs) -> Result<(), io::Error> {
-        match self.preferences.lock() {
-            Ok(mut guard) => {
-                guard.shortcuts = shortcuts;
-                self.save_preferences_locked(&guard.clone())
-            }
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+        let mut guard = self.write_preferences()?;
+        guard.shortcuts = shortcuts;
+        self.save_preferences_locked(&guard.clone())
     }
 
     // 获取快捷键配置
     pub fn get_shortcuts(&self) -> Result<ShortcutConfigs, io::Error> {
-        match self.preferences.lock() {
-            Ok(guard) => Ok(guard.shortcuts.clone()),
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+        let guard = self.read_preferences()?;
+        Ok(guard.shortcuts.clone())
     }
 
     // 获取应用切换快捷键
     pub fn get_toggle_app_shortcut(&self) -> Result<(Modifiers, Code), io::Error> {
-        match self.preferences.lock() {
-            Ok(guard) => Ok(guard.shortcuts.toggle_app.to_tauri_shortcut()),
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+        let guard = self.read_preferences()?;
+        guard
+            .shortcuts
+            .toggle_app
+            .to_tauri_shortcut()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
     }
 
     // 获取当前活跃的API URL
     pub fn get_active_api_url(&self) -> Result<String, io::Error> {
-        match self.preferences.try_lock() {
-            Ok(guard) => {
-                let config = &guard.api_urls;
-                if config.urls.is_empty() {
-                    return Ok("https://mememeow.morami.icu".to_string());
-                }
-                
-                let index = if config.active_index < config.urls.len() {
-                    config.active_index
-                } else {
-                    0
-                };
-                
-                Ok(config.urls[index].url.clone())
-            },
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
+        let guard = self.read_preferences()?;
+        Ok(guard.api_urls.active_url())
+    }
+
+    // 获取用于搜索的预览源URL
+    pub fn get_preview_api_url(&self) -> Result<String, io::Error> {
+        let guard = self.read_preferences()?;
+        Ok(guard.api_urls.preview_url())
+    }
+
+    // 获取用于搜索的预览源（包含请求格式等设置）
+    pub fn get_preview_api(&self) -> Result<ApiUrl, io::Error> {
+        let guard = self.read_preferences()?;
+        Ok(guard.api_urls.preview_api())
+    }
+
+    // 设置指定API源的搜索请求方法
+    pub fn update_api_search_method(
+        &self,
+        index: usize,
+        method: SearchMethod,
+    ) -> Result<(), io::Error> {
+        let mut guard = self.write_preferences()?;
+        let api = guard.api_urls.urls.get_mut(index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "无效的API URL索引")
+        })?;
+        api.search_method = method;
+        debug!("API源 {} 的搜索方法已更新: {:?}", index, method);
+        self.save_preferences_locked(&guard.clone())
+    }
+
+    // 设置指定API源响应中结果数组的路径，None 表示自动识别
+    pub fn update_api_results_key(
+        &self,
+        index: usize,
+        results_key: Option<String>,
+    ) -> Result<(), io::Error> {
+        let mut guard = self.write_preferences()?;
+        let api = guard.api_urls.urls.get_mut(index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "无效的API URL索引")
+        })?;
+        api.results_key = results_key.filter(|key| !key.trim().is_empty());
+        debug!("API源 {} 的结果路径已更新: {:?}", index, api.results_key);
+        self.save_preferences_locked(&guard.clone())
+    }
+
+    // 设置指定API源的搜索请求头，传入空表时清除；请求头的值不会写入日志
+    pub fn update_api_headers(
+        &self,
+        index: usize,
+        headers: HashMap<String, String>,
+    ) -> Result<(), io::Error> {
+        let headers: HashMap<String, String> = headers
+            .into_iter()
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        for (name, value) in &headers {
+            HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("无效的请求头名称: {}", name))
+            })?;
+            HeaderValue::from_str(value).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("请求头 {} 的值无效", name))
+            })?;
         }
+
+        let mut guard = self.write_preferences()?;
+        let api = guard.api_urls.urls.get_mut(index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "无效的API URL索引")
+        })?;
+        api.headers = headers;
+        let mut names: Vec<&str> = api.headers.keys().map(String::as_str).collect();
+        names.sort();
+        debug!("API源 {} 的请求头已更新: {:?}", index, names);
+        self.save_preferences_locked(&guard.clone())
+    }
+
+    // 设置指定API源的搜索请求格式
+    pub fn update_api_request_format(
+        &self,
+        index: usize,
+        format: RequestFormat,
+    ) -> Result<(), io::Error> {
+        let mut guard = self.write_preferences()?;
+        let api = guard.api_urls.urls.get_mut(index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "无效的API URL索引")
+        })?;
+        api.request_format = format;
+        debug!("API源 {} 的请求格式已更新: {:?}", index, format);
+        self.save_preferences_locked(&guard.clone())
+    }
+
+    // 将搜索结果中的预览图片URL解析为全尺寸源上的URL
+    pub fn resolve_full_image_url(&self, image_url: &str) -> Result<String, io::Error> {
+        let guard = self.read_preferences()?;
+        Ok(guard.api_urls.resolve_full_url(image_url))
+    }
+
+    // 设置预览源与全尺寸源
+    pub fn set_tiered_api_sources(
+        &self,
+        preview_index: Option<usize>,
+        full_index: Option<usize>,
+    ) -> Result<(), io::Error> {
+        let mut guard = self.write_preferences()?;
+
+        let len = guard.api_urls.urls.len();
+        if preview_index.is_some_and(|i| i >= len) || full_index.is_some_and(|i| i >= len) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "API URL索引超出范围"));
+        }
+
+        guard.api_urls.preview_index = preview_index;
+        guard.api_urls.full_index = full_index;
+        debug!("预览源: {:?}，全尺寸源: {:?}", preview_index, full_index);
+        self.save_preferences_locked(&guard.clone())
     }
 
     // 获取API URL配置
     pub fn get_api_url_config(&self) -> Result<ApiUrlConfig, io::Error> {
-        match self.preferences.try_lock() {
-            Ok(guard) => Ok(guard.api_urls.clone()),
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+        let guard = self.read_preferences()?;
+        Ok(guard.api_urls.clone())
     }
 
     // 更新API URL配置
-    pub fn update_api_url_config(&self, config: ApiUrlConfig) -> Result<(), io::Error> {
-        match self.preferences.try_lock() {
-            Ok(mut guard) => {
-                guard.api_urls = config;
-                debug!("API URL配置已更新");
-                self.save_preferences_locked(&guard.clone())
-            },
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+    pub fn update_api_url_config(&self, mut config: ApiUrlConfig) -> Result<(), io::Error> {
+        config.normalize();
+        let mut guard = self.write_preferences()?;
+        guard.api_urls = config;
+        debug!("API URL配置已更新");
+        self.save_preferences_locked(&guard.clone())
     }
 
     // 设置活跃的API URL
     pub fn set_active_api_url(&self, index: usize) -> Result<(), io::Error> {
-        match self.preferences.try_lock() {
-            Ok(mut guard) => {
-                if index < guard.api_urls.urls.len() {
-                    guard.api_urls.active_index = index;
-                    debug!("活跃API URL已更新为索引 {}", index);
-                    self.save_preferences_locked(&guard.clone())
-                } else {
-                    Err(io::Error::new(io::ErrorKind::InvalidInput, "API URL索引超出范围"))
-                }
-            },
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
+        let mut guard = self.write_preferences()?;
+        if index < guard.api_urls.urls.len() {
+            guard.api_urls.active_index = index;
+            debug!("活跃API URL已更新为索引 {}", index);
+            self.save_preferences_locked(&guard.clone())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "API URL索引超出范围"))
         }
     }
 
     // 添加API URL
     pub fn add_api_url(&self, name: String, url: String) -> Result<(), io::Error> {
-        match self.preferences.try_lock() {
-            Ok(mut guard) => {
-                guard.api_urls.urls.push(ApiUrl { name, url });
-                debug!("已添加新的API URL");
-                self.save_preferences_locked(&guard.clone())
-            }
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
-            }
-        }
+        let mut guard = self.write_preferences()?;
+        guard.api_urls.urls.push(ApiUrl {
+            name,
+            url,
+            request_format: RequestFormat::default(),
+            search_method: SearchMethod::default(),
+            results_key: None,
+            headers: Default::default(),
+        });
+        debug!("已添加新的API URL");
+        self.save_preferences_locked(&guard.clone())
     }
 
     // 删除API URL
     pub fn remove_api_url(&self, index: usize) -> Result<(), io::Error> {
-        match self.preferences.try_lock() {
-            Ok(mut guard) => {
-                if index < guard.api_urls.urls.len() {
-                    guard.api_urls.urls.remove(index);
-                    
-                    // 如果删除的是当前活跃的API，则将活跃索引重置为0
-                    if guard.api_urls.active_index >= guard.api_urls.urls.len() {
-                        guard.api_urls.active_index = 0;
-                    }
-                    
-                    debug!("已删除API URL，索引: {}", index);
-                    self.save_preferences_locked(&guard.clone())
-                } else {
-                    Err(io::Error::new(io::ErrorKind::InvalidInput, "API URL索引超出范围"))
-                }
-            },
-            Err(err) => {
-                error!("获取偏好锁失败: {}", err);
-                Err(io::Error::new(io::ErrorKind::Other, "获取偏好锁失败"))
+        let mut guard = self.write_preferences()?;
+        if index < guard.api_urls.urls.len() {
+            guard.api_urls.urls.remove(index);
+            guard.api_urls.shift_indices_after_removal(index);
+
+            // 如果删除的是当前活跃的API，则将活跃索引重置为0
+            if guard.api_urls.active_index >= guard.api_urls.urls.len() {
+                guard.api_urls.active_index = 0;
             }
+
+            debug!("已删除API URL，索引: {}", index);
+            self.save_preferences_locked(&guard.clone())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "API URL索引超出范围"))
         }
     }
 
     
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn creates_default_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preferences.json");
+        let manager = ConfigManager::open(path.clone());
+
+        assert!(path.is_file());
+        let saved = ConfigManager::load_preferences(&path).unwrap();
+        assert_eq!(saved.result_limit, manager.get_preferences().unwrap().result_limit);
+    }
+
+    #[test]
+    fn unknown_fields_survive_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preferences.json");
+        fs::write(
+            &path,
+            r#"{"result_limit": 5, "future_option": {"enabled": true}, "future_list": [1, 2]}"#,
+        )
+        .unwrap();
+
+        let manager = ConfigManager::open(path.clone());
+        manager.modify_preferences(|prefs| prefs.result_limit = 7).unwrap();
+
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["result_limit"], 7);
+        assert_eq!(saved["future_option"], serde_json::json!({"enabled": true}));
+        assert_eq!(saved["future_list"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn reset_backs_up_corrupt_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preferences.json");
+        fs::write(&path, "{not json").unwrap();
+
+        // 损坏的配置文件加载失败时使用默认配置，但文件本身保持不变
+        let manager = ConfigManager::open(path.clone());
+        let defaults = manager.reset_preferences().unwrap();
+
+        let backup = dir.path().join("preferences.json.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{not json");
+        let saved = ConfigManager::load_preferences(&path).unwrap();
+        assert_eq!(saved.result_limit, defaults.result_limit);
+    }
+
+    fn api_config(urls: &[&str], active_index: usize, preview_index: Option<usize>) -> ApiUrlConfig {
+        let urls: Vec<_> = urls.iter().map(|url| serde_json::json!({"name": url, "url": url})).collect();
+        serde_json::from_value(serde_json::json!({
+            "urls": urls,
+            "active_index": active_index,
+            "preview_index": preview_index,
+        }))
+        .unwrap()
+    }
+
+    fn config_urls(config: &ApiUrlConfig) -> Vec<&str> {
+        config.urls.iter().map(|api| api.url.as_str()).collect()
+    }
+
+    #[test]
+    fn normalize_clamps_out_of_range_index() {
+        let mut config = api_config(&["https://a", "https://b"], 5, Some(9));
+        assert!(config.normalize());
+        assert_eq!((config.active_index, config.preview_index), (0, None));
+        assert_eq!(config_urls(&config), ["https://a", "https://b"]);
+
+        // 已经一致的配置不做修改
+        assert!(!config.normalize());
+    }
+
+    #[test]
+    fn normalize_removes_duplicate_urls_and_remaps_indexes() {
+        // 第三项与第一项只差末尾的斜杠，活跃源指向被去除的那一项
+        let mut config = api_config(&["https://a", "https://b", "https://a/", "https://c"], 2, Some(3));
+        assert!(config.normalize());
+        assert_eq!(config_urls(&config), ["https://a", "https://b", "https://c"]);
+        assert_eq!(config.active_index, 0);
+        assert_eq!(config.preview_index, Some(2));
+    }
+
+    fn shortcut(input: &str) -> ShortcutConfig {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_owner_of_registered_shortcut() {
+        let shortcuts = ShortcutConfigs {
+            toggle_app: default_toggle_app_shortcut(),
+            copy_last: Some(shortcut("Ctrl+Shift+C")),
+            open_search: None,
+            quick_paste: vec![QuickPasteBinding {
+                favorite_id: "fav-1".to_string(),
+                shortcut: shortcut("Alt+1"),
+            }],
+        };
+
+        let (mods, code) = default_toggle_app_shortcut().to_tauri_shortcut().unwrap();
+        assert_eq!(shortcuts.owner_of(mods, code).as_deref(), Some("切换应用窗口"));
+
+        // 未设置动作名称时使用配置项名称，修饰键顺序不影响匹配
+        let (mods, code) = shortcut("Shift+Ctrl+C").to_tauri_shortcut().unwrap();
+        assert_eq!(shortcuts.owner_of(mods, code).as_deref(), Some("copy_last"));
+        let (mods, code) = shortcut("Alt+1").to_tauri_shortcut().unwrap();
+        assert_eq!(shortcuts.owner_of(mods, code).as_deref(), Some("quick_paste:fav-1"));
+
+        let (mods, code) = shortcut("Ctrl+Alt+M").to_tauri_shortcut().unwrap();
+        assert_eq!(shortcuts.owner_of(mods, code), None);
+    }
+
+    #[test]
+    fn shortcut_configs_default_when_missing() {
+        let shortcuts: ShortcutConfigs = serde_json::from_str("{}").unwrap();
+        assert_eq!(shortcuts.toggle_app.modifiers, ["ctrl", "alt"]);
+        assert_eq!(shortcuts.toggle_app.key, "n");
+        assert!(shortcuts.copy_last.is_none());
+        assert!(shortcuts.open_search.is_none());
+        assert!(shortcuts.quick_paste.is_empty());
+
+        // 未绑定的动作不写入配置文件
+        let json = serde_json::to_value(&shortcuts).unwrap();
+        assert!(json.get("copy_last").is_none() && json.get("open_search").is_none());
+    }
+
+    #[test]
+    fn shortcut_configs_round_trip_all_actions() {
+        let shortcuts = ShortcutConfigs {
+            toggle_app: shortcut("Ctrl+Alt+M"),
+            copy_last: Some(shortcut("Ctrl+Shift+C")),
+            open_search: Some(shortcut("Alt+Space")),
+            quick_paste: vec![QuickPasteBinding {
+                favorite_id: "fav-1".to_string(),
+                shortcut: shortcut("Alt+1"),
+            }],
+        };
+
+        let json = serde_json::to_string(&shortcuts).unwrap();
+        let restored: ShortcutConfigs = serde_json::from_str(&json).unwrap();
+        let keys = |s: &ShortcutConfigs| {
+            s.entries()
+                .into_iter()
+                .map(|(id, config)| (id, config.to_tauri_shortcut().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&restored), keys(&shortcuts));
+        assert_eq!(restored.entries().len(), 4);
+    }
+
+    #[test]
+    fn concurrent_readers_see_consistent_preferences() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preferences.json");
+        let manager = Arc::new(ConfigManager::open(path.clone()));
+        manager
+            .modify_preferences(|prefs| {
+                prefs.result_limit = 1;
+                prefs.log_level = "1".to_string();
+            })
+            .unwrap();
+
+        let readers: Vec<_> = (0..16)
+            .map(|_| {
+                let manager = Arc::clone(&manager);
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        let prefs = manager.get_preferences().unwrap();
+                        // 两个字段在同一次修改中写入，读取时不应看到只改了一半的配置
+                        assert_eq!(prefs.log_level, prefs.result_limit.to_string());
+                    }
+                })
+            })
+            .collect();
+        let writer = {
+            let manager = Arc::clone(&manager);
+            thread::spawn(move || {
+                for i in 2..=50 {
+                    manager
+                        .modify_preferences(|prefs| {
+                            prefs.result_limit = i;
+                            prefs.log_level = i.to_string();
+                        })
+                        .unwrap();
+                }
+            })
+        };
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        writer.join().unwrap();
+
+        let saved = ConfigManager::load_preferences(&path).unwrap();
+        assert_eq!(saved.result_limit, 50);
+        assert_eq!(saved.log_level, "50");
+    }
+}
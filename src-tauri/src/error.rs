@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+
+use crate::meme_server::SearchError;
+
+/// 返回给前端的结构化错误
+///
+/// 序列化为 `{"kind": "network", "message": "..."}`，前端可按 `kind` 区分错误类别并本地化提示，
+/// `message` 保留原始的错误描述。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum MemeError {
+    /// 网络请求失败或服务器返回错误状态码
+    Network(String),
+    /// 配置文件读取、写入失败
+    Config(String),
+    /// 响应或文件内容无法解析
+    Parse(String),
+    /// 请求的资源不存在，如未缓存的清单或未知的表情包库
+    NotFound(String),
+    /// 操作当前不允许执行，如已达到搜索次数上限
+    Locked(String),
+    /// 参数无效
+    InvalidInput(String),
+    /// 操作已被取消
+    Cancelled(String),
+    /// 读写系统剪贴板失败
+    Clipboard(String),
+    /// 其他未分类的错误
+    Other(String),
+}
+
+impl MemeError {
+    /// 错误描述，不含类别
+    pub fn message(&self) -> &str {
+        match self {
+            MemeError::Network(message)
+            | MemeError::Config(message)
+            | MemeError::Parse(message)
+            | MemeError::NotFound(message)
+            | MemeError::Locked(message)
+            | MemeError::InvalidInput(message)
+            | MemeError::Cancelled(message)
+            | MemeError::Clipboard(message)
+            | MemeError::Other(message) => message,
+        }
+    }
+
+    /// 带上下文描述的IO错误，按错误类型归类，与 `From<io::Error>` 一致
+    pub fn io(context: impl fmt::Display, err: io::Error) -> Self {
+        Self::from_io_kind(err.kind(), format!("{}: {}", context, err))
+    }
+
+    fn from_io_kind(kind: io::ErrorKind, message: String) -> Self {
+        match kind {
+            io::ErrorKind::InvalidInput => MemeError::InvalidInput(message),
+            io::ErrorKind::NotFound => MemeError::NotFound(message),
+            io::ErrorKind::InvalidData => MemeError::Parse(message),
+            _ => MemeError::Config(message),
+        }
+    }
+}
+
+impl fmt::Display for MemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for MemeError {}
+
+impl From<SearchError> for MemeError {
+    fn from(err: SearchError) -> Self {
+        let message = err.to_string();
+        match err {
+            SearchError::InvalidUrl(..) => MemeError::Config(message),
+            SearchError::Request(_) | SearchError::Status(_) => MemeError::Network(message),
+            SearchError::Parse(_) => MemeError::Parse(message),
+            SearchError::QuotaExceeded(_) => MemeError::Locked(message),
+        }
+    }
+}
+
+/// 配置管理器以 `io::Error` 报告错误，校验失败时使用 `InvalidInput`
+impl From<io::Error> for MemeError {
+    fn from(err: io::Error) -> Self {
+        Self::from_io_kind(err.kind(), err.to_string())
+    }
+}
+
+/// 供仍返回 `String` 的调用方使用 `?`
+impl From<MemeError> for String {
+    fn from(err: MemeError) -> Self {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_with_stable_kind_tags() {
+        let cases = [
+            (MemeError::Network("m".into()), "network"),
+            (MemeError::Config("m".into()), "config"),
+            (MemeError::Parse("m".into()), "parse"),
+            (MemeError::NotFound("m".into()), "not_found"),
+            (MemeError::Locked("m".into()), "locked"),
+            (MemeError::InvalidInput("m".into()), "invalid_input"),
+            (MemeError::Cancelled("m".into()), "cancelled"),
+            (MemeError::Clipboard("m".into()), "clipboard"),
+            (MemeError::Other("m".into()), "other"),
+        ];
+        for (err, kind) in cases {
+            assert_eq!(
+                serde_json::to_value(&err).unwrap(),
+                serde_json::json!({ "kind": kind, "message": "m" })
+            );
+        }
+    }
+
+    #[test]
+    fn io_errors_keep_their_category() {
+        let err = MemeError::io("读取失败", io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert_eq!(err, MemeError::NotFound("读取失败: missing".into()));
+
+        let err: MemeError = io::Error::new(io::ErrorKind::InvalidInput, "bad").into();
+        assert_eq!(err, MemeError::InvalidInput("bad".into()));
+
+        let err: MemeError = io::Error::new(io::ErrorKind::PermissionDenied, "denied").into();
+        assert_eq!(err, MemeError::Config("denied".into()));
+    }
+}
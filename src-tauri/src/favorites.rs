@@ -0,0 +1,447 @@
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::meme_server::MemeItem;
+use crate::utils::misc::{app_dirs, write_atomic};
+
+// 单个收藏条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteEntry {
+    pub item: MemeItem,
+    // 收藏时间（Unix秒），用于稳定排序
+    #[serde(default)]
+    pub added_at: u64,
+    // 用户为收藏添加的标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // 用户为收藏写的备注
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+// 收藏列表
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    #[serde(default)]
+    pub entries: Vec<FavoriteEntry>,
+}
+
+// 标签匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatchMode {
+    // 需要包含全部标签
+    #[default]
+    All,
+    // 包含任一标签即可
+    Any,
+}
+
+impl FromStr for TagMatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "all" => Ok(TagMatchMode::All),
+            "any" => Ok(TagMatchMode::Any),
+            other => Err(format!("未知的标签匹配方式: {}", other)),
+        }
+    }
+}
+
+// 串行化收藏文件的读-改-写，避免并发修改互相覆盖
+static FAVORITES_LOCK: Mutex<()> = Mutex::new(());
+
+// 获取收藏文件路径
+pub(crate) fn get_favorites_path() -> Result<PathBuf, String> {
+    let config_dir = app_dirs().config_dir();
+    if !config_dir.exists() {
+        if let Err(e) = fs::create_dir_all(&config_dir) {
+            return Err(format!("创建配置目录失败: {}", e));
+        }
+    }
+
+    Ok(config_dir.join("favorites.json"))
+}
+
+// 加载收藏列表，文件不存在时返回空列表
+pub fn load_favorites() -> Result<Favorites, String> {
+    load_favorites_from(&get_favorites_path()?)
+}
+
+pub(crate) fn load_favorites_from(file_path: &Path) -> Result<Favorites, String> {
+    if !file_path.exists() {
+        return Ok(Favorites::default());
+    }
+
+    let content = fs::read_to_string(file_path).map_err(|e| {
+        error!("读取收藏失败: {}", e);
+        format!("读取收藏失败: {}", e)
+    })?;
+
+    serde_json::from_str(&content).map_err(|e| {
+        error!("解析收藏失败: {}", e);
+        format!("解析收藏失败: {}", e)
+    })
+}
+
+// 保存收藏列表
+pub fn save_favorites(favorites: &Favorites) -> Result<(), String> {
+    save_favorites_to(&get_favorites_path()?, favorites)
+}
+
+pub(crate) fn save_favorites_to(file_path: &Path, favorites: &Favorites) -> Result<(), String> {
+    let json_content = serde_json::to_string_pretty(favorites).map_err(|e| {
+        error!("序列化收藏失败: {}", e);
+        format!("序列化收藏失败: {}", e)
+    })?;
+
+    write_atomic(file_path, json_content.as_bytes()).map_err(|e| {
+        error!("保存收藏失败: {}", e);
+        format!("保存收藏失败: {}", e)
+    })?;
+
+    debug!("成功保存收藏到: {:?}", file_path);
+    Ok(())
+}
+
+// 标签比较时忽略大小写和首尾空白
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+// 按标签筛选收藏，按收藏时间排序并按id去重
+pub fn filter_by_tags(entries: &[FavoriteEntry], tags: &[String], mode: TagMatchMode) -> Vec<MemeItem> {
+    let wanted: HashSet<String> = tags
+        .iter()
+        .map(|t| normalize_tag(t))
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let mut matched: Vec<&FavoriteEntry> = entries
+        .iter()
+        .filter(|entry| {
+            // 未指定标签时返回全部收藏
+            if wanted.is_empty() {
+                return true;
+            }
+            let entry_tags: HashSet<String> = entry.tags.iter().map(|t| normalize_tag(t)).collect();
+            match mode {
+                TagMatchMode::All => wanted.iter().all(|t| entry_tags.contains(t)),
+                TagMatchMode::Any => wanted.iter().any(|t| entry_tags.contains(t)),
+            }
+        })
+        .collect();
+    // 稳定排序，收藏时间相同时保持文件中的顺序
+    matched.sort_by_key(|entry| entry.added_at);
+
+    let mut seen = HashSet::new();
+    matched
+        .into_iter()
+        .filter(|entry| seen.insert(entry.item.id.clone()))
+        .map(|entry| entry.item.clone())
+        .collect()
+}
+
+// 收藏一个表情包，已收藏时不重复添加
+//
+// 收藏、取消收藏与查询都按id判断：前端持有的是收藏列表中的条目，id 在本机始终一致。
+// 导入则来自其他设备，搜索结果的id可能是随机生成的，因此导入改用 `favorite_key` 按图片地址去重。
+#[tauri::command]
+pub fn add_favorite(item: MemeItem) -> Result<(), String> {
+    add_favorite_in(&get_favorites_path()?, item)
+}
+
+fn add_favorite_in(file_path: &Path, item: MemeItem) -> Result<(), String> {
+    let _lock = FAVORITES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut favorites = load_favorites_from(file_path)?;
+    if favorites.entries.iter().any(|entry| entry.item.id == item.id) {
+        debug!("表情包已在收藏中: {}", item.id);
+        return Ok(());
+    }
+
+    let added_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    info!("已收藏表情包: {}", item.id);
+    favorites.entries.push(FavoriteEntry {
+        item,
+        added_at,
+        tags: Vec::new(),
+        note: None,
+    });
+    save_favorites_to(file_path, &favorites)
+}
+
+// 取消收藏，返回是否确实移除了收藏
+#[tauri::command]
+pub fn remove_favorite(id: String) -> Result<bool, String> {
+    remove_favorite_in(&get_favorites_path()?, &id)
+}
+
+fn remove_favorite_in(file_path: &Path, id: &str) -> Result<bool, String> {
+    let _lock = FAVORITES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut favorites = load_favorites_from(file_path)?;
+    let before = favorites.entries.len();
+    favorites.entries.retain(|entry| entry.item.id != id);
+    if favorites.entries.len() == before {
+        return Ok(false);
+    }
+
+    save_favorites_to(file_path, &favorites)?;
+    info!("已取消收藏: {}", id);
+    Ok(true)
+}
+
+// 列出全部收藏，按收藏时间排序
+#[tauri::command]
+pub fn list_favorites() -> Result<Vec<MemeItem>, String> {
+    let favorites = load_favorites()?;
+    Ok(filter_by_tags(&favorites.entries, &[], TagMatchMode::default()))
+}
+
+// 判断表情包是否已收藏
+#[tauri::command]
+pub fn is_favorite(id: String) -> Result<bool, String> {
+    let favorites = load_favorites()?;
+    Ok(favorites.entries.iter().any(|entry| entry.item.id == id))
+}
+
+// 在收藏中按标签搜索，完全在本地完成
+#[tauri::command]
+pub fn search_favorites(tags: Vec<String>, mode: Option<String>) -> Result<Vec<MemeItem>, String> {
+    let mode = match mode {
+        Some(mode) => mode.parse::<TagMatchMode>()?,
+        None => TagMatchMode::default(),
+    };
+    let favorites = load_favorites()?;
+    let items = filter_by_tags(&favorites.entries, &tags, mode);
+    debug!("收藏标签搜索 {:?}（{:?}）命中 {} 条", tags, mode, items.len());
+    Ok(items)
+}
+
+// 导入时本地与导入数据备注不一致的收藏
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteConflict {
+    pub id: String,
+    pub local_note: Option<String>,
+    pub imported_note: Option<String>,
+}
+
+// 导入收藏前的差异预览
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FavoritesDiff {
+    // 将新增的收藏
+    pub added: Vec<MemeItem>,
+    // 本地已存在的收藏
+    pub existing: Vec<MemeItem>,
+    // 备注冲突的收藏，应用时保留本地备注
+    pub conflicts: Vec<NoteConflict>,
+}
+
+// 读取待导入的收藏文件
+fn read_import_file(path: &str) -> Result<Favorites, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("读取文件失败: {} - {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("收藏文件格式错误: {}", e))
+}
+
+// 判断收藏是否为同一张图片的键
+//
+// 搜索结果的id可能是每次随机生成的，因此按图片地址比较：解析后主机名小写、
+// 省略默认端口并去掉片段，无法解析的地址只去掉首尾空白。
+fn favorite_key(item: &MemeItem) -> String {
+    let url = item.url.trim();
+    match tauri::Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+// 计算导入数据相对本地收藏的差异，导入数据内部按图片地址去重
+pub fn diff_favorites(local: &Favorites, imported: &Favorites) -> FavoritesDiff {
+    let mut diff = FavoritesDiff::default();
+    let mut seen = HashSet::new();
+
+    for entry in &imported.entries {
+        let key = favorite_key(&entry.item);
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+
+        match local.entries.iter().find(|local| favorite_key(&local.item) == key) {
+            Some(local_entry) => {
+                diff.existing.push(entry.item.clone());
+                if entry.note.is_some() && local_entry.note != entry.note {
+                    diff.conflicts.push(NoteConflict {
+                        id: local_entry.item.id.clone(),
+                        local_note: local_entry.note.clone(),
+                        imported_note: entry.note.clone(),
+                    });
+                }
+            }
+            None => diff.added.push(entry.item.clone()),
+        }
+    }
+    diff
+}
+
+// 预览导入收藏文件会产生的变化，不修改任何数据
+#[tauri::command]
+pub fn preview_favorites_import(path: String) -> Result<FavoritesDiff, String> {
+    let imported = read_import_file(&path)?;
+    let local = load_favorites()?;
+    Ok(diff_favorites(&local, &imported))
+}
+
+// 将收藏文件合并到本地收藏
+//
+// 新收藏直接加入；已存在的收藏合并标签，备注冲突时保留本地备注，
+// 本地没有备注时采用导入的备注。
+#[tauri::command]
+pub fn apply_favorites_import(path: String) -> Result<FavoritesDiff, String> {
+    let imported = read_import_file(&path)?;
+    let _lock = FAVORITES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut local = load_favorites()?;
+    let diff = diff_favorites(&local, &imported);
+
+    let mut seen = HashSet::new();
+    for entry in imported.entries {
+        let key = favorite_key(&entry.item);
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+
+        match local.entries.iter_mut().find(|local| favorite_key(&local.item) == key) {
+            Some(local_entry) => {
+                for tag in entry.tags {
+                    if !local_entry.tags.iter().any(|t| normalize_tag(t) == normalize_tag(&tag)) {
+                        local_entry.tags.push(tag);
+                    }
+                }
+                if local_entry.note.is_none() {
+                    local_entry.note = entry.note;
+                }
+            }
+            None => local.entries.push(entry),
+        }
+    }
+
+    save_favorites(&local)?;
+    info!(
+        "已导入收藏：新增 {} 个，已存在 {} 个，备注冲突 {} 个",
+        diff.added.len(),
+        diff.existing.len(),
+        diff.conflicts.len()
+    );
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, url: &str, note: Option<&str>) -> FavoriteEntry {
+        FavoriteEntry {
+            item: MemeItem {
+                id: id.to_string(),
+                url: url.to_string(),
+                description: None,
+                score: None,
+                source: None,
+                updated_at: None,
+                path: None,
+            },
+            added_at: 0,
+            tags: Vec::new(),
+            note: note.map(str::to_string),
+        }
+    }
+
+    fn ids(path: &Path) -> Vec<String> {
+        load_favorites_from(path)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|entry| entry.item.id)
+            .collect()
+    }
+
+    #[test]
+    fn add_and_remove_round_trip_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("favorites.json");
+        assert!(ids(&path).is_empty());
+
+        add_favorite_in(&path, entry("a", "https://x/a.png", None).item).unwrap();
+        add_favorite_in(&path, entry("b", "https://x/b.png", None).item).unwrap();
+        assert_eq!(ids(&path), ["a", "b"]);
+
+        assert!(remove_favorite_in(&path, "a").unwrap());
+        assert_eq!(ids(&path), ["b"]);
+        // 不存在的收藏不会被移除，文件保持不变
+        assert!(!remove_favorite_in(&path, "a").unwrap());
+        assert_eq!(ids(&path), ["b"]);
+    }
+
+    #[test]
+    fn adding_same_id_twice_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("favorites.json");
+
+        add_favorite_in(&path, entry("a", "https://x/a.png", None).item).unwrap();
+        let first = load_favorites_from(&path).unwrap().entries[0].added_at;
+        add_favorite_in(&path, entry("a", "https://x/other.png", None).item).unwrap();
+
+        let favorites = load_favorites_from(&path).unwrap();
+        assert_eq!(favorites.entries.len(), 1);
+        assert_eq!(favorites.entries[0].item.url, "https://x/a.png");
+        assert_eq!(favorites.entries[0].added_at, first);
+    }
+
+    #[test]
+    fn diff_matches_favorites_by_url_not_id() {
+        let local = Favorites {
+            entries: vec![entry("local-1", "https://img.example.com/cat.png", Some("本地"))],
+        };
+        let imported = Favorites {
+            entries: vec![
+                // 同一张图片，id 不同、主机名大小写和片段不同
+                entry("random-a", "https://IMG.example.com:443/cat.png#top", Some("导入")),
+                entry("random-b", " https://img.example.com/cat.png ", None),
+                entry("random-c", "https://img.example.com/dog.png", None),
+                entry("random-d", "https://img.example.com/dog.png", None),
+            ],
+        };
+
+        let diff = diff_favorites(&local, &imported);
+        assert_eq!(diff.existing.len(), 1);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, "random-c");
+        assert_eq!(diff.conflicts.len(), 1);
+        assert_eq!(diff.conflicts[0].id, "local-1");
+    }
+
+    #[test]
+    fn same_id_with_different_urls_is_not_deduplicated() {
+        let local = Favorites {
+            entries: vec![entry("fixed", "https://img.example.com/cat.png", None)],
+        };
+        let imported = Favorites {
+            entries: vec![entry("fixed", "https://img.example.com/dog.png", None)],
+        };
+
+        let diff = diff_favorites(&local, &imported);
+        assert_eq!((diff.added.len(), diff.existing.len()), (1, 0));
+    }
+}
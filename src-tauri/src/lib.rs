@@ -1,23 +1,34 @@
 use log::error;
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use log::{debug, info};
-use std::sync::OnceLock;
+use log::{debug, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use tauri::image::Image;
 use tauri::Emitter;
 use tauri::Manager;
 use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
 use tauri_plugin_clipboard_manager::ClipboardExt;
-use tauri_plugin_http::reqwest;
 
 // Import utils
 mod utils;
+// 导入结构化错误类型
+mod error;
+use error::MemeError;
+use utils::key_map::ShortcutConfig;
+use utils::cancel::{cancel_background, run_cancellable, OperationKind};
+use utils::image_cache::download_image_cached;
+use utils::network::{download_image, image_extension};
 // 导入表情包服务模块
 mod meme_server;
-use meme_server::{MemeItem, MemeServerClient, MemeServerConfig};
+use meme_server::{
+    filter_blocked, sort_memes, MemeItem, MemeServerClient, MemeServerConfig, ResolvedLib, SearchError,
+    SearchOptions, SearchQuota, SearchResult, SearchStatus, SortOrder,
+};
 
 // 导入配置管理器
 mod config_manager;
-use config_manager::{ConfigManager, ShortcutConfigs, UserPreferences};
+use config_manager::{ClipboardMode, ConfigManager, ShortcutConfigs, UserPreferences};
 
 // 导入系统托盘模块
 mod sys_tray;
@@ -26,20 +37,53 @@ use sys_tray::create_system_tray;
 // 导入社区表情包模块
 mod meme_community;
 
-// 创建一个全局静态HTTP客户端，确保只初始化一次
-static MEME_CLIENT: OnceLock<MemeServerClient> = OnceLock::new();
+// 导入应用状态备份模块
+mod state_bundle;
+
+// 导入收藏模块
+mod favorites;
+
+// 导入搜索历史模块
+mod search_history;
+
+// 导入表情库本地安装模块
+mod lib_install;
+
+// 创建一个全局静态HTTP客户端，配置变更后可通过 reload_meme_client 重建
+static MEME_CLIENT: RwLock<Option<Arc<MemeServerClient>>> = RwLock::new(None);
 // 创建一个全局静态配置管理器
 static CONFIG_MANAGER: OnceLock<ConfigManager> = OnceLock::new();
 
-fn get_meme_client() -> &'static MemeServerClient {
-    MEME_CLIENT.get_or_init(|| {
-        // 在实际应用中，可能需要从配置文件读取这些值
-        let config = MemeServerConfig {
-            api_url: "https://mememeow.morami.icu".to_string(),
-            timeout_seconds: 10,
-        };
-        MemeServerClient::new(Some(config))
-    })
+// 根据当前偏好设置构建表情包服务客户端
+fn build_meme_client() -> MemeServerClient {
+    let prefs = get_config_manager().get_preferences().unwrap_or_else(|err| {
+        error!("获取偏好设置失败: {}，将使用默认客户端配置", err);
+        UserPreferences::default()
+    });
+
+    let config = MemeServerConfig {
+        api_url: "https://mememeow.morami.icu".to_string(),
+        timeout_seconds: prefs.timeout_seconds,
+        proxy_url: prefs.proxy_url,
+        user_agent: prefs.user_agent,
+        test_mode: prefs.test_mode || meme_server::test_mode_from_env(),
+    };
+    MemeServerClient::new(Some(config))
+}
+
+fn get_meme_client() -> Arc<MemeServerClient> {
+    if let Some(client) = MEME_CLIENT
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+    {
+        return client.clone();
+    }
+
+    let mut guard = MEME_CLIENT.write().unwrap_or_else(|e| e.into_inner());
+    guard
+        .get_or_insert_with(|| Arc::new(build_meme_client()))
+        .clone()
 }
 
 fn get_config_manager() -> &'static ConfigManager {
@@ -54,312 +98,2806 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-// 表情包搜索Tauri命令
-#[tauri::command]
-async fn search_memes(keyword: String) -> Result<Vec<MemeItem>, String> {
+// 本次运行已发起的搜索次数，重启后清零
+static SESSION_SEARCH_COUNT: AtomicU64 = AtomicU64::new(0);
+
+// 占用一次搜索配额，超出上限时返回错误
+fn take_search_quota(quota: u64) -> Result<(), MemeError> {
+    SESSION_SEARCH_COUNT
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+            if quota == 0 || used < quota {
+                Some(used + 1)
+            } else {
+                None
+            }
+        })
+        .map(|_| ())
+        .map_err(|_| {
+            MemeError::Locked(format!(
+                "已达到本次运行的搜索次数上限（{}次），请重启应用或调整设置",
+                quota
+            ))
+        })
+}
+
+// 本次运行的搜索配额，只有实际发起网络请求的搜索才占用，命中缓存时不占用
+fn session_quota(quota: u64) -> SearchQuota {
+    SearchQuota::new(move || take_search_quota(quota).map_err(String::from))
+}
+
+// 用于合并的历史结果最多保留的关键词数量
+const PREVIOUS_RESULTS_LIMIT: usize = 100;
+static PREVIOUS_RESULTS: OnceLock<Mutex<HashMap<String, Vec<MemeItem>>>> = OnceLock::new();
+
+// 将本次结果与同一API、关键词和表情库组合上一次的结果合并，并记录本次结果
+fn merge_with_previous_results(
+    keyword: &str,
+    options: &SearchOptions,
+    fresh: Vec<MemeItem>,
+) -> (Vec<MemeItem>, usize) {
+    let key = format!(
+        "{}|{}|{}",
+        get_config_manager().get_preview_api_url().unwrap_or_default(),
+        keyword.trim().to_lowercase(),
+        meme_server::resolve_search_libs(keyword, options).join(",")
+    );
+
+    let mut previous = PREVIOUS_RESULTS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let (merged, fresh_count) = merge_and_remember(&mut previous, key, fresh);
+    if merged.len() > fresh_count {
+        debug!("合并了{}个之前的结果", merged.len() - fresh_count);
+    }
+    (merged, fresh_count)
+}
+
+// 合并 `key` 上一次的结果，只记录本次结果，避免合并结果随搜索次数不断累积
+fn merge_and_remember(
+    previous: &mut HashMap<String, Vec<MemeItem>>,
+    key: String,
+    fresh: Vec<MemeItem>,
+) -> (Vec<MemeItem>, usize) {
+    let cached = previous.get(&key).map(Vec::as_slice).unwrap_or(&[]);
+    let (merged, fresh_count) = meme_server::merge_cached_results(fresh, cached);
+
+    if !previous.contains_key(&key) && previous.len() >= PREVIOUS_RESULTS_LIMIT {
+        if let Some(evict) = previous.keys().next().cloned() {
+            previous.remove(&evict);
+        }
+    }
+    previous.insert(key, merged[..fresh_count].to_vec());
+    (merged, fresh_count)
+}
+
+// 执行一次表情包搜索，返回带元数据的结果
+//
+// 只有参数或配置无效时返回 Err；网络错误与取消体现在结果状态中。
+async fn perform_search(
+    app: &tauri::AppHandle,
+    keyword: &str,
+    sort_order: Option<String>,
+    options: SearchOptions,
+) -> Result<SearchResult, MemeError> {
     info!("收到表情包搜索请求，关键词: {}", keyword);
 
-    if keyword.trim().is_empty() {
-        return Ok(Vec::new()); // 空关键词返回空结果
+    let prefs = get_config_manager().get_preferences()?;
+
+    // 未指定排序方式时使用偏好设置中的默认值
+    let sort_order = match sort_order {
+        Some(order) => order.parse::<SortOrder>().map_err(MemeError::InvalidInput)?,
+        None => prefs.sort_order,
+    };
+
+    // 空关键词或短于最短长度的关键词返回空结果，不请求服务器
+    if keyword.trim().chars().count() < prefs.min_keyword_length.max(1) {
+        return Ok(SearchResult::with_status(SearchStatus::EmptyKeyword, None));
+    }
+
+    let options = SearchOptions {
+        quota: Some(session_quota(prefs.session_search_quota)),
+        ..options
+    };
+
+    // 调用表情包服务客户端执行搜索，登记为可取消操作
+    let client = get_meme_client();
+    let timeout = prefs.search_timeout_seconds.map(std::time::Duration::from_secs);
+    let (outcome, failover) = search_with_failover(
+        get_config_manager(),
+        &client,
+        keyword,
+        &options,
+        timeout,
+        prefs.auto_failover,
+    )
+    .await;
+    if let Some(failover) = failover {
+        let _ = app.emit("api-failover", failover);
     }
 
-    // 调用表情包服务客户端执行搜索
-    match get_meme_client().search_memes(&keyword).await {
-        Ok(memes) => {
-            debug!("成功获取{}个表情包", memes.len());
-            Ok(memes)
+    match outcome {
+        Some(Ok(response)) => {
+            let (mut memes, mut blocked_count) = filter_blocked(response.items, &prefs.blocklist);
+            debug!("成功获取{}个表情包，屏蔽{}个", memes.len(), blocked_count);
+            sort_memes(&mut memes, sort_order, keyword, &prefs.source_priority);
+            let mut result = if prefs.merge_cached_results {
+                let (merged, fresh_count) = merge_with_previous_results(keyword, &options, memes);
+                // 之前的结果可能命中之后才添加的屏蔽词；本次结果已过滤，前 fresh_count 项不受影响
+                let (merged, merged_blocked) = filter_blocked(merged, &prefs.blocklist);
+                blocked_count += merged_blocked;
+                SearchResult::merged(merged, fresh_count)
+            } else {
+                SearchResult::new(memes)
+            };
+            result.from_cache = response.from_cache;
+            result.api_url = Some(response.api_url);
+            result.blocked_count = blocked_count;
+            Ok(result)
         }
-        Err(err) => {
+        Some(Err(SearchError::QuotaExceeded(message))) => Err(MemeError::Locked(message)),
+        Some(Err(err)) => {
             debug!("获取表情包失败: {}", err);
-            Err(err.to_string())
+            Ok(SearchResult::failed(err.into()))
+        }
+        None => {
+            info!("表情包搜索已取消，关键词: {}", keyword);
+            Ok(SearchResult::with_status(
+                SearchStatus::Cancelled,
+                Some("搜索已取消".to_string()),
+            ))
         }
     }
 }
 
-// 获取用户偏好设置
-#[tauri::command]
-fn get_user_preferences() -> Result<UserPreferences, String> {
-    match get_config_manager().get_preferences() {
-        Ok(prefs) => Ok(prefs),
-        Err(err) => Err(err.to_string()),
+// 自动切换API时发送给前端的事件内容
+#[derive(Debug, Clone, serde::Serialize)]
+struct ApiFailover {
+    index: usize,
+    name: String,
+    url: String,
+}
+
+// 在预览源上搜索，连续失败达到阈值时切换到下一个API并重试一次
+//
+// 返回搜索结果（`None` 表示已取消）以及发生切换时切换到的API。
+async fn search_with_failover(
+    config: &ConfigManager,
+    client: &MemeServerClient,
+    keyword: &str,
+    options: &SearchOptions,
+    timeout: Option<std::time::Duration>,
+    auto_failover: bool,
+) -> (
+    Option<Result<meme_server::SearchResponse, meme_server::SearchError>>,
+    Option<ApiFailover>,
+) {
+    let search = || {
+        run_cancellable(
+            OperationKind::Search,
+            client.search_preview_traced(config, keyword, options, timeout),
+        )
+    };
+
+    let outcome = search().await;
+    let failover = match &outcome {
+        Some(Ok(_)) => {
+            client.reset_failures();
+            None
+        }
+        // 配额用完时没有发送请求，不算作API失败
+        Some(Err(SearchError::QuotaExceeded(_))) => None,
+        Some(Err(_)) => fail_over_if_needed(config, client, auto_failover),
+        None => None,
+    };
+    let Some(failover) = failover else {
+        return (outcome, None);
+    };
+
+    let outcome = search().await;
+    match &outcome {
+        Some(Ok(_)) => client.reset_failures(),
+        Some(Err(_)) => {
+            client.record_failure();
+        }
+        None => {}
     }
+    (outcome, Some(failover))
 }
 
-// 设置剪贴板复制选项
-#[tauri::command]
-fn set_copy_to_clipboard(enabled: bool) -> Result<(), String> {
-    debug!("设置剪贴板复制选项: {}", enabled);
-    match get_config_manager().update_clipboard_setting(enabled) {
-        Ok(_) => Ok(()),
-        Err(err) => Err(err.to_string()),
+// 记录一次搜索失败，连续失败达到阈值且允许自动切换时切换到下一个API
+//
+// 切换成功时返回切换到的API，由调用方发送 api-failover 事件。
+fn fail_over_if_needed(
+    config: &ConfigManager,
+    client: &MemeServerClient,
+    auto_failover: bool,
+) -> Option<ApiFailover> {
+    let failures = client.record_failure();
+    if !auto_failover || failures < meme_server::FAILOVER_THRESHOLD {
+        return None;
+    }
+
+    match invalidating_search_cache_in(config, client, || config.fail_over_active_api()) {
+        Ok(Some((index, api))) => {
+            client.reset_failures();
+            info!("活跃API连续失败 {} 次，已切换到: {} ({})", failures, api.name, api.url);
+            Some(ApiFailover {
+                index,
+                name: api.name,
+                url: api.url,
+            })
+        }
+        Ok(None) => None,
+        Err(e) => {
+            error!("自动切换API失败: {}", e);
+            None
+        }
     }
 }
 
-// 获取快捷键配置
+// 设置活跃API连续失败时是否自动切换
 #[tauri::command]
-fn get_shortcuts() -> Result<ShortcutConfigs, String> {
-    match get_config_manager().get_shortcuts() {
-        Ok(shortcuts) => Ok(shortcuts),
-        Err(err) => Err(err.to_string()),
-    }
+fn set_auto_failover(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_auto_failover(enabled)
+        .map_err(MemeError::from)
 }
 
-// 设置快捷键配置
+// 随机表情包最近返回记录的长度，用于避免短时间内重复
+const RANDOM_MEME_HISTORY: usize = 20;
+static RECENT_RANDOM_MEMES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+// 从已启用的表情库中随机返回一个表情包
+//
+// 优先从本地已安装的表情库中取样，本地没有时以随机标签搜索。
+// exclude_recent 默认开启，会跳过最近返回过的表情包。
 #[tauri::command]
-fn set_shortcuts(shortcuts: ShortcutConfigs) -> Result<(), String> {
-    debug!("设置快捷键配置: {:?}", shortcuts);
-    match get_config_manager().update_shortcuts(shortcuts.clone()) {
-        Ok(_) => {
-            info!("快捷键配置已更新");
-            Ok(())
+async fn random_meme(app: tauri::AppHandle, exclude_recent: Option<bool>) -> Result<MemeItem, MemeError> {
+    let enabled = meme_community::get_enabled_meme_libs()?;
+    if enabled.is_empty() {
+        return Err(MemeError::NotFound("没有启用的表情库".to_string()));
+    }
+
+    let local_enabled = enabled.clone();
+    let mut candidates = tokio::task::spawn_blocking(move || {
+        meme_community::list_local_memes(&meme_community::local_libs_dir(), &local_enabled)
+    })
+    .await
+    .map_err(|e| MemeError::Other(format!("读取本地表情库失败: {}", e)))?;
+
+    if candidates.is_empty() {
+        let tags = meme_community::enabled_lib_tags(&enabled);
+        if tags.is_empty() {
+            return Err(MemeError::NotFound(
+                "本地没有已安装的表情包，且已启用的表情库没有可用的标签".to_string(),
+            ));
         }
-        Err(err) => {
-            error!("更新快捷键配置失败: {}", err);
-            Err(err.to_string())
+        let tag = &tags[meme_server::random_index(tags.len())];
+        debug!("本地没有表情包，使用随机标签搜索: {}", tag);
+        let result = perform_search(&app, tag, Some("server".to_string()), SearchOptions::default()).await?;
+        if let Some(err) = result.error {
+            return Err(result.failure.unwrap_or(MemeError::Other(err)));
+        }
+        candidates = result.items;
+    }
+
+    let mut recent = RECENT_RANDOM_MEMES.lock().unwrap_or_else(|e| e.into_inner());
+    if exclude_recent.unwrap_or(true) {
+        let fresh: Vec<MemeItem> = candidates
+            .iter()
+            .filter(|item| !recent.contains(&item.id))
+            .cloned()
+            .collect();
+        // 全部都最近返回过时不再排除
+        if !fresh.is_empty() {
+            candidates = fresh;
         }
     }
+    if candidates.is_empty() {
+        return Err(MemeError::NotFound("没有找到可用的表情包".to_string()));
+    }
+
+    let meme = candidates.swap_remove(meme_server::random_index(candidates.len()));
+    if recent.len() >= RANDOM_MEME_HISTORY {
+        recent.pop_front();
+    }
+    recent.push_back(meme.id.clone());
+    Ok(meme)
 }
 
+// 记录用户发起的搜索，关键词过短未实际搜索时不记录
+async fn record_search_history(app: &tauri::AppHandle, keyword: &str, result: &SearchResult) {
+    if result.status == SearchStatus::EmptyKeyword {
+        return;
+    }
+    record_keyword(app, keyword).await;
+}
 
-// 剪贴板功能
+// 在阻塞线程池中写入搜索历史文件，然后更新托盘的最近搜索菜单
+async fn record_keyword(app: &tauri::AppHandle, keyword: &str) {
+    let keyword = keyword.to_string();
+    match tokio::task::spawn_blocking(move || search_history::record_search(&keyword)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!("记录搜索历史失败: {}", e),
+        Err(e) => error!("记录搜索历史的任务异常退出: {}", e),
+    }
+    sys_tray::rebuild_tray_menu(app);
+}
+
+// 执行一次搜索，记录搜索历史并发送 search-complete 事件，供各搜索命令共用
+//
+// 指定 `token` 时，若搜索期间前端已用更大的令牌发起新搜索，结果被丢弃并返回 Cancelled 状态，
+// 过期的结果既不记录历史也不发送事件。
+async fn run_search(
+    app: &tauri::AppHandle,
+    keyword: &str,
+    sort_order: Option<String>,
+    options: SearchOptions,
+    token: Option<u64>,
+) -> Result<SearchResult, MemeError> {
+    let started = std::time::Instant::now();
+    let result = perform_search(app, keyword, sort_order, options).await?;
+
+    if search_superseded(token) {
+        debug!("丢弃过期的搜索结果，关键词: {}", keyword);
+        return Ok(stale_search_result());
+    }
+    record_search_history(app, keyword, &result).await;
+    emit_search_complete(app, keyword, &result, started.elapsed());
+    Ok(result)
+}
+
+// 表情包搜索Tauri命令
 #[tauri::command]
-async fn copy_image_to_clipboard(image_url: String, window: tauri::Window) -> Result<(), String> {
-    info!("Copying image to clipboard: {}", image_url);
+async fn search_memes(
+    app: tauri::AppHandle,
+    keyword: String,
+    sort_order: Option<String>,
+    include_libs: Option<Vec<String>>,
+    exclude_libs: Option<Vec<String>>,
+    since: Option<u64>,
+) -> Result<Vec<MemeItem>, MemeError> {
+    // 仅对本次搜索生效的表情包库调整
+    let options = SearchOptions {
+        include_libs: include_libs.unwrap_or_default(),
+        exclude_libs: exclude_libs.unwrap_or_default(),
+        since,
+        ..Default::default()
+    };
 
-    // 检查功能是否启用
-    let copy_enabled = match get_config_manager().get_preferences() {
-        Ok(prefs) => prefs.copy_to_clipboard,
-        Err(err) => {
-            error!("获取偏好设置失败: {}", err);
-            return Err(format!("Failed to get preferences: {}", err));
+    let result = run_search(&app, &keyword, sort_order, options, None).await?;
+    match result.status {
+        SearchStatus::Error => Err(result
+            .failure
+            .unwrap_or_else(|| MemeError::Other(result.error.unwrap_or_default()))),
+        SearchStatus::Cancelled => Err(MemeError::Cancelled(result.error.unwrap_or_default())),
+        _ => Ok(result.items),
+    }
+}
+
+// 搜索完成时发送给前端的事件内容
+#[derive(Debug, Clone, serde::Serialize)]
+struct SearchComplete {
+    keyword: String,
+    status: SearchStatus,
+    count: usize,
+    elapsed_ms: u64,
+    from_cache: bool,
+    api_url: Option<String>,
+    // 被屏蔽词过滤掉的结果数量
+    blocked_count: usize,
+}
+
+impl SearchComplete {
+    fn new(keyword: &str, result: &SearchResult, elapsed: std::time::Duration) -> Self {
+        Self {
+            keyword: keyword.to_string(),
+            status: result.status,
+            count: result.items.len(),
+            elapsed_ms: elapsed.as_millis() as u64,
+            from_cache: result.from_cache,
+            api_url: result.api_url.clone(),
+            blocked_count: result.blocked_count,
         }
-    };
+    }
+}
 
-    if !copy_enabled {
-        info!("Clipboard copy is disabled in preferences");
-        return Ok(());
+// 发送 search-complete 事件，便于前端区分空关键词与无结果并展示耗时
+fn emit_search_complete(
+    app: &tauri::AppHandle,
+    keyword: &str,
+    result: &SearchResult,
+    elapsed: std::time::Duration,
+) {
+    let payload = SearchComplete::new(keyword, result, elapsed);
+    if let Err(e) = app.emit("search-complete", &payload) {
+        error!("发送搜索完成事件失败: {}", e);
     }
+}
 
-    // 下载图片数据
-    let response = reqwest::get(&image_url).await.map_err(|e| e.to_string())?;
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+// 表情包搜索，返回包含状态等元数据的结果
+#[tauri::command]
+async fn search_memes_detailed(
+    app: tauri::AppHandle,
+    keyword: String,
+    sort_order: Option<String>,
+    include_libs: Option<Vec<String>>,
+    exclude_libs: Option<Vec<String>>,
+    since: Option<u64>,
+) -> Result<SearchResult, MemeError> {
+    let options = SearchOptions {
+        include_libs: include_libs.unwrap_or_default(),
+        exclude_libs: exclude_libs.unwrap_or_default(),
+        since,
+        ..Default::default()
+    };
 
-    // 将图片数据编码为 base64
-    // let base64_image = base64::encode(&bytes);
+    run_search(&app, &keyword, sort_order, options, None).await
+}
 
-    let image = Image::from_bytes(&bytes).unwrap();
+// 前端最近一次发起的带令牌搜索
+static LATEST_SEARCH_TOKEN: AtomicU64 = AtomicU64::new(0);
 
-    // 获取剪贴板管理器
-    let clipboard = window.clipboard();
+// 带令牌的搜索是否已被更大令牌的搜索取代；未指定令牌的搜索不会过期
+fn search_superseded(token: Option<u64>) -> bool {
+    token.is_some_and(|token| LATEST_SEARCH_TOKEN.load(Ordering::SeqCst) != token)
+}
 
-    clipboard.write_image(&image).map_err(|e| e.to_string())?;
-    debug!("Image copied to clipboard successfully");
-    Ok(())
+fn stale_search_result() -> SearchResult {
+    SearchResult::with_status(SearchStatus::Cancelled, Some("搜索已被更新的请求取代".to_string()))
 }
 
-// 添加API URL配置的命令函数
+// 带令牌的表情包搜索，用于快速输入时丢弃过期的响应
+//
+// 前端每次搜索传入递增的令牌。响应返回时若已有更大的令牌发起过搜索，
+// 本次结果会被丢弃并返回 Cancelled 状态，避免旧结果覆盖新结果。
 #[tauri::command]
-fn get_api_url_config() -> Result<config_manager::ApiUrlConfig, String> {
-    get_config_manager().get_api_url_config().map_err(|e| e.to_string())
+async fn search_memes_with_token(
+    app: tauri::AppHandle,
+    keyword: String,
+    token: u64,
+    sort_order: Option<String>,
+    include_libs: Option<Vec<String>>,
+    exclude_libs: Option<Vec<String>>,
+    since: Option<u64>,
+) -> Result<SearchResult, MemeError> {
+    let latest = LATEST_SEARCH_TOKEN.fetch_max(token, Ordering::SeqCst);
+    if latest > token {
+        debug!("搜索令牌 {} 已过期（最新 {}），跳过搜索", token, latest);
+        return Ok(stale_search_result());
+    }
+
+    let options = SearchOptions {
+        include_libs: include_libs.unwrap_or_default(),
+        exclude_libs: exclude_libs.unwrap_or_default(),
+        since,
+        ..Default::default()
+    };
+    run_search(&app, &keyword, sort_order, options, Some(token)).await
 }
 
+// 预览某个关键词的搜索实际会使用的表情包库，不执行搜索
 #[tauri::command]
-fn update_api_url_config(config: config_manager::ApiUrlConfig) -> Result<(), String> {
-    get_config_manager().update_api_url_config(config).map_err(|e| e.to_string())
+fn resolve_search_libs(
+    keyword: String,
+    include_libs: Option<Vec<String>>,
+    exclude_libs: Option<Vec<String>>,
+) -> Vec<ResolvedLib> {
+    let manifest = meme_community::load_manifest_from_cache().ok();
+    let options = SearchOptions {
+        include_libs: include_libs.unwrap_or_default(),
+        exclude_libs: exclude_libs.unwrap_or_default(),
+        ..Default::default()
+    };
+
+    meme_server::resolve_search_libs(&keyword, &options)
+        .into_iter()
+        .map(|uuid| {
+            let name = manifest
+                .as_ref()
+                .and_then(|m| {
+                    m.meme_libs
+                        .get(&uuid)
+                        .or_else(|| m.meme_libs.values().find(|lib| lib.uuid == uuid))
+                })
+                .map(|lib| lib.name.clone());
+            ResolvedLib { uuid, name }
+        })
+        .collect()
 }
 
+// 设置搜索结果的默认排序方式与表情包库优先级
 #[tauri::command]
-fn add_api_url(name: String, url: String) -> Result<(), String> {
-    get_config_manager().add_api_url(name, url).map_err(|e| e.to_string())
+fn set_sort_order(sort_order: String, source_priority: Option<Vec<String>>) -> Result<(), MemeError> {
+    let sort_order = sort_order.parse::<SortOrder>().map_err(MemeError::InvalidInput)?;
+    let source_priority = match source_priority {
+        Some(priority) => priority,
+        None => get_config_manager()
+            .get_preferences()?
+            .source_priority,
+    };
+    get_config_manager()
+        .update_sort_settings(sort_order, source_priority)
+        .map_err(MemeError::from)
 }
 
+// 设置备用URL下载的尝试方式：sequential 按顺序尝试，race 同时请求所有URL
 #[tauri::command]
-fn remove_api_url(index: usize) -> Result<(), String> {
-    get_config_manager().remove_api_url(index).map_err(|e| e.to_string())
+fn set_download_mode(mode: utils::network::DownloadMode) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_download_mode(mode)
+        .map_err(MemeError::from)
 }
 
+// 设置备用URL下载的超时增长策略
 #[tauri::command]
-fn set_active_api_url(index: usize) -> Result<(), String> {
-    get_config_manager().set_active_api_url(index).map_err(|e| e.to_string())
+fn set_download_timeout_policy(policy: utils::network::TimeoutPolicy) -> Result<Vec<u64>, MemeError> {
+    policy.validate().map_err(MemeError::InvalidInput)?;
+    get_config_manager().update_download_timeout(policy)?;
+    // 返回实际会尝试的超时序列，便于前端展示
+    Ok(policy.timeouts())
 }
 
-// 修改 run 函数以使用配置的快捷键并添加系统托盘
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_window_state::Builder::new().build())
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_clipboard_manager::init())
-        .plugin({
-            #[cfg(desktop)]
-            {
-                use tauri_plugin_global_shortcut::{
-                    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState,
-                };
-
-                tauri_plugin_global_shortcut::Builder::new()
-                    .with_handler(move |app, shortcut, event| {
-                        if event.state == ShortcutState::Pressed {
-                            // 获取当前配置的切换应用快捷键
-                            if let Some(config_manager) = CONFIG_MANAGER.get() {
-                                match config_manager.get_toggle_app_shortcut() {
-                                    Ok((mods, code)) => {
-                                        let config_shortcut = Shortcut::new(Some(mods), code);
+// 设置所有网络请求使用的代理，不传则直连
+#[tauri::command]
+fn set_proxy(url: Option<String>) -> Result<(), MemeError> {
+    get_config_manager().update_proxy_url(url)?;
+    // 重建搜索客户端，使新代理立即生效
+    reload_meme_client()
+}
 
-                                        // 检查是否匹配配置的快捷键
-                                        if shortcut == &config_shortcut {
-                                            info!("触发切换应用快捷键: {:?}", shortcut);
-                                            let window = app.get_webview_window("main");
-                                            if let Some(window) = window {
-                                                match window.is_visible() {
-                                                    Ok(is_visible) => {
-                                                        if is_visible {
-                                                            if let Err(e) = window.hide() {
-                                                                error!("无法隐藏窗口: {}", e);
-                                                            }
-                                                        } else {
-                                                            if let Err(e) = window.show() {
-                                                                error!("无法显示窗口: {}", e);
-                                                            } else if let Err(e) =
-                                                                window.set_focus()
-                                                            {
-                                                                error!("无法设置窗口焦点: {}", e);
-                                                            }
-                                                        }
-                                                    }
-                                                    Err(e) => {
-                                                        error!("无法获取窗口可见状态: {}", e);
-                                                    }
-                                                }
-                                            } else {
-                                                error!("无法获取主窗口引用");
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("无法获取切换应用快捷键配置: {}", e);
-                                    }
-                                }
-                            } else {
-                                error!("配置管理器未初始化");
-                            }
-                        }
-                    })
-                    .build()
-            }
-            #[cfg(not(desktop))]
-            {
-                // 非桌面平台的无操作插件
-                tauri_plugin_global_shortcut::Builder::new().build()
-            }
-        })
-        .setup(|app| {
-            #[cfg(desktop)]
-            {
-                use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+// 设置社区清单下载超时（秒）
+#[tauri::command]
+fn set_manifest_timeout(secs: u64) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_manifest_timeout(secs)
+        .map_err(MemeError::from)
+}
 
-                // 初始化配置管理器
-                let config_manager = get_config_manager();
+// 添加屏蔽词，地址或描述中包含屏蔽词的搜索结果会被隐藏，返回更新后的屏蔽词列表
+#[tauri::command]
+fn add_blocked_term(term: String) -> Result<Vec<String>, MemeError> {
+    get_config_manager()
+        .add_blocked_term(&term)
+        .map_err(MemeError::from)
+}
 
-                // 注册快捷键
-                register_app_shortcuts(app, config_manager);
+// 移除屏蔽词（忽略大小写），返回更新后的屏蔽词列表
+#[tauri::command]
+fn remove_blocked_term(term: String) -> Result<Vec<String>, MemeError> {
+    get_config_manager()
+        .remove_blocked_term(&term)
+        .map_err(MemeError::from)
+}
 
-                // 初始化系统托盘
-                if let Err(e) = create_system_tray(app) {
-                    error!("创建系统托盘失败: {}", e);
-                } else {
-                    info!("系统托盘创建成功");
-                }
-            }
-            Ok(())
-        })
-        .plugin(tauri_plugin_autostart::init(
-            MacosLauncher::LaunchAgent,
-            Some(vec!["--flag1", "--flag2"]),
-        ))
-        .setup(|app| {
-            // 获取自动启动管理器
-            let autostart_manager = app.autolaunch();
-            // 启用 autostart
-            let _ = autostart_manager.enable();
-            // 检查 enable 状态
-            println!(
-                "registered for autostart? {}",
-                autostart_manager.is_enabled().unwrap()
-            );
-            // 禁用 autostart
-            let _ = autostart_manager.disable();
-            Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
-            greet,
-            search_memes,
-            get_user_preferences,
-            set_copy_to_clipboard,
-            copy_image_to_clipboard,
-            get_shortcuts,
-            set_shortcuts,
-            refresh_shortcuts,
-            // 添加API URL管理命令
-            get_api_url_config,
-            update_api_url_config,
-            add_api_url,
-            remove_api_url,
-            set_active_api_url,
-            meme_community::get_api_server_urls_config,
-            meme_community::fetch_community_manifest,
-            meme_community::refresh_community_manifest,
-            meme_community::get_enabled_meme_libs,
-            meme_community::enable_meme_lib,
-            meme_community::disable_meme_lib
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+// 设置单次搜索请求的超时（秒），不传则恢复为客户端默认超时
+#[tauri::command]
+fn set_search_timeout(secs: Option<u64>) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_search_timeout(secs)
+        .map_err(MemeError::from)
 }
 
-// 注册应用快捷键
-#[cfg(desktop)]
-fn register_app_shortcuts(app: &tauri::App, config_manager: &ConfigManager) {
-    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+// 设置日志级别（off、error、warn、info、debug、trace），立即生效，返回规范化后的级别名
+#[tauri::command]
+fn set_log_level(level: String) -> Result<String, MemeError> {
+    let level = get_config_manager().update_log_level(&level)?;
+    utils::logging::set_level(utils::logging::parse_level(&level).map_err(MemeError::InvalidInput)?);
+    Ok(level)
+}
 
-    match config_manager.get_toggle_app_shortcut() {
-        Ok((mods, code)) => {
-            let toggle_shortcut = Shortcut::new(Some(mods), code);
+// 在系统文件管理器中打开日志目录，便于用户提交日志
+#[tauri::command]
+fn open_log_dir(app: tauri::AppHandle) -> Result<String, MemeError> {
+    use tauri_plugin_opener::OpenerExt;
 
-            match app.global_shortcut().register(toggle_shortcut) {
-                Ok(_) => {
-                    info!("成功注册应用切换快捷键");
-                }
-                Err(e) => {
-                    // 注册失败，通知前端
-                    error!("注册快捷键失败: {}", e);
-
-                    // 发送事件通知前端注册失败
-                    let main_window = app.get_webview_window("main");
-                    if let Some(window) = main_window {
-                        let _ = window.emit("shortcut-registration-failed", 
-                            format!("无法注册快捷键 {}，可能与系统快捷键冲突。请在设置中配置其他快捷键。", 
-                                format_shortcut_for_display(&mods, &code)));
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            error!("获取快捷键配置失败: {}", e);
+    let dir = utils::logging::log_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| MemeError::Other(format!("创建日志目录失败: {:?} - {}", dir, e)))?;
+    let dir = dir.to_string_lossy().into_owned();
+    app.opener()
+        .open_path(dir.clone(), None::<&str>)
+        .map_err(|e| MemeError::Other(format!("打开日志目录失败: {}", e)))?;
+    Ok(dir)
+}
 
-            // 通知用户配置获取失败
-            let main_window = app.get_webview_window("main");
-            if let Some(window) = main_window {
-                let _ = window.emit(
-                    "shortcut-registration-failed",
-                    format!("无法获取快捷键配置: {}，请在设置中重新配置快捷键。", e),
-                );
-            }
+// 读取当前日志文件的最后若干行，用于应用内的诊断面板
+#[tauri::command]
+fn tail_logs(lines: usize) -> Vec<String> {
+    utils::logging::tail_lines(&utils::logging::log_file_path(), lines).unwrap_or_else(|e| {
+        error!("读取日志文件失败: {}", e);
+        Vec::new()
+    })
+}
+
+// 搜索次数配额的使用情况
+#[derive(Debug, serde::Serialize)]
+struct SearchQuotaStatus {
+    // 配额上限，0 表示不限制
+    quota: u64,
+    used: u64,
+    // 剩余次数，不限制时为None
+    remaining: Option<u64>,
+}
+
+// 获取本次运行的搜索配额使用情况
+#[tauri::command]
+fn get_search_quota_status() -> Result<SearchQuotaStatus, MemeError> {
+    let quota = get_config_manager()
+        .get_preferences()?
+        .session_search_quota;
+    let used = SESSION_SEARCH_COUNT.load(Ordering::SeqCst);
+    Ok(SearchQuotaStatus {
+        quota,
+        used,
+        remaining: (quota > 0).then(|| quota.saturating_sub(used)),
+    })
+}
+
+// 设置本次运行允许的搜索次数（0 表示不限制）
+#[tauri::command]
+fn set_session_search_quota(quota: u64) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_session_search_quota(quota)
+        .map_err(MemeError::from)
+}
+
+// 清空搜索结果缓存，返回清除的条目数
+#[tauri::command]
+fn clear_search_cache() -> usize {
+    let cleared = get_meme_client().clear_cache();
+    info!("已清空搜索缓存，共{}条", cleared);
+    cleared
+}
+
+// 搜索缓存中的命名空间（按API主机划分）
+#[tauri::command]
+fn get_cache_namespaces() -> Vec<String> {
+    get_meme_client().cache_namespaces()
+}
+
+// 清除指定API主机的搜索缓存，返回清除的条目数
+#[tauri::command]
+fn clear_cache_namespace(host: String) -> usize {
+    let cleared = get_meme_client().clear_cache_namespace(&host);
+    info!("已清除 {} 的搜索缓存，共{}条", host, cleared);
+    cleared
+}
+
+// 执行可能改变搜索所用API的操作，API变化后清除旧API的搜索缓存
+fn invalidating_search_cache<T>(change: impl FnOnce() -> T) -> T {
+    invalidating_search_cache_in(get_config_manager(), &get_meme_client(), change)
+}
+
+fn invalidating_search_cache_in<T>(
+    config: &ConfigManager,
+    client: &MemeServerClient,
+    change: impl FnOnce() -> T,
+) -> T {
+    let previous = config.get_preview_api_url().ok();
+    let result = change();
+    let current = config.get_preview_api_url().ok();
+    if let Some(previous) = previous.filter(|previous| current.as_ref() != Some(previous)) {
+        let cleared = client.clear_cache_namespace(&previous);
+        debug!("搜索API已变化，清除 {} 的搜索缓存{}条", previous, cleared);
+    }
+    result
+}
+
+// 设置搜索结果缓存有效期（秒），0 表示不缓存
+#[tauri::command]
+fn set_search_cache_ttl(secs: u64) -> Result<(), MemeError> {
+    get_config_manager().update_search_cache_ttl(secs)?;
+    if secs == 0 {
+        get_meme_client().clear_cache();
+    }
+    Ok(())
+}
+
+// 设置每次搜索的结果数量，返回实际生效的值（限制在1到100之间）
+#[tauri::command]
+fn set_result_limit(limit: usize) -> Result<usize, MemeError> {
+    get_config_manager()
+        .update_result_limit(limit)
+        .map_err(MemeError::from)
+}
+
+// 设置是否将搜索结果与之前的结果合并
+#[tauri::command]
+fn set_merge_cached_results(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_merge_cached_results(enabled)
+        .map_err(MemeError::from)
+}
+
+// 设置启动后是否用最近的搜索关键词预热搜索缓存
+#[tauri::command]
+fn set_warm_cache_on_startup(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_warm_cache_on_startup(enabled)
+        .map_err(MemeError::from)
+}
+
+// 启动预热时重新搜索的历史关键词数量
+const CACHE_WARMUP_KEYWORDS: usize = 5;
+// 启动后等待窗口加载完成再开始预热
+const CACHE_WARMUP_DELAY: std::time::Duration = std::time::Duration::from_secs(3);
+
+// 在后台用最近的搜索关键词填充搜索缓存，不阻塞启动
+//
+// 每次搜索占用一次搜索配额并遵守并发上限，配额用尽时停止；结果只写入缓存，不记录历史。
+fn spawn_cache_warmup() {
+    tauri::async_runtime::spawn(async move {
+        let prefs = match get_config_manager().get_preferences() {
+            Ok(prefs) if prefs.warm_cache_on_startup => prefs,
+            _ => return,
+        };
+        let keywords = match search_history::load_history() {
+            Ok(history) => history.top_keywords(CACHE_WARMUP_KEYWORDS, prefs.min_keyword_length),
+            Err(e) => {
+                error!("预热搜索缓存失败: {}", e);
+                return;
+            }
+        };
+        if keywords.is_empty() {
+            return;
+        }
+
+        tokio::time::sleep(CACHE_WARMUP_DELAY).await;
+        info!("开始预热搜索缓存，关键词数量: {}", keywords.len());
+        let client = get_meme_client();
+        let timeout = prefs.search_timeout_seconds.map(std::time::Duration::from_secs);
+        for keyword in keywords {
+            let options = SearchOptions {
+                quota: Some(session_quota(prefs.session_search_quota)),
+                ..Default::default()
+            };
+            let _permit = utils::concurrency::acquire().await;
+            match client.search_memes(&keyword, &options, timeout).await {
+                Ok(items) => debug!("已预热关键词 {}，{} 个结果", keyword, items.len()),
+                Err(e @ SearchError::QuotaExceeded(_)) => {
+                    info!("停止预热搜索缓存: {}", e);
+                    break;
+                }
+                Err(e) => error!("预热关键词 {} 失败: {}", keyword, e),
+            }
+        }
+    });
+}
+
+// 设置发起搜索所需的最短关键词长度
+#[tauri::command]
+fn set_min_keyword_length(length: usize) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_min_keyword_length(length)
+        .map_err(MemeError::from)
+}
+
+// 设置搜索历史保留天数（0 表示永久保留），并立即清理过期记录
+#[tauri::command]
+fn set_search_history_retention(days: u64) -> Result<usize, MemeError> {
+    get_config_manager().update_search_history_retention(days)?;
+    search_history::purge_expired().map_err(MemeError::Config)
+}
+
+// 开启或关闭搜索历史记录，关闭时不会删除已有记录
+#[tauri::command]
+fn set_record_history(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_record_history(enabled)
+        .map_err(MemeError::from)
+}
+
+// 设置搜索历史最多保留的条数
+#[tauri::command]
+fn set_search_history_limit(limit: usize) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_search_history_limit(limit)
+        .map_err(MemeError::from)
+}
+
+// 在运行时开启或关闭详细的请求追踪日志
+#[tauri::command]
+fn set_request_tracing(enabled: bool) {
+    utils::request_trace::set_enabled(enabled);
+}
+
+// 设置社区清单缓存有效期（秒），0 表示每次都重新下载
+#[tauri::command]
+fn set_manifest_cache_ttl(secs: u64) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_manifest_cache_ttl(secs)
+        .map_err(MemeError::from)
+}
+
+// 设置社区清单自动刷新间隔（秒），0 表示禁用
+#[tauri::command]
+fn set_manifest_auto_refresh(secs: u64) -> Result<(), MemeError> {
+    get_config_manager().update_manifest_auto_refresh(secs)?;
+    meme_community::notify_auto_refresh_changed();
+    Ok(())
+}
+
+// 获取近期需要提示用户的错误，按时间从新到旧排列
+#[tauri::command]
+fn get_recent_errors() -> Vec<utils::recent_errors::RecentError> {
+    utils::recent_errors::snapshot()
+}
+
+// 获取批量请求的并发上限
+#[tauri::command]
+fn get_concurrency_limit() -> usize {
+    utils::concurrency::limit()
+}
+
+// 设置批量请求的并发上限（1-32），立即对之后的操作生效
+#[tauri::command]
+fn set_concurrency_limit(n: usize) -> Result<(), MemeError> {
+    utils::concurrency::set_limit(n).map_err(MemeError::InvalidInput)?;
+    get_config_manager()
+        .update_concurrency_limit(n)
+        .map_err(MemeError::from)
+}
+
+// 将偏好设置恢复为默认值并重新应用，返回新的默认设置
+#[tauri::command]
+fn reset_preferences(app: tauri::AppHandle) -> Result<config_manager::UserPreferences, MemeError> {
+    let defaults = get_config_manager()
+        .reset_preferences()
+        .map_err(|e| MemeError::io("恢复默认设置失败", e))?;
+
+    // 让依赖偏好设置的运行时状态立即生效
+    utils::concurrency::set_limit(defaults.concurrency_limit).map_err(MemeError::InvalidInput)?;
+    reload_meme_client()?;
+    sys_tray::set_left_click_toggles(&app, defaults.tray_click_toggles);
+    meme_community::notify_auto_refresh_changed();
+    refresh_shortcuts(app)?;
+    Ok(defaults)
+}
+
+// 显示并聚焦主窗口
+fn show_main_window(app: &tauri::AppHandle) {
+    match app.get_webview_window("main") {
+        Some(window) => {
+            if let Err(e) = window.unminimize() {
+                error!("无法还原窗口: {}", e);
+            }
+            if let Err(e) = window.show() {
+                error!("无法显示窗口: {}", e);
+            } else if let Err(e) = window.set_focus() {
+                error!("无法设置窗口焦点: {}", e);
+            }
+        }
+        None => error!("无法获取主窗口引用"),
+    }
+}
+
+// 切换主窗口时对窗口执行的操作
+#[derive(Debug, PartialEq, Eq)]
+enum WindowToggle {
+    Show,
+    Hide,
+}
+
+// 根据主窗口当前是否可见决定切换操作
+fn window_toggle_for(visible: bool) -> WindowToggle {
+    if visible {
+        WindowToggle::Hide
+    } else {
+        WindowToggle::Show
+    }
+}
+
+// 切换主窗口的显示与隐藏，供切换应用快捷键与系统托盘使用
+fn toggle_main_window(app: &tauri::AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        error!("无法获取主窗口引用");
+        return;
+    };
+
+    match window.is_visible().map(window_toggle_for) {
+        Ok(WindowToggle::Hide) => {
+            if let Err(e) = window.hide() {
+                error!("无法隐藏窗口: {}", e);
+            } else {
+                on_main_window_hidden();
+            }
+        }
+        Ok(WindowToggle::Show) => {
+            if let Err(e) = window.show() {
+                error!("无法显示窗口: {}", e);
+            } else if let Err(e) = window.set_focus() {
+                error!("无法设置窗口焦点: {}", e);
+            }
+        }
+        Err(e) => error!("无法获取窗口可见状态: {}", e),
+    }
+}
+
+// 切换应用快捷键的防抖间隔，部分系统按一次会触发两次 Pressed 事件
+const TOGGLE_SHORTCUT_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(250);
+static LAST_SHORTCUT_TOGGLE: Mutex<Option<std::time::Instant>> = Mutex::new(None);
+
+// 距上一次切换不足防抖间隔时忽略本次切换
+fn is_debounced_toggle(
+    last: Option<std::time::Instant>,
+    now: std::time::Instant,
+    interval: std::time::Duration,
+) -> bool {
+    last.is_some_and(|last| now.saturating_duration_since(last) < interval)
+}
+
+// 记录一次快捷键切换，返回本次是否应执行；被忽略的触发不更新记录的时间
+fn accept_shortcut_toggle() -> bool {
+    let mut last = LAST_SHORTCUT_TOGGLE.lock().unwrap_or_else(|e| e.into_inner());
+    let now = std::time::Instant::now();
+    if is_debounced_toggle(*last, now, TOGGLE_SHORTCUT_DEBOUNCE) {
+        return false;
+    }
+    *last = Some(now);
+    true
+}
+
+// 设置是否只允许运行一个实例，下次启动生效
+#[tauri::command]
+fn set_single_instance(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_single_instance(enabled)
+        .map_err(MemeError::from)
+}
+
+// 将Tauri主题转换为前端使用的名称
+fn theme_name(theme: tauri::Theme) -> &'static str {
+    match theme {
+        tauri::Theme::Light => "light",
+        tauri::Theme::Dark => "dark",
+        _ => "unknown",
+    }
+}
+
+// 用户强制使用的主题，未设置时返回None
+fn theme_override() -> Option<String> {
+    get_config_manager()
+        .get_preferences()
+        .ok()
+        .and_then(|prefs| prefs.theme_override)
+}
+
+// 获取当前应使用的主题：设置了主题覆盖时返回覆盖值，否则返回系统主题
+#[tauri::command]
+fn get_system_theme(window: tauri::Window) -> String {
+    if let Some(theme) = theme_override() {
+        return theme;
+    }
+    match window.theme() {
+        Ok(theme) => theme_name(theme).to_string(),
+        Err(e) => {
+            debug!("获取系统主题失败: {}", e);
+            "unknown".to_string()
+        }
+    }
+}
+
+// 设置强制使用的主题（light、dark，None 表示跟随系统），返回生效的主题
+#[tauri::command]
+fn set_theme_override(app: tauri::AppHandle, theme: Option<String>) -> Result<String, MemeError> {
+    let theme = match theme.as_deref().map(|t| t.trim().to_lowercase()) {
+        None => None,
+        Some(t) if t.is_empty() => None,
+        Some(t) if t == "light" || t == "dark" => Some(t),
+        Some(t) => return Err(MemeError::InvalidInput(format!("无效的主题: {}", t))),
+    };
+    get_config_manager().update_theme_override(theme.clone())?;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| MemeError::Other("无法获取主窗口引用".to_string()))?;
+    let tauri_theme = theme.as_deref().map(|t| match t {
+        "dark" => tauri::Theme::Dark,
+        _ => tauri::Theme::Light,
+    });
+    if let Err(e) = window.set_theme(tauri_theme) {
+        error!("设置窗口主题失败: {}", e);
+    }
+
+    let effective = match theme {
+        Some(theme) => theme,
+        None => window
+            .theme()
+            .map(|t| theme_name(t).to_string())
+            .unwrap_or_else(|_| "unknown".to_string()),
+    };
+    let _ = app.emit("theme-changed", &effective);
+    Ok(effective)
+}
+
+// 取消所有进行中的后台操作（搜索等），不影响用户主动发起的复制
+#[tauri::command]
+fn cancel_all_operations() -> usize {
+    cancel_background()
+}
+
+// 设置窗口隐藏时是否取消进行中的后台操作
+#[tauri::command]
+fn set_pause_when_hidden(enabled: bool) -> Result<(), MemeError> {
+    debug!("设置窗口隐藏时暂停后台操作: {}", enabled);
+    get_config_manager()
+        .update_pause_when_hidden(enabled)
+        .map_err(MemeError::from)
+}
+
+// 设置左键单击托盘图标时是否切换主窗口
+#[tauri::command]
+fn set_tray_click_toggles(app: tauri::AppHandle, enabled: bool) -> Result<(), MemeError> {
+    get_config_manager().update_tray_click_toggles(enabled)?;
+    sys_tray::set_left_click_toggles(&app, enabled);
+    Ok(())
+}
+
+// 主窗口隐藏后，根据偏好设置取消进行中的后台操作
+fn on_main_window_hidden() {
+    let pause_when_hidden = get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.pause_when_hidden)
+        .unwrap_or(false);
+
+    if pause_when_hidden {
+        let cancelled = cancel_background();
+        debug!("窗口已隐藏，取消了 {} 个后台操作", cancelled);
+    }
+}
+
+// 重建表情包服务客户端，使超时、代理、User-Agent 等设置立即生效
+#[tauri::command]
+fn reload_meme_client() -> Result<(), MemeError> {
+    let client = Arc::new(build_meme_client());
+    *MEME_CLIENT.write().unwrap_or_else(|e| e.into_inner()) = Some(client);
+    info!("表情包服务客户端已根据最新配置重建");
+    Ok(())
+}
+
+// 获取用户偏好设置
+#[tauri::command]
+fn get_user_preferences() -> Result<UserPreferences, MemeError> {
+    match get_config_manager().get_preferences() {
+        Ok(prefs) => Ok(prefs),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// 运行时实际生效的配置
+#[derive(serde::Serialize)]
+struct EffectiveConfig {
+    active_api_url: String,
+    preview_api_url: String,
+    timeout_seconds: u64,
+    manifest_timeout_secs: u64,
+    proxy_url: Option<String>,
+    user_agent: Option<String>,
+    test_mode: bool,
+    enabled_lib_count: usize,
+    // 与默认值不同的偏好设置项
+    overridden_preferences: Vec<String>,
+    // 生效的环境变量覆盖
+    env_overrides: Vec<String>,
+}
+
+// 获取运行时实际生效的配置，用于诊断
+#[tauri::command]
+fn get_effective_config() -> Result<EffectiveConfig, MemeError> {
+    let config_manager = get_config_manager();
+    let prefs = config_manager
+        .get_preferences()?;
+
+    let env_test_mode = meme_server::test_mode_from_env();
+    let mut env_overrides = Vec::new();
+    if env_test_mode {
+        env_overrides.push("MEMEMEOW_TEST_MODE".to_string());
+    }
+
+    // 逐项比较当前偏好与默认值
+    let current = serde_json::to_value(&prefs).map_err(|e| MemeError::Parse(e.to_string()))?;
+    let defaults = serde_json::to_value(UserPreferences::default())
+        .map_err(|e| MemeError::Parse(e.to_string()))?;
+    let mut overridden_preferences: Vec<String> = match (current, defaults) {
+        (serde_json::Value::Object(current), serde_json::Value::Object(defaults)) => current
+            .into_iter()
+            .filter(|(key, value)| defaults.get(key) != Some(value))
+            .map(|(key, _)| key)
+            .collect(),
+        _ => Vec::new(),
+    };
+    overridden_preferences.sort();
+
+    let enabled_lib_count = meme_community::get_enabled_meme_libs()
+        .map(|libs| libs.len())
+        .unwrap_or(0);
+
+    Ok(EffectiveConfig {
+        active_api_url: config_manager
+            .get_active_api_url()?,
+        preview_api_url: config_manager
+            .get_preview_api_url()?,
+        timeout_seconds: prefs.timeout_seconds,
+        manifest_timeout_secs: prefs.manifest_timeout_secs,
+        proxy_url: prefs.proxy_url.as_deref().map(utils::misc::redact_url),
+        user_agent: prefs.user_agent,
+        test_mode: prefs.test_mode || env_test_mode,
+        enabled_lib_count,
+        overridden_preferences,
+        env_overrides,
+    })
+}
+
+// 设置剪贴板复制选项
+#[tauri::command]
+fn set_copy_to_clipboard(enabled: bool) -> Result<(), MemeError> {
+    debug!("设置剪贴板复制选项: {}", enabled);
+    match get_config_manager().update_clipboard_setting(enabled) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// 设置复制表情包时写入剪贴板的内容：image、url 或 both
+#[tauri::command]
+fn set_clipboard_mode(mode: ClipboardMode) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_clipboard_mode(mode)
+        .map_err(MemeError::from)
+}
+
+// 设置测试模式，开启后搜索返回固定测试数据
+#[tauri::command]
+fn set_test_mode(enabled: bool) -> Result<(), MemeError> {
+    debug!("设置测试模式: {}", enabled);
+    get_config_manager().update_test_mode(enabled)?;
+    reload_meme_client()
+}
+
+// 获取快捷键配置
+#[tauri::command]
+fn get_shortcuts() -> Result<ShortcutConfigs, MemeError> {
+    match get_config_manager().get_shortcuts() {
+        Ok(shortcuts) => Ok(shortcuts),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// 查看快捷键配置实际解析得到的按键，包括是否使用了默认按键
+#[tauri::command]
+fn debug_resolve_shortcut(config: ShortcutConfig) -> utils::key_map::ResolvedShortcut {
+    let resolved = config.resolve();
+    if resolved.unknown_key {
+        debug!("快捷键按键 {:?} 无法识别", config.key);
+    }
+    resolved
+}
+
+// 快捷键与其触发动作的对应关系
+#[derive(Debug, serde::Serialize)]
+struct ShortcutBinding {
+    // 配置项名称，例如 toggle_app
+    id: String,
+    action: String,
+    display: String,
+    // 当前是否已成功注册为全局快捷键
+    registered: bool,
+}
+
+// 获取所有已配置快捷键的触发动作及注册状态
+#[tauri::command]
+fn get_shortcut_bindings(app: tauri::AppHandle) -> Result<Vec<ShortcutBinding>, MemeError> {
+    let shortcuts = get_config_manager().get_shortcuts()?;
+
+    let bindings = shortcuts
+        .entries()
+        .into_iter()
+        .map(|(id, config)| {
+            let action = config.action_name(&id).to_string();
+
+            // 按键无法识别的快捷键不会被注册，显示错误信息
+            #[cfg(desktop)]
+            let (display, registered) = match config.to_tauri_shortcut() {
+                Ok((mods, code)) => {
+                    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+                    (
+                        format_shortcut_for_display(&mods, &code),
+                        app.global_shortcut()
+                            .is_registered(Shortcut::new(Some(mods), code)),
+                    )
+                }
+                Err(e) => (e, false),
+            };
+            #[cfg(not(desktop))]
+            let (display, registered) = {
+                let _ = &app;
+                match config.to_tauri_shortcut() {
+                    Ok((mods, code)) => (format!("{:?}+{:?}", mods, code), false),
+                    Err(e) => (e, false),
+                }
+            };
+
+            ShortcutBinding {
+                id,
+                action,
+                display,
+                registered,
+            }
+        })
+        .collect();
+
+    Ok(bindings)
+}
+
+// 解析用户输入的快捷键字符串，供设置界面实时校验
+#[tauri::command]
+fn parse_shortcut(input: String) -> Result<ShortcutConfig, String> {
+    input.parse::<ShortcutConfig>()
+}
+
+// 快捷键能否注册的预检结果
+#[derive(Debug, serde::Serialize)]
+struct ShortcutProbe {
+    registrable: bool,
+    // 不能注册时的原因
+    reason: Option<String>,
+}
+
+impl ShortcutProbe {
+    fn ok() -> Self {
+        Self {
+            registrable: true,
+            reason: None,
+        }
+    }
+
+    fn rejected(reason: String) -> Self {
+        Self {
+            registrable: false,
+            reason: Some(reason),
+        }
+    }
+}
+
+// 全局快捷键的注册接口，测试时可替换为内存中的实现
+#[cfg(desktop)]
+trait ShortcutRegistry {
+    fn is_registered(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> bool;
+    fn register(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> Result<(), String>;
+    fn unregister(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> Result<(), String>;
+    fn unregister_all(&self) -> Result<(), String>;
+}
+
+#[cfg(desktop)]
+impl<R: tauri::Runtime> ShortcutRegistry for tauri_plugin_global_shortcut::GlobalShortcut<R> {
+    fn is_registered(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> bool {
+        tauri_plugin_global_shortcut::GlobalShortcut::is_registered(self, shortcut)
+    }
+
+    fn register(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> Result<(), String> {
+        tauri_plugin_global_shortcut::GlobalShortcut::register(self, shortcut).map_err(|e| e.to_string())
+    }
+
+    fn unregister(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> Result<(), String> {
+        tauri_plugin_global_shortcut::GlobalShortcut::unregister(self, shortcut).map_err(|e| e.to_string())
+    }
+
+    fn unregister_all(&self) -> Result<(), String> {
+        tauri_plugin_global_shortcut::GlobalShortcut::unregister_all(self).map_err(|e| e.to_string())
+    }
+}
+
+// 在保存前检查快捷键能否注册，供设置界面在录入时实时提示冲突
+//
+// 通过临时注册再立即注销来检测是否被其他程序占用，不会改变已保存的配置。
+// 已被本应用其他快捷键使用时同样视为不可注册。
+#[tauri::command]
+fn probe_shortcut(app: tauri::AppHandle, config: ShortcutConfig) -> ShortcutProbe {
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+        let shortcuts = get_config_manager().get_shortcuts().ok();
+        probe_shortcut_in(app.global_shortcut(), shortcuts.as_ref(), &config)
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = app;
+        if let Err(e) = config.validate().and_then(|_| config.to_tauri_shortcut()) {
+            return ShortcutProbe::rejected(e);
+        }
+        ShortcutProbe::rejected("当前平台不支持全局快捷键".to_string())
+    }
+}
+
+// 在给定的注册表上预检快捷键，`shortcuts` 用于指出占用该快捷键的本应用动作
+#[cfg(desktop)]
+fn probe_shortcut_in(
+    registry: &impl ShortcutRegistry,
+    shortcuts: Option<&ShortcutConfigs>,
+    config: &ShortcutConfig,
+) -> ShortcutProbe {
+    use tauri_plugin_global_shortcut::Shortcut;
+
+    if let Err(e) = config.validate() {
+        return ShortcutProbe::rejected(e);
+    }
+    let (mods, code) = match config.to_tauri_shortcut() {
+        Ok(shortcut) => shortcut,
+        Err(e) => return ShortcutProbe::rejected(e),
+    };
+
+    let display = format_shortcut_for_display(&mods, &code);
+    let shortcut = Shortcut::new(Some(mods), code);
+    if registry.is_registered(shortcut) {
+        let owner = shortcuts.and_then(|shortcuts| shortcuts.owner_of(mods, code));
+        return ShortcutProbe::rejected(match owner {
+            Some(action) => format!("快捷键 {} 已被本应用的「{}」使用", display, action),
+            None => format!("快捷键 {} 已被本应用使用", display),
+        });
+    }
+
+    match registry.register(shortcut) {
+        Ok(()) => {
+            if let Err(e) = registry.unregister(shortcut) {
+                error!("注销预检快捷键失败: {} - {}", display, e);
+            }
+            ShortcutProbe::ok()
+        }
+        Err(e) => ShortcutProbe::rejected(format!(
+            "无法注册快捷键 {}，可能与系统或其他程序的快捷键冲突: {}",
+            display, e
+        )),
+    }
+}
+
+// 设置快捷键配置
+#[tauri::command]
+fn set_shortcuts(shortcuts: ShortcutConfigs) -> Result<(), MemeError> {
+    debug!("设置快捷键配置: {:?}", shortcuts);
+    // 拒绝保存无法注册的快捷键
+    shortcuts.validate().map_err(MemeError::InvalidInput)?;
+    // 拒绝保存相互冲突的快捷键，否则只有其中一个会生效
+    shortcuts.check_conflicts().map_err(MemeError::InvalidInput)?;
+    match get_config_manager().update_shortcuts(shortcuts.clone()) {
+        Ok(_) => {
+            info!("快捷键配置已更新");
+            Ok(())
+        }
+        Err(err) => {
+            error!("更新快捷键配置失败: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+
+// 剪贴板功能
+#[tauri::command]
+async fn copy_image_to_clipboard(image_url: String, window: tauri::Window) -> Result<(), MemeError> {
+    copy_meme_to_clipboard(window.app_handle(), image_url).await
+}
+
+// 按指定方式复制表情包，不传方式时使用偏好设置
+#[tauri::command]
+async fn copy_meme(
+    app: tauri::AppHandle,
+    image_url: String,
+    mode: Option<ClipboardMode>,
+) -> Result<(), MemeError> {
+    let mode = match mode {
+        Some(mode) => mode,
+        None => clipboard_mode(),
+    };
+    copy_meme_with_mode(&app, image_url, mode).await
+}
+
+// 隐藏窗口后等待焦点回到目标应用的时间
+const PASTE_FOCUS_DELAY_MS: u64 = 150;
+
+// 复制表情包并直接粘贴到之前获得焦点的应用
+//
+// 先写入剪贴板，再隐藏主窗口让焦点回到目标应用，最后模拟粘贴快捷键。
+// 未开启自动粘贴时只复制，与 copy_image_to_clipboard 相同。
+// macOS 上需要授予辅助功能权限，详见 utils::paste。
+#[tauri::command]
+async fn paste_meme(app: tauri::AppHandle, image_url: String) -> Result<(), MemeError> {
+    let synthetic_paste = get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.synthetic_paste)
+        .unwrap_or(false);
+
+    run_paste_steps(
+        copy_meme_to_clipboard(&app, image_url),
+        synthetic_paste,
+        || {
+            if let Some(window) = app.get_webview_window("main") {
+                if let Err(e) = window.hide() {
+                    error!("无法隐藏窗口: {}", e);
+                } else {
+                    on_main_window_hidden();
+                }
+            }
+        },
+        std::time::Duration::from_millis(PASTE_FOCUS_DELAY_MS),
+        send_paste_keystroke(),
+    )
+    .await
+}
+
+// 按顺序执行自动粘贴的各个步骤：写入剪贴板、隐藏主窗口、等待焦点切换、模拟粘贴
+//
+// 复制失败或未开启自动粘贴时不执行后续步骤。
+async fn run_paste_steps(
+    copy: impl std::future::Future<Output = Result<(), MemeError>>,
+    synthetic_paste: bool,
+    hide_window: impl FnOnce(),
+    focus_delay: std::time::Duration,
+    send_paste: impl std::future::Future<Output = Result<(), MemeError>>,
+) -> Result<(), MemeError> {
+    copy.await?;
+    if !synthetic_paste {
+        return Ok(());
+    }
+
+    hide_window();
+    tokio::time::sleep(focus_delay).await;
+    send_paste.await
+}
+
+// 向当前获得焦点的应用发送粘贴快捷键
+async fn send_paste_keystroke() -> Result<(), MemeError> {
+    #[cfg(desktop)]
+    {
+        tokio::task::spawn_blocking(utils::paste::send_paste_keystroke)
+            .await
+            .map_err(|e| MemeError::Other(format!("模拟粘贴失败: {}", e)))?
+            .map_err(MemeError::Other)
+    }
+    #[cfg(not(desktop))]
+    {
+        Err(MemeError::Other("当前平台不支持自动粘贴".to_string()))
+    }
+}
+
+// 设置复制后是否自动模拟粘贴快捷键
+#[tauri::command]
+fn set_synthetic_paste(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_synthetic_paste(enabled)
+        .map_err(MemeError::from)
+}
+
+// 最近一次成功复制的表情包地址，供“复制上一个”快捷键使用
+static LAST_COPIED_MEME: Mutex<Option<String>> = Mutex::new(None);
+
+// 偏好设置中的剪贴板复制方式
+fn clipboard_mode() -> ClipboardMode {
+    get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.clipboard_mode)
+        .unwrap_or_default()
+}
+
+// 按偏好设置复制表情包，供复制命令与快速粘贴快捷键共用
+async fn copy_meme_to_clipboard(app: &tauri::AppHandle, image_url: String) -> Result<(), MemeError> {
+    copy_meme_with_mode(app, image_url, clipboard_mode()).await
+}
+
+// 按指定方式把表情包写入剪贴板
+//
+// 先解析全尺寸源与重定向得到最终地址，各模式写入的地址与下载的图片都使用该地址。
+async fn copy_meme_with_mode(
+    app: &tauri::AppHandle,
+    image_url: String,
+    mode: ClipboardMode,
+) -> Result<(), MemeError> {
+    info!("Copying image to clipboard: {} ({:?})", image_url, mode);
+
+    if !clipboard_copy_enabled()? {
+        return Ok(());
+    }
+
+    let url = resolve_copy_url(&image_url).await;
+    let image = if mode == ClipboardMode::Url {
+        None
+    } else {
+        // 下载图片数据（登记为复制操作，窗口隐藏时不会被取消）
+        let downloaded = run_cancellable(OperationKind::Copy, download_image_cached(&url))
+            .await
+            .ok_or_else(|| MemeError::Cancelled("复制已取消".to_string()))?
+            .map_err(MemeError::Network);
+        match downloaded {
+            Ok(image) => Some(image),
+            // Both 模式下载失败时仍写入地址
+            Err(e) if mode == ClipboardMode::Both => {
+                warn!("下载图片失败: {}，只复制图片地址", e);
+                None
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    write_meme(app.clipboard(), mode, &url, image.as_ref())?;
+    *LAST_COPIED_MEME.lock().unwrap_or_else(|e| e.into_inner()) = Some(image_url);
+    Ok(())
+}
+
+// 复制时实际使用的图片地址：配置了分级存储时换成全尺寸源，并按设置跟随重定向
+//
+// 解析失败时退回到之前的地址。
+async fn resolve_copy_url(image_url: &str) -> String {
+    let image_url = match get_config_manager().resolve_full_image_url(image_url) {
+        Ok(full_url) => {
+            if full_url != image_url {
+                debug!("使用全尺寸源URL: {}", full_url);
+            }
+            full_url
+        }
+        Err(err) => {
+            error!("解析全尺寸源URL失败: {}", err);
+            image_url.to_string()
+        }
+    };
+
+    let resolve_redirects = get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.resolve_redirects)
+        .unwrap_or(false);
+    if !resolve_redirects {
+        return image_url;
+    }
+    match utils::network::resolve_redirects(&image_url).await {
+        Ok(final_url) => {
+            if final_url != image_url {
+                debug!("重定向解析为: {}", final_url);
+            }
+            final_url
+        }
+        Err(err) => {
+            error!("解析重定向失败: {}", err);
+            image_url
+        }
+    }
+}
+
+// 检查复制到剪贴板的功能是否启用
+fn clipboard_copy_enabled() -> Result<bool, MemeError> {
+    match get_config_manager().get_preferences() {
+        Ok(prefs) => {
+            if !prefs.copy_to_clipboard {
+                info!("Clipboard copy is disabled in preferences");
+            }
+            Ok(prefs.copy_to_clipboard)
+        }
+        Err(err) => {
+            error!("获取偏好设置失败: {}", err);
+            Err(MemeError::io("Failed to get preferences", err))
+        }
+    }
+}
+
+// 复制表情包用到的剪贴板写入操作，测试中以记录写入内容的实现代替系统剪贴板
+trait ClipboardSink {
+    fn write_text(&self, text: &str) -> Result<(), MemeError>;
+    fn write_image(&self, image: &Image<'_>) -> Result<(), MemeError>;
+}
+
+impl<R: tauri::Runtime> ClipboardSink for tauri_plugin_clipboard_manager::Clipboard<R> {
+    fn write_text(&self, text: &str) -> Result<(), MemeError> {
+        self.write_text(text.to_string())
+            .map_err(|e| MemeError::Clipboard(format!("复制图片地址失败: {}", e)))
+    }
+
+    fn write_image(&self, image: &Image<'_>) -> Result<(), MemeError> {
+        self.write_image(image)
+            .map_err(|e| MemeError::Clipboard(format!("写入图片失败: {}", e)))
+    }
+}
+
+// 按模式写入地址与图片，`image` 为 None 时只能写入地址
+//
+// Both 先写入地址再写入图片：剪贴板只保留一项内容的平台上最终是图片，
+// 剪贴板历史中两者都在；图片无法写入时剪贴板中已有地址，不再重复写入。
+fn write_meme(
+    clipboard: &impl ClipboardSink,
+    mode: ClipboardMode,
+    url: &str,
+    image: Option<&utils::network::DownloadedImage>,
+) -> Result<(), MemeError> {
+    match (mode, image) {
+        (ClipboardMode::Url, _) | (_, None) => {
+            clipboard.write_text(url)?;
+            debug!("Image URL copied to clipboard");
+        }
+        (ClipboardMode::Both, Some(image)) => {
+            clipboard.write_text(url)?;
+            debug!("Image URL copied to clipboard");
+            let written = decode_clipboard_image(&image.bytes, image.content_type.as_deref())
+                .and_then(|decoded| clipboard.write_image(&decoded));
+            match written {
+                Ok(()) => debug!("Image copied to clipboard successfully"),
+                Err(e) => warn!("写入图片失败: {}，剪贴板中保留图片地址", e),
+            }
+        }
+        (ClipboardMode::Image, Some(image)) => {
+            write_image_or_url(clipboard, &image.bytes, image.content_type.as_deref(), url)?;
+        }
+    }
+    Ok(())
+}
+
+// 写入图片，无法正确写入时改为写入图片地址
+fn write_image_or_url(
+    clipboard: &impl ClipboardSink,
+    bytes: &[u8],
+    content_type: Option<&str>,
+    url: &str,
+) -> Result<(), MemeError> {
+    let image_error = match decode_clipboard_image(bytes, content_type)
+        .and_then(|image| clipboard.write_image(&image))
+    {
+        Ok(()) => {
+            debug!("Image copied to clipboard successfully");
+            return Ok(());
+        }
+        Err(e) => {
+            warn!("写入图片失败: {}，改为复制图片地址", e);
+            e
+        }
+    };
+
+    clipboard.write_text(url).map_err(|e| {
+        MemeError::Clipboard(format!("无法复制图片: {}；复制图片地址也失败: {}", image_error, e))
+    })?;
+    debug!("Image URL copied to clipboard as fallback");
+    Ok(())
+}
+
+// 解码可写入剪贴板的静态图片
+//
+// 剪贴板只能保存静态图片，多帧的 GIF 动图写入后只剩第一帧，因此不写入图片；
+// 单帧的 GIF 照常解码。
+fn decode_clipboard_image(bytes: &[u8], content_type: Option<&str>) -> Result<Image<'static>, MemeError> {
+    if bytes.len() > utils::network::MAX_IMAGE_BYTES {
+        return Err(MemeError::InvalidInput(format!(
+            "图片过大: {} 字节，上限为 {} 字节",
+            bytes.len(),
+            utils::network::MAX_IMAGE_BYTES
+        )));
+    }
+    if image_extension(content_type, bytes) == Some("gif") && utils::network::is_animated_gif(bytes) {
+        return Err(MemeError::InvalidInput("GIF 动图以地址形式复制，以保留动画".to_string()));
+    }
+    Image::from_bytes(bytes).map_err(|e| MemeError::Parse(format!("无法解析图片: {}", e)))
+}
+
+// 解码图片数据并写入剪贴板，用于本地图片
+fn write_image_to_clipboard(clipboard: &impl ClipboardSink, bytes: &[u8]) -> Result<(), MemeError> {
+    let image = decode_clipboard_image(bytes, None)?;
+    clipboard.write_image(&image)?;
+    debug!("Image copied to clipboard successfully");
+    Ok(())
+}
+
+// 清空复制时缓存的图片，返回删除的文件数
+#[tauri::command]
+fn clear_image_cache() -> Result<usize, MemeError> {
+    utils::image_cache::clear().map_err(MemeError::Config)
+}
+
+// 将本地图片文件复制到剪贴板，不经过网络
+#[tauri::command]
+async fn copy_local_image(app: tauri::AppHandle, path: String) -> Result<(), MemeError> {
+    info!("Copying local image to clipboard: {}", path);
+
+    if !clipboard_copy_enabled()? {
+        return Ok(());
+    }
+
+    // 先检查文件大小，避免读取过大的文件
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| MemeError::io(format!("无法读取文件: {}", path), e))?;
+    if !metadata.is_file() {
+        return Err(MemeError::InvalidInput(format!("不是文件: {}", path)));
+    }
+    if metadata.len() > utils::network::MAX_IMAGE_BYTES as u64 {
+        return Err(MemeError::InvalidInput(format!(
+            "图片过大: {} 字节，上限为 {} 字节",
+            metadata.len(),
+            utils::network::MAX_IMAGE_BYTES
+        )));
+    }
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| MemeError::io(format!("无法读取文件: {}", path), e))?;
+    write_image_to_clipboard(app.clipboard(), &bytes)
+}
+
+// 跟随重定向解析URL的最终地址
+#[tauri::command]
+async fn resolve_url(url: String) -> Result<String, MemeError> {
+    utils::network::resolve_redirects(&url)
+        .await
+        .map_err(MemeError::Network)
+}
+
+// 设置复制前是否先解析重定向
+#[tauri::command]
+fn set_resolve_redirects(enabled: bool) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_resolve_redirects(enabled)
+        .map_err(MemeError::from)
+}
+
+// 生成编码表情包URL的二维码PNG，便于在手机上打开
+#[tauri::command]
+fn get_meme_qr(url: String) -> Result<Vec<u8>, MemeError> {
+    let parsed = tauri::Url::parse(url.trim())
+        .map_err(|e| MemeError::InvalidInput(format!("无效的URL: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(MemeError::InvalidInput(format!(
+            "不支持的URL协议: {}",
+            parsed.scheme()
+        )));
+    }
+
+    let code = qrcode::QrCode::new(parsed.as_str().as_bytes()).map_err(|e| match e {
+        qrcode::types::QrError::DataTooLong => {
+            MemeError::InvalidInput("URL过长，无法编码为二维码".to_string())
+        }
+        other => MemeError::Other(format!("生成二维码失败: {}", other)),
+    })?;
+    let image = code
+        .render::<image::Luma<u8>>()
+        .min_dimensions(256, 256)
+        .build();
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| MemeError::Other(format!("编码二维码图片失败: {}", e)))?;
+    Ok(png.into_inner())
+}
+
+// 剪贴板写入延迟测试结果（毫秒）
+#[derive(serde::Serialize)]
+struct ClipboardBench {
+    runs: u32,
+    min_ms: f64,
+    median_ms: f64,
+    max_ms: f64,
+}
+
+// 测量剪贴板写入图片的延迟，用于区分剪贴板慢与网络慢
+#[tauri::command]
+async fn benchmark_clipboard(runs: u32, window: tauri::Window) -> Result<ClipboardBench, MemeError> {
+    let runs = runs.clamp(1, 50);
+    info!("开始剪贴板写入延迟测试，共 {} 次", runs);
+
+    // 64x64 的纯色测试图片
+    let (width, height) = (64, 64);
+    let rgba = [0xFF, 0x99, 0x33, 0xFF].repeat((width * height) as usize);
+    let image = Image::new_owned(rgba, width, height);
+    let clipboard = window.clipboard();
+
+    let mut samples = Vec::with_capacity(runs as usize);
+    for _ in 0..runs {
+        let started = std::time::Instant::now();
+        clipboard
+            .write_image(&image)
+            .map_err(|e| MemeError::Clipboard(format!("写入图片失败: {}", e)))?;
+        samples.push(started.elapsed().as_secs_f64() * 1000.0);
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+
+    let median_ms = if samples.len() % 2 == 0 {
+        (samples[samples.len() / 2 - 1] + samples[samples.len() / 2]) / 2.0
+    } else {
+        samples[samples.len() / 2]
+    };
+
+    let bench = ClipboardBench {
+        runs,
+        min_ms: samples[0],
+        median_ms,
+        max_ms: samples[samples.len() - 1],
+    };
+    info!(
+        "剪贴板写入延迟: 最小 {:.1}ms，中位 {:.1}ms，最大 {:.1}ms",
+        bench.min_ms, bench.median_ms, bench.max_ms
+    );
+    Ok(bench)
+}
+
+// 设置无结果时显示的占位图（本地路径或URL），传入空值则清除
+#[tauri::command]
+fn set_placeholder_image(path_or_url: Option<String>) -> Result<(), MemeError> {
+    let source = path_or_url.filter(|s| !s.trim().is_empty());
+    if let Some(source) = &source {
+        let is_url = source.starts_with("http://") || source.starts_with("https://");
+        if !is_url && !std::path::Path::new(source).is_file() {
+            return Err(MemeError::NotFound(format!("占位图文件不存在: {}", source)));
+        }
+    }
+    get_config_manager()
+        .update_placeholder_image(source)
+        .map_err(MemeError::from)
+}
+
+// 读取已配置的占位图数据，未配置时返回None
+#[tauri::command]
+async fn get_placeholder_image() -> Result<Option<Vec<u8>>, MemeError> {
+    let source = match get_config_manager().get_preferences()?.placeholder_image {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let image = download_image(&source).await.map_err(MemeError::Network)?;
+        Ok(Some(image.bytes))
+    } else {
+        tokio::fs::read(&source)
+            .await
+            .map(Some)
+            .map_err(|e| MemeError::io(format!("读取占位图失败: {}", source), e))
+    }
+}
+
+// 目标文件已存在时，在文件名后追加序号，如 `meme (1).png`
+fn unique_file_path(path: std::path::PathBuf) -> std::path::PathBuf {
+    if !path.exists() {
+        return path;
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+    (1..)
+        .map(|counter| {
+            let name = match &extension {
+                Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+                None => format!("{} ({})", stem, counter),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(path)
+}
+
+// 将表情包保存为本地文件
+//
+// 根据图片实际格式修正文件扩展名，目标文件已存在时追加序号而不覆盖，
+// 返回最终写入的路径。
+#[tauri::command]
+async fn save_meme_to_file(url: String, path: String) -> Result<String, MemeError> {
+    info!("保存表情包到文件: {} -> {}", url, path);
+
+    // 复制过的图片直接从缓存读取
+    let image = download_image_cached(&url).await.map_err(MemeError::Network)?;
+
+    let mut path = std::path::PathBuf::from(path);
+    if let Some(ext) = image_extension(image.content_type.as_deref(), &image.bytes) {
+        let matches = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case(ext) || (ext == "jpg" && e.eq_ignore_ascii_case("jpeg")))
+            .unwrap_or(false);
+        if !matches {
+            path.set_extension(ext);
+        }
+    }
+    let path = unique_file_path(path);
+
+    tokio::fs::write(&path, &image.bytes)
+        .await
+        .map_err(|e| MemeError::io(format!("写入文件失败: {:?}", path), e))?;
+
+    info!("表情包已保存到: {:?}", path);
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 已查询过的图片MIME类型缓存
+static MIME_CACHE: OnceLock<std::sync::Mutex<std::collections::HashMap<String, String>>> =
+    OnceLock::new();
+
+// 获取表情包图片的MIME类型，无需下载完整文件
+#[tauri::command]
+async fn get_meme_mime(url: String) -> Result<String, MemeError> {
+    let cache = MIME_CACHE.get_or_init(Default::default);
+    if let Some(mime) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&url) {
+        return Ok(mime.clone());
+    }
+
+    let mime = match utils::network::fetch_content_type(&url)
+        .await
+        .map_err(MemeError::Network)?
+    {
+        Some(mime) => mime,
+        None => {
+            debug!("服务器未返回Content-Type，根据扩展名猜测: {}", url);
+            utils::network::guess_mime_from_url(&url)
+                .unwrap_or("application/octet-stream")
+                .to_string()
+        }
+    };
+
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(url, mime.clone());
+    Ok(mime)
+}
+
+// 添加API URL配置的命令函数
+#[tauri::command]
+fn get_api_url_config() -> Result<config_manager::ApiUrlConfig, MemeError> {
+    get_config_manager().get_api_url_config().map_err(MemeError::from)
+}
+
+#[tauri::command]
+fn update_api_url_config(config: config_manager::ApiUrlConfig) -> Result<(), MemeError> {
+    invalidating_search_cache(|| get_config_manager().update_api_url_config(config))
+        .map_err(MemeError::from)
+}
+
+#[tauri::command]
+fn add_api_url(name: String, url: String) -> Result<(), MemeError> {
+    get_config_manager().add_api_url(name, url).map_err(MemeError::from)
+}
+
+#[tauri::command]
+fn remove_api_url(index: usize) -> Result<(), MemeError> {
+    invalidating_search_cache(|| get_config_manager().remove_api_url(index))
+        .map_err(MemeError::from)
+}
+
+#[tauri::command]
+fn set_active_api_url(index: usize) -> Result<(), MemeError> {
+    invalidating_search_cache(|| get_config_manager().set_active_api_url(index))
+        .map_err(MemeError::from)
+}
+
+// 设置指定API源的搜索请求格式（json 或 form）
+#[tauri::command]
+fn set_api_request_format(index: usize, format: String) -> Result<(), MemeError> {
+    let format = format.parse::<utils::misc::RequestFormat>().map_err(MemeError::InvalidInput)?;
+    get_config_manager()
+        .update_api_request_format(index, format)
+        .map_err(MemeError::from)
+}
+
+// 设置指定API源的搜索方法（auto、post 或 get）
+#[tauri::command]
+fn set_api_search_method(index: usize, method: String) -> Result<(), MemeError> {
+    let method = method.parse::<utils::misc::SearchMethod>().map_err(MemeError::InvalidInput)?;
+    get_config_manager()
+        .update_api_search_method(index, method)
+        .map_err(MemeError::from)
+}
+
+// 设置指定API源响应中结果数组的路径（如 payload.items），不传则自动识别
+#[tauri::command]
+fn set_api_results_key(index: usize, results_key: Option<String>) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_api_results_key(index, results_key)
+        .map_err(MemeError::from)
+}
+
+// 设置指定API源的搜索请求头（如 Authorization），用于需要认证的私有部署
+#[tauri::command]
+fn set_api_headers(index: usize, headers: HashMap<String, String>) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_api_headers(index, headers)
+        .map_err(MemeError::from)
+}
+
+// 清除指定API源的搜索请求头
+#[tauri::command]
+fn clear_api_headers(index: usize) -> Result<(), MemeError> {
+    get_config_manager()
+        .update_api_headers(index, HashMap::new())
+        .map_err(MemeError::from)
+}
+
+#[tauri::command]
+fn set_tiered_api_sources(
+    preview_index: Option<usize>,
+    full_index: Option<usize>,
+) -> Result<(), MemeError> {
+    invalidating_search_cache(|| {
+        get_config_manager().set_tiered_api_sources(preview_index, full_index)
+    })
+        .map_err(MemeError::from)
+}
+
+// 按顺序探测已配置的API，将第一个可用的设为活跃API并返回其索引
+#[tauri::command]
+async fn select_first_working_api() -> Result<usize, MemeError> {
+    let config = get_config_manager().get_api_url_config()?;
+
+    for (index, api) in config.urls.iter().enumerate() {
+        match utils::network::probe_url(&api.url, &api.headers, std::time::Duration::from_secs(3))
+            .await
+        {
+            Ok(probe) if probe.is_reachable() => {
+                info!("API可用: {} ({}ms)，设为活跃API", api.url, probe.latency_ms);
+                invalidating_search_cache(|| get_config_manager().set_active_api_url(index))?;
+                return Ok(index);
+            }
+            Ok(probe) => debug!("API不可用: {} 状态码 {}", api.url, probe.status),
+            Err(e) => debug!("API不可用: {} - {}", api.url, e),
+        }
+    }
+
+    Err(MemeError::Network("没有可用的API".to_string()))
+}
+
+// 单个API的健康检查结果
+#[derive(Debug, serde::Serialize)]
+struct ApiHealth {
+    name: String,
+    url: String,
+    reachable: bool,
+    // 往返耗时，请求失败时为空
+    latency_ms: Option<u64>,
+    status: Option<u16>,
+}
+
+// 并发探测所有已配置的API，按配置顺序返回各自的可用性与延迟
+#[tauri::command]
+async fn check_api_urls() -> Result<Vec<ApiHealth>, MemeError> {
+    let config = get_config_manager().get_api_url_config()?;
+    Ok(probe_apis(config.urls, std::time::Duration::from_secs(3)).await)
+}
+
+// 并发探测各API，同时进行的探测数受 `utils::concurrency` 的全局上限约束（在 probe_url 中获取许可）
+async fn probe_apis(apis: Vec<utils::misc::ApiUrl>, timeout: std::time::Duration) -> Vec<ApiHealth> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, api) in apis.into_iter().enumerate() {
+        tasks.spawn(async move {
+            let probe = utils::network::probe_url(&api.url, &api.headers, timeout).await;
+            let health = match probe {
+                Ok(probe) => ApiHealth {
+                    reachable: probe.is_reachable(),
+                    latency_ms: Some(probe.latency_ms),
+                    status: Some(probe.status),
+                    name: api.name,
+                    url: api.url,
+                },
+                Err(e) => {
+                    debug!("API不可用: {} - {}", api.url, e);
+                    ApiHealth {
+                        reachable: false,
+                        latency_ms: None,
+                        status: None,
+                        name: api.name,
+                        url: api.url,
+                    }
+                }
+            };
+            (index, health)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => error!("API健康检查任务失败: {}", e),
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, health)| health).collect()
+}
+
+// 单个API的对比测试结果
+#[derive(Debug, serde::Serialize)]
+struct ApiComparison {
+    name: String,
+    url: String,
+    success: bool,
+    latency_ms: u64,
+    result_count: usize,
+    error: Option<String>,
+}
+
+// 用同一关键词并发搜索所有已配置的API并对比结果，不会修改活跃API
+#[tauri::command]
+async fn compare_apis(keyword: String) -> Result<Vec<ApiComparison>, MemeError> {
+    let prefs = get_config_manager().get_preferences()?;
+    let timeout = std::time::Duration::from_secs(prefs.timeout_seconds);
+    let client = get_meme_client();
+    // 比较的是各API的实际响应，不能命中之前的搜索缓存
+    let options = SearchOptions {
+        bypass_cache: true,
+        ..Default::default()
+    };
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, api) in prefs.api_urls.urls.into_iter().enumerate() {
+        let client = client.clone();
+        let keyword = keyword.clone();
+        let options = options.clone();
+        tasks.spawn(async move {
+            let _permit = utils::concurrency::acquire().await;
+            let started = std::time::Instant::now();
+            let result = tokio::time::timeout(
+                timeout,
+                client.search_memes_at(&api, &keyword, &options, None),
+            )
+            .await;
+            let latency_ms = started.elapsed().as_millis() as u64;
+
+            let (success, result_count, error) = match result {
+                Ok(Ok(items)) => (true, items.len(), None),
+                Ok(Err(e)) => (false, 0, Some(e.to_string())),
+                Err(_) => (false, 0, Some(format!("请求超时（{}秒）", timeout.as_secs()))),
+            };
+            (
+                index,
+                ApiComparison {
+                    name: api.name,
+                    url: api.url,
+                    success,
+                    latency_ms,
+                    result_count,
+                    error,
+                },
+            )
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => error!("API对比任务失败: {}", e),
+        }
+    }
+    // 保持与配置中相同的顺序
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, comparison)| comparison).collect())
+}
+
+// 多API搜索中失败的API
+#[derive(Debug, serde::Serialize)]
+struct FailedApi {
+    name: String,
+    url: String,
+    error: String,
+}
+
+// 多API搜索的合并结果
+#[derive(Debug, serde::Serialize)]
+struct MultiApiSearchResult {
+    items: Vec<MemeItem>,
+    // 搜索失败的API，只要有一个API成功就仍会返回部分结果
+    failed: Vec<FailedApi>,
+    // 被屏蔽词过滤掉的结果数量
+    blocked_count: usize,
+}
+
+// 同时在所有已配置的API上搜索，按图片地址去重后合并结果
+#[tauri::command]
+async fn search_all_apis(
+    app: tauri::AppHandle,
+    keyword: String,
+    sort_order: Option<String>,
+) -> Result<MultiApiSearchResult, MemeError> {
+    let prefs = get_config_manager().get_preferences()?;
+    let sort_order = match sort_order {
+        Some(order) => order.parse::<SortOrder>().map_err(MemeError::InvalidInput)?,
+        None => prefs.sort_order,
+    };
+    if keyword.trim().chars().count() < prefs.min_keyword_length.max(1) {
+        return Ok(MultiApiSearchResult {
+            items: Vec::new(),
+            failed: Vec::new(),
+            blocked_count: 0,
+        });
+    }
+    let options = SearchOptions {
+        quota: Some(session_quota(prefs.session_search_quota)),
+        ..Default::default()
+    };
+
+    let timeout = prefs.search_timeout_seconds.map(std::time::Duration::from_secs);
+    let (items, failed) =
+        search_apis(get_meme_client(), prefs.api_urls.urls, &keyword, &options, timeout).await?;
+
+    let (mut items, blocked_count) = filter_blocked(items, &prefs.blocklist);
+    sort_memes(&mut items, sort_order, &keyword, &prefs.source_priority);
+    record_keyword(&app, &keyword).await;
+    Ok(MultiApiSearchResult {
+        items,
+        failed,
+        blocked_count,
+    })
+}
+
+// 在每个API上搜索，合并结果并记录失败的API；只有全部失败时才返回错误
+//
+// 各API的请求共用 `options` 中的配额，整次搜索只扣除一次。
+async fn search_apis(
+    client: Arc<MemeServerClient>,
+    apis: Vec<utils::misc::ApiUrl>,
+    keyword: &str,
+    options: &SearchOptions,
+    timeout: Option<std::time::Duration>,
+) -> Result<(Vec<MemeItem>, Vec<FailedApi>), MemeError> {
+    let api_count = apis.len();
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, api) in apis.into_iter().enumerate() {
+        let client = client.clone();
+        let keyword = keyword.to_string();
+        let options = options.clone();
+        tasks.spawn(async move {
+            let _permit = utils::concurrency::acquire().await;
+            let result = client
+                .search_memes_at(&api, &keyword, &options, timeout)
+                .await;
+            (index, api, result)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => error!("多API搜索任务失败: {}", e),
+        }
+    }
+    // 按配置中的顺序合并，排在前面的API的结果优先保留
+    results.sort_by_key(|(index, _, _)| *index);
+
+    let mut items: Vec<MemeItem> = Vec::new();
+    let mut seen_urls = std::collections::HashSet::new();
+    let mut failed = Vec::new();
+    let mut quota_exceeded = None;
+    for (_, api, result) in results {
+        match result {
+            Ok(memes) => {
+                items.extend(memes.into_iter().filter(|item| seen_urls.insert(item.url.clone())));
+            }
+            Err(e) => {
+                if let SearchError::QuotaExceeded(message) = &e {
+                    quota_exceeded = Some(message.clone());
+                }
+                error!("API搜索失败: {} - {}", api.url, e);
+                failed.push(FailedApi {
+                    name: api.name,
+                    url: api.url,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    // 所有API都失败时返回错误
+    if api_count > 0 && failed.len() == api_count {
+        if let Some(message) = quota_exceeded {
+            return Err(MemeError::Locked(message));
+        }
+        return Err(MemeError::Network(
+            failed
+                .iter()
+                .map(|f| format!("{}: {}", f.url, f.error))
+                .collect::<Vec<_>>()
+                .join("; "),
+        ));
+    }
+    Ok((items, failed))
+}
+
+// 初始化日志，需在 run 之前调用；日志级别读取自偏好设置
+pub fn init_logging() {
+    // 读取配置本身也会输出日志，因此先以默认级别初始化再应用偏好设置
+    utils::logging::init(log::LevelFilter::Info);
+    let level = get_config_manager()
+        .get_preferences()
+        .map_err(|e| e.to_string())
+        .and_then(|prefs| utils::logging::parse_level(&prefs.log_level));
+    match level {
+        Ok(level) => utils::logging::set_level(level),
+        Err(e) => error!("读取日志级别失败: {}，使用默认级别", e),
+    }
+}
+
+// 修改 run 函数以使用配置的快捷键并添加系统托盘
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let mut builder = tauri::Builder::default();
+
+    // 单实例保护需要最先注册，再次启动时显示已运行的窗口而不是创建新实例
+    #[cfg(desktop)]
+    {
+        let single_instance = get_config_manager()
+            .get_preferences()
+            .map(|prefs| prefs.single_instance)
+            .unwrap_or(true);
+        if single_instance {
+            builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+                info!("检测到重复启动，显示已运行的窗口");
+                show_main_window(app);
+            }));
+        } else {
+            info!("单实例保护已关闭");
+        }
+    }
+
+    builder
+        .plugin(tauri_plugin_window_state::Builder::new().build())
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin({
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_global_shortcut::{
+                    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState,
+                };
+
+                tauri_plugin_global_shortcut::Builder::new()
+                    .with_handler(move |app, shortcut, event| {
+                        if event.state == ShortcutState::Pressed {
+                            // 获取当前配置的切换应用快捷键
+                            if let Some(config_manager) = CONFIG_MANAGER.get() {
+                                match config_manager.get_toggle_app_shortcut() {
+                                    Ok((mods, code)) => {
+                                        let config_shortcut = Shortcut::new(Some(mods), code);
+
+                                        // 检查是否匹配配置的快捷键
+                                        if shortcut == &config_shortcut {
+                                            if !accept_shortcut_toggle() {
+                                                debug!("忽略重复触发的切换应用快捷键");
+                                                return;
+                                            }
+                                            info!("触发切换应用快捷键: {:?}", shortcut);
+                                            toggle_main_window(app);
+                                        } else {
+                                            dispatch_action_shortcut(app, shortcut);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        error!("无法获取切换应用快捷键配置: {}", e);
+                                    }
+                                }
+                            } else {
+                                error!("配置管理器未初始化");
+                            }
+                        }
+                    })
+                    .build()
+            }
+            #[cfg(not(desktop))]
+            {
+                // 非桌面平台的无操作插件
+                tauri_plugin_global_shortcut::Builder::new().build()
+            }
+        })
+        .plugin(tauri_plugin_autostart::init(
+            MacosLauncher::LaunchAgent,
+            Some(vec!["--flag1", "--flag2"]),
+        ))
+        // Builder::setup 只保留最后一次设置的回调，所有启动逻辑都需放在这一个回调中
+        .setup(|app| {
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+                // 初始化配置管理器
+                let config_manager = get_config_manager();
+
+                // 注册快捷键
+                register_app_shortcuts(app, config_manager);
+
+                // 应用用户强制使用的主题
+                if let (Some(theme), Some(window)) = (theme_override(), app.get_webview_window("main")) {
+                    let theme = if theme == "dark" { tauri::Theme::Dark } else { tauri::Theme::Light };
+                    if let Err(e) = window.set_theme(Some(theme)) {
+                        error!("设置窗口主题失败: {}", e);
+                    }
+                }
+
+                // 初始化系统托盘
+                if let Err(e) = create_system_tray(app) {
+                    error!("创建系统托盘失败: {}", e);
+                } else {
+                    info!("系统托盘创建成功");
+                }
+            }
+
+            // 清理过期的搜索历史
+            if let Err(e) = search_history::purge_expired() {
+                error!("清理搜索历史失败: {}", e);
+            }
+
+            // 启动社区清单后台自动刷新
+            meme_community::spawn_manifest_auto_refresh(app.handle().clone());
+
+            // 按设置在后台预热搜索缓存
+            spawn_cache_warmup();
+
+            // 获取自动启动管理器
+            let autostart_manager = app.autolaunch();
+            // 启用 autostart
+            let _ = autostart_manager.enable();
+            // 检查 enable 状态
+            println!(
+                "registered for autostart? {}",
+                autostart_manager.is_enabled().unwrap()
+            );
+            // 禁用 autostart
+            let _ = autostart_manager.disable();
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // 系统主题变化时通知前端，设置了主题覆盖时忽略
+            if let tauri::WindowEvent::ThemeChanged(theme) = event {
+                if theme_override().is_none() {
+                    let _ = window.emit("theme-changed", theme_name(*theme));
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            search_memes,
+            search_memes_detailed,
+            search_memes_with_token,
+            random_meme,
+            resolve_search_libs,
+            set_sort_order,
+            set_download_timeout_policy,
+            set_download_mode,
+            set_manifest_timeout,
+            set_proxy,
+            set_search_timeout,
+            add_blocked_term,
+            remove_blocked_term,
+            set_log_level,
+            open_log_dir,
+            tail_logs,
+            set_min_keyword_length,
+            set_merge_cached_results,
+            set_warm_cache_on_startup,
+            set_result_limit,
+            clear_search_cache,
+            get_cache_namespaces,
+            clear_cache_namespace,
+            set_search_cache_ttl,
+            set_auto_failover,
+            get_search_quota_status,
+            set_session_search_quota,
+            set_search_history_retention,
+            set_record_history,
+            set_search_history_limit,
+            set_request_tracing,
+            set_manifest_auto_refresh,
+            set_manifest_cache_ttl,
+            get_concurrency_limit,
+            get_recent_errors,
+            set_single_instance,
+            get_system_theme,
+            set_theme_override,
+            set_concurrency_limit,
+            reset_preferences,
+            reload_meme_client,
+            get_user_preferences,
+            get_effective_config,
+            set_copy_to_clipboard,
+            set_clipboard_mode,
+            copy_meme,
+            paste_meme,
+            set_synthetic_paste,
+            clear_image_cache,
+            set_test_mode,
+            set_pause_when_hidden,
+            set_tray_click_toggles,
+            cancel_all_operations,
+            copy_image_to_clipboard,
+            copy_local_image,
+            get_meme_qr,
+            resolve_url,
+            set_resolve_redirects,
+            save_meme_to_file,
+            get_meme_mime,
+            benchmark_clipboard,
+            set_placeholder_image,
+            get_placeholder_image,
+            get_shortcuts,
+            set_shortcuts,
+            parse_shortcut,
+            probe_shortcut,
+            get_shortcut_bindings,
+            debug_resolve_shortcut,
+            set_quick_paste,
+            refresh_shortcuts,
+            suspend_shortcuts,
+            resume_shortcuts,
+            // 添加API URL管理命令
+            get_api_url_config,
+            update_api_url_config,
+            add_api_url,
+            remove_api_url,
+            set_active_api_url,
+            set_tiered_api_sources,
+            set_api_headers,
+            clear_api_headers,
+            set_api_request_format,
+            set_api_search_method,
+            set_api_results_key,
+            select_first_working_api,
+            check_api_urls,
+            compare_apis,
+            search_all_apis,
+            meme_community::get_api_server_urls_config,
+            meme_community::fetch_community_manifest,
+            meme_community::refresh_community_manifest,
+            meme_community::check_manifest_updates,
+            meme_community::get_enabled_meme_libs,
+            meme_community::enable_meme_lib,
+            meme_community::disable_meme_lib,
+            meme_community::find_duplicate_memes,
+            meme_community::fetch_resource_index,
+            meme_community::get_resource_index,
+            meme_community::repair_enabled_libs,
+            meme_community::get_meme_lib,
+            meme_community::query_meme_libs,
+            meme_community::browse_lib,
+            meme_community::download_meme_pack,
+            lib_install::download_meme_lib,
+            lib_install::get_installed_libs,
+            lib_install::get_cache_usage,
+            lib_install::prune_meme_lib,
+            lib_install::clear_all_cached_libs,
+            state_bundle::export_state,
+            state_bundle::import_state,
+            state_bundle::export_config,
+            state_bundle::import_config,
+            favorites::add_favorite,
+            favorites::remove_favorite,
+            favorites::list_favorites,
+            favorites::is_favorite,
+            favorites::search_favorites,
+            favorites::preview_favorites_import,
+            favorites::apply_favorites_import,
+            search_history::purge_search_history,
+            search_history::get_search_history,
+            search_history::clear_search_history,
+            search_history::remove_history_entry
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+// 注册应用快捷键
+#[cfg(desktop)]
+fn register_app_shortcuts(app: &tauri::App, config_manager: &ConfigManager) {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+    let app = app.handle();
+    // 失败原因已记录并通知前端
+    let _ = register_configured_shortcuts(app.global_shortcut(), config_manager, &|message| {
+        notify_shortcut_failure(app, message)
+    });
+}
+
+// 通过 shortcut-registration-failed 事件通知前端快捷键注册失败
+#[cfg(desktop)]
+fn notify_shortcut_failure(app: &tauri::AppHandle, message: String) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("shortcut-registration-failed", message);
+    }
+}
+
+// 按配置注册所有快捷键：动作快捷键、快速粘贴快捷键与切换应用快捷键
+//
+// 单个快捷键注册失败时通过 `notify` 通知前端，不影响其他快捷键；返回切换应用快捷键的注册结果。
+#[cfg(desktop)]
+fn register_configured_shortcuts(
+    registry: &impl ShortcutRegistry,
+    config_manager: &ConfigManager,
+    notify: &dyn Fn(String),
+) -> Result<(), MemeError> {
+    use tauri_plugin_global_shortcut::Shortcut;
+
+    register_action_shortcuts(registry, config_manager, notify);
+
+    match config_manager.get_toggle_app_shortcut() {
+        Ok((mods, code)) => match registry.register(Shortcut::new(Some(mods), code)) {
+            Ok(()) => {
+                info!("成功注册应用切换快捷键");
+                Ok(())
+            }
+            Err(e) => {
+                error!("注册快捷键失败: {}", e);
+                notify(format!(
+                    "无法注册快捷键 {}，可能与系统快捷键冲突。请在设置中配置其他快捷键。",
+                    format_shortcut_for_display(&mods, &code)
+                ));
+                Err(MemeError::Other(format!("无法注册快捷键: {}", e)))
+            }
+        },
+        Err(e) => {
+            error!("获取快捷键配置失败: {}", e);
+            notify(format!("无法获取快捷键配置: {}，请在设置中重新配置快捷键。", e));
+            Err(MemeError::io("无法获取快捷键配置", e))
         }
     }
 }
 
+// 注册除切换窗口以外的所有快捷键：动作快捷键与快速粘贴快捷键
+#[cfg(desktop)]
+fn register_action_shortcuts(
+    registry: &impl ShortcutRegistry,
+    config_manager: &ConfigManager,
+    notify: &dyn Fn(String),
+) {
+    use tauri_plugin_global_shortcut::Shortcut;
+
+    let shortcuts = match config_manager.get_shortcuts() {
+        Ok(shortcuts) => shortcuts,
+        Err(e) => {
+            error!("获取快捷键配置失败: {}", e);
+            return;
+        }
+    };
+
+    for (id, config) in [("copy_last", &shortcuts.copy_last), ("open_search", &shortcuts.open_search)] {
+        let Some(config) = config else {
+            continue;
+        };
+        let result = config.to_tauri_shortcut().and_then(|(mods, code)| {
+            registry.register(Shortcut::new(Some(mods), code)).map_err(|e| {
+                format!(
+                    "无法注册快捷键 {}，可能与系统快捷键冲突: {}",
+                    format_shortcut_for_display(&mods, &code),
+                    e
+                )
+            })
+        });
+        match result {
+            Ok(_) => debug!("成功注册快捷键: {}", id),
+            Err(e) => {
+                error!("注册快捷键「{}」失败: {}", config.action_name(id), e);
+                notify(e);
+            }
+        }
+    }
+
+    register_quick_paste_shortcuts(registry, &shortcuts, notify);
+}
+
+// 注册所有快速粘贴快捷键，单个注册失败时通知前端但不影响其他快捷键
+#[cfg(desktop)]
+fn register_quick_paste_shortcuts(
+    registry: &impl ShortcutRegistry,
+    shortcuts: &ShortcutConfigs,
+    notify: &dyn Fn(String),
+) {
+    use tauri_plugin_global_shortcut::Shortcut;
+
+    for binding in &shortcuts.quick_paste {
+        let (mods, code) = match binding.shortcut.to_tauri_shortcut() {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!("快速粘贴快捷键 {} 无效: {}", binding.favorite_id, e);
+                notify(format!("快速粘贴快捷键无效: {}，请在设置中重新配置。", e));
+                continue;
+            }
+        };
+        match registry.register(Shortcut::new(Some(mods), code)) {
+            Ok(_) => debug!("成功注册快速粘贴快捷键: {}", binding.favorite_id),
+            Err(e) => {
+                error!("注册快速粘贴快捷键失败: {}", e);
+                notify(format!(
+                    "无法注册快速粘贴快捷键 {}，可能与系统快捷键冲突。",
+                    format_shortcut_for_display(&mods, &code)
+                ));
+            }
+        }
+    }
+}
+
+// 处理快速粘贴快捷键：将绑定的收藏复制到剪贴板
+//
+// 绑定的收藏已被删除时不做任何操作，只通过 quick-paste-unavailable 事件通知前端。
+#[cfg(desktop)]
+fn dispatch_quick_paste(
+    app: &tauri::AppHandle,
+    shortcut: &tauri_plugin_global_shortcut::Shortcut,
+) {
+    let favorite_id = match get_config_manager()
+        .get_shortcuts()
+        .ok()
+        .and_then(|s| s.find_quick_paste(shortcut.mods, shortcut.key).map(|b| b.favorite_id.clone()))
+    {
+        Some(id) => id,
+        None => return,
+    };
+    info!("触发快速粘贴快捷键: {}", favorite_id);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let favorite = favorites::load_favorites()
+            .ok()
+            .and_then(|f| f.entries.into_iter().find(|e| e.item.id == favorite_id));
+        let Some(favorite) = favorite else {
+            info!("快速粘贴绑定的收藏已不存在: {}", favorite_id);
+            let _ = app.emit("quick-paste-unavailable", favorite_id);
+            return;
+        };
+
+        if let Err(e) = copy_meme_to_clipboard(&app, favorite.item.url).await {
+            error!("快速粘贴失败: {}", e);
+        }
+    });
+}
+
+// 处理切换窗口以外的快捷键：先匹配动作快捷键，再匹配快速粘贴
+#[cfg(desktop)]
+fn dispatch_action_shortcut(
+    app: &tauri::AppHandle,
+    shortcut: &tauri_plugin_global_shortcut::Shortcut,
+) {
+    let action = get_config_manager()
+        .get_shortcuts()
+        .ok()
+        .and_then(|s| s.find_action(shortcut.mods, shortcut.key));
+
+    match action {
+        Some(config_manager::ShortcutAction::CopyLast) => {
+            let last = LAST_COPIED_MEME.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            let Some(url) = last else {
+                info!("还没有复制过表情包，忽略复制上一个快捷键");
+                return;
+            };
+            info!("触发复制上一个表情包快捷键: {}", url);
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = copy_meme_to_clipboard(&app, url).await {
+                    error!("复制上一个表情包失败: {}", e);
+                }
+            });
+        }
+        Some(config_manager::ShortcutAction::OpenSearch) => {
+            info!("触发打开搜索快捷键");
+            show_main_window(app);
+            let _ = app.emit("focus-search", ());
+        }
+        None => dispatch_quick_paste(app, shortcut),
+    }
+}
+
+// 为收藏设置或清除快速粘贴快捷键，设置后立即重新注册快捷键
+#[tauri::command]
+fn set_quick_paste(
+    app: tauri::AppHandle,
+    favorite_id: String,
+    shortcut: Option<ShortcutConfig>,
+) -> Result<(), MemeError> {
+    let mut shortcuts = get_config_manager()
+        .get_shortcuts()?;
+
+    shortcuts.quick_paste.retain(|b| b.favorite_id != favorite_id);
+    if let Some(shortcut) = shortcut {
+        shortcuts.quick_paste.push(config_manager::QuickPasteBinding {
+            favorite_id,
+            shortcut,
+        });
+    }
+
+    set_shortcuts(shortcuts)?;
+    refresh_shortcuts(app)
+}
+
 // 格式化快捷键显示
 #[cfg(desktop)]
 fn format_shortcut_for_display(
@@ -382,70 +2920,733 @@ fn format_shortcut_for_display(
         parts.push("Meta".to_string());
     }
 
-    // 将Code转换为字符串
-    let key = format!("{:?}", code);
-    // 去除Code::前缀
-    let key = key.replace("Code::", "").replace("Key", "");
-
-    parts.push(key);
+    parts.push(utils::key_map::code_to_display_string(code));
     parts.join("+")
 }
 
+// 全局快捷键是否被暂停，暂停期间 refresh_shortcuts 不会重新注册
+static SHORTCUTS_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+// 设置暂停状态，返回状态是否发生了变化
+fn set_shortcuts_suspended(suspended: bool) -> bool {
+    SHORTCUTS_SUSPENDED.swap(suspended, Ordering::SeqCst) != suspended
+}
+
+// 暂停所有全局快捷键，供设置界面录制新快捷键时使用，避免录制时触发已有快捷键
+#[tauri::command]
+fn suspend_shortcuts(app: tauri::AppHandle) -> Result<(), MemeError> {
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+        suspend_shortcuts_in(app.global_shortcut())
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = app;
+        set_shortcuts_suspended(true);
+        Ok(())
+    }
+}
+
+#[cfg(desktop)]
+fn suspend_shortcuts_in(registry: &impl ShortcutRegistry) -> Result<(), MemeError> {
+    if !set_shortcuts_suspended(true) {
+        debug!("全局快捷键已处于暂停状态");
+        return Ok(());
+    }
+    registry
+        .unregister_all()
+        .map_err(|e| MemeError::Other(format!("注销所有快捷键失败: {}", e)))?;
+    info!("已暂停全局快捷键");
+    Ok(())
+}
+
+// 恢复全局快捷键，按当前配置重新注册
+#[tauri::command]
+fn resume_shortcuts(app: tauri::AppHandle) -> Result<(), MemeError> {
+    set_shortcuts_suspended(false);
+    info!("恢复全局快捷键");
+    refresh_shortcuts(app)
+}
+
 // 添加刷新快捷键的命令
 #[tauri::command]
-fn refresh_shortcuts(app: tauri::AppHandle) -> Result<(), String> {
+fn refresh_shortcuts(app: tauri::AppHandle) -> Result<(), MemeError> {
     #[cfg(desktop)]
     {
         use tauri_plugin_global_shortcut::GlobalShortcutExt;
 
-        // 先清空当前注册的所有快捷键
-        if let Err(e) = app.global_shortcut().unregister_all() {
-            error!("注销所有快捷键失败: {}", e);
+        let config_manager = CONFIG_MANAGER
+            .get()
+            .ok_or_else(|| MemeError::Config("配置管理器未初始化".to_string()))?;
+        refresh_shortcuts_in(app.global_shortcut(), config_manager, &|message| {
+            notify_shortcut_failure(&app, message)
+        })
+    }
+
+    #[cfg(not(desktop))]
+    {
+        let _ = app;
+        Ok(())
+    }
+}
+
+// 清空已注册的快捷键并按配置重新注册，暂停期间不做任何操作
+#[cfg(desktop)]
+fn refresh_shortcuts_in(
+    registry: &impl ShortcutRegistry,
+    config_manager: &ConfigManager,
+    notify: &dyn Fn(String),
+) -> Result<(), MemeError> {
+    if SHORTCUTS_SUSPENDED.load(Ordering::SeqCst) {
+        debug!("全局快捷键已暂停，跳过刷新");
+        return Ok(());
+    }
+
+    if let Err(e) = registry.unregister_all() {
+        error!("注销所有快捷键失败: {}", e);
+    }
+    register_configured_shortcuts(registry, config_manager, notify)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use utils::mock_server::{MockResponse, MockServer};
+    use std::time::{Duration, Instant};
+
+    fn meme(url: &str) -> MemeItem {
+        MemeItem {
+            id: url.to_string(),
+            url: url.to_string(),
+            description: None,
+            score: None,
+            source: None,
+            updated_at: None,
+            path: None,
         }
+    }
 
-        // 从配置中重新注册快捷键
-        if let Some(config_manager) = CONFIG_MANAGER.get() {
-            match config_manager.get_toggle_app_shortcut() {
-                Ok((mods, code)) => {
-                    use tauri_plugin_global_shortcut::Shortcut;
-                    let toggle_shortcut = Shortcut::new(Some(mods), code);
+    fn urls(items: &[MemeItem]) -> Vec<&str> {
+        items.iter().map(|item| item.url.as_str()).collect()
+    }
 
-                    match app.global_shortcut().register(toggle_shortcut) {
-                        Ok(_) => {
-                            info!("成功刷新并注册应用切换快捷键");
-                            Ok(())
-                        }
-                        Err(e) => {
-                            error!("刷新注册快捷键失败: {}", e);
-
-                            // 通知用户注册失败
-                            let main_window = app.get_webview_window("main");
-                            if let Some(window) = main_window {
-                                let _ = window.emit(
-                                    "shortcut-registration-failed",
-                                    format!(
-                                        "无法注册快捷键 {}，请在设置中配置其他快捷键。",
-                                        format_shortcut_for_display(&mods, &code)
-                                    ),
-                                );
-                            }
+    #[test]
+    fn newer_token_supersedes_search() {
+        LATEST_SEARCH_TOKEN.fetch_max(1_000, Ordering::SeqCst);
+        let token = LATEST_SEARCH_TOKEN.load(Ordering::SeqCst);
+        assert!(!search_superseded(Some(token)));
+        assert!(!search_superseded(None));
 
-                            Err(format!("无法注册快捷键: {}", e))
-                        }
+        LATEST_SEARCH_TOKEN.fetch_max(token + 1, Ordering::SeqCst);
+        assert!(search_superseded(Some(token)));
+        assert!(!search_superseded(None));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Write {
+        Text(String),
+        Image(u32, u32),
+    }
+
+    // 记录写入内容的剪贴板
+    #[derive(Default)]
+    struct MockClipboard {
+        writes: RefCell<Vec<Write>>,
+        fail_images: bool,
+    }
+
+    impl ClipboardSink for MockClipboard {
+        fn write_text(&self, text: &str) -> Result<(), MemeError> {
+            self.writes.borrow_mut().push(Write::Text(text.to_string()));
+            Ok(())
+        }
+
+        fn write_image(&self, image: &Image<'_>) -> Result<(), MemeError> {
+            if self.fail_images {
+                return Err(MemeError::Clipboard("剪贴板不可用".to_string()));
+            }
+            self.writes
+                .borrow_mut()
+                .push(Write::Image(image.width(), image.height()));
+            Ok(())
+        }
+    }
+
+    const URL: &str = "https://example.com/full/cat.png";
+
+    fn png(width: u32, height: u32) -> utils::network::DownloadedImage {
+        let mut bytes = Vec::new();
+        image::RgbaImage::new(width, height)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        utils::network::DownloadedImage {
+            bytes,
+            content_type: Some("image/png".to_string()),
+        }
+    }
+
+    // 两帧 1x1 的 GIF 动图
+    fn animated_gif() -> utils::network::DownloadedImage {
+        let mut bytes = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\x00\x00\x00\xff\xff\xff".to_vec();
+        for _ in 0..2 {
+            bytes.extend_from_slice(b"\x21\xf9\x04\x00\x0a\x00\x00\x00");
+            bytes.extend_from_slice(b"\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00");
+        }
+        bytes.push(0x3b);
+        utils::network::DownloadedImage {
+            bytes,
+            content_type: Some("image/gif".to_string()),
+        }
+    }
+
+    fn copy(clipboard: &MockClipboard, mode: ClipboardMode, image: Option<utils::network::DownloadedImage>) {
+        write_meme(clipboard, mode, URL, image.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn url_mode_writes_only_the_url() {
+        let clipboard = MockClipboard::default();
+        copy(&clipboard, ClipboardMode::Url, None);
+        assert_eq!(*clipboard.writes.borrow(), vec![Write::Text(URL.to_string())]);
+    }
+
+    #[test]
+    fn image_mode_writes_the_decoded_image() {
+        let clipboard = MockClipboard::default();
+        copy(&clipboard, ClipboardMode::Image, Some(png(3, 2)));
+        assert_eq!(*clipboard.writes.borrow(), vec![Write::Image(3, 2)]);
+    }
+
+    #[test]
+    fn both_mode_writes_url_then_image() {
+        let clipboard = MockClipboard::default();
+        copy(&clipboard, ClipboardMode::Both, Some(png(3, 2)));
+        assert_eq!(
+            *clipboard.writes.borrow(),
+            vec![Write::Text(URL.to_string()), Write::Image(3, 2)]
+        );
+    }
+
+    #[test]
+    fn both_mode_without_image_writes_url_once() {
+        for image in [None, Some(animated_gif())] {
+            let clipboard = MockClipboard::default();
+            copy(&clipboard, ClipboardMode::Both, image);
+            assert_eq!(*clipboard.writes.borrow(), vec![Write::Text(URL.to_string())]);
+        }
+    }
+
+    #[test]
+    fn image_mode_falls_back_to_the_url() {
+        let undecodable = utils::network::DownloadedImage {
+            bytes: b"not an image".to_vec(),
+            content_type: Some("image/png".to_string()),
+        };
+        for image in [animated_gif(), undecodable] {
+            let clipboard = MockClipboard::default();
+            copy(&clipboard, ClipboardMode::Image, Some(image));
+            assert_eq!(*clipboard.writes.borrow(), vec![Write::Text(URL.to_string())]);
+        }
+
+        let clipboard = MockClipboard {
+            fail_images: true,
+            ..Default::default()
+        };
+        copy(&clipboard, ClipboardMode::Image, Some(png(1, 1)));
+        assert_eq!(*clipboard.writes.borrow(), vec![Write::Text(URL.to_string())]);
+    }
+
+    #[test]
+    fn set_shortcuts_rejects_duplicate_keys() {
+        let shortcut = |input: &str| input.parse::<ShortcutConfig>().unwrap();
+        let shortcuts = ShortcutConfigs {
+            toggle_app: shortcut("Ctrl+Alt+N"),
+            // 修饰键写法与顺序不同，但解析为同一组合
+            copy_last: Some(shortcut("Option+Control+n")),
+            open_search: None,
+            quick_paste: Vec::new(),
+        };
+
+        match set_shortcuts(shortcuts) {
+            Err(MemeError::InvalidInput(message)) => {
+                assert!(message.contains("toggle_app") && message.contains("copy_last"), "{}", message)
+            }
+            other => panic!("expected a conflict error, got {:?}", other),
+        }
+    }
+
+    // 内存中的快捷键注册表
+    #[cfg(desktop)]
+    #[derive(Default)]
+    struct MockRegistry {
+        registered: RefCell<std::collections::HashSet<tauri_plugin_global_shortcut::Shortcut>>,
+        // 被其他程序占用、无法注册的快捷键
+        taken: Vec<tauri_plugin_global_shortcut::Shortcut>,
+    }
+
+    #[cfg(desktop)]
+    impl ShortcutRegistry for MockRegistry {
+        fn is_registered(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> bool {
+            self.registered.borrow().contains(&shortcut)
+        }
+
+        fn register(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> Result<(), String> {
+            if self.taken.contains(&shortcut) || !self.registered.borrow_mut().insert(shortcut) {
+                return Err("已被注册".to_string());
+            }
+            Ok(())
+        }
+
+        fn unregister(&self, shortcut: tauri_plugin_global_shortcut::Shortcut) -> Result<(), String> {
+            self.registered.borrow_mut().remove(&shortcut);
+            Ok(())
+        }
+
+        fn unregister_all(&self) -> Result<(), String> {
+            self.registered.borrow_mut().clear();
+            Ok(())
+        }
+    }
+
+    #[cfg(desktop)]
+    fn global_shortcut(config: &ShortcutConfig) -> tauri_plugin_global_shortcut::Shortcut {
+        let (mods, code) = config.to_tauri_shortcut().unwrap();
+        tauri_plugin_global_shortcut::Shortcut::new(Some(mods), code)
+    }
+
+    #[cfg(desktop)]
+    #[test]
+    fn probe_accepts_free_shortcut_without_keeping_it() {
+        let registry = MockRegistry::default();
+        let config: ShortcutConfig = "Ctrl+Shift+M".parse().unwrap();
+
+        let probe = probe_shortcut_in(&registry, None, &config);
+        assert!(probe.registrable, "{:?}", probe.reason);
+        assert!(!registry.is_registered(global_shortcut(&config)));
+    }
+
+    #[cfg(desktop)]
+    #[test]
+    fn probe_rejects_already_registered_shortcut() {
+        let registry = MockRegistry::default();
+        let config: ShortcutConfig = "Ctrl+Shift+M".parse().unwrap();
+        registry.register(global_shortcut(&config)).unwrap();
+
+        let probe = probe_shortcut_in(&registry, None, &config);
+        assert!(!probe.registrable);
+        assert!(probe.reason.unwrap().contains("已被本应用使用"));
+
+        // 能找到占用者时给出对应的动作名称，且不会注销已有的快捷键
+        let shortcuts = ShortcutConfigs {
+            copy_last: Some(config.clone()),
+            ..Default::default()
+        };
+        let probe = probe_shortcut_in(&registry, Some(&shortcuts), &config);
+        assert!(probe.reason.unwrap().contains("copy_last"));
+        assert!(registry.is_registered(global_shortcut(&config)));
+    }
+
+    #[cfg(desktop)]
+    #[test]
+    fn probe_rejects_shortcut_taken_by_another_program() {
+        let config: ShortcutConfig = "Ctrl+Shift+M".parse().unwrap();
+        let registry = MockRegistry {
+            taken: vec![global_shortcut(&config)],
+            ..Default::default()
+        };
+
+        let probe = probe_shortcut_in(&registry, None, &config);
+        assert!(!probe.registrable);
+        assert!(probe.reason.unwrap().contains("冲突"));
+    }
+
+    fn api(url: &str) -> utils::misc::ApiUrl {
+        serde_json::from_value(serde_json::json!({"name": url, "url": url, "search_method": "post"})).unwrap()
+    }
+
+    #[tokio::test]
+    async fn search_apis_tolerates_one_failing_endpoint() {
+        let failing = MockServer::start(|_| MockResponse::status(500)).await;
+        let working =
+            MockServer::start(|_| MockResponse::json(r#"["https://x/1.png", "https://x/2.png", "https://x/1.png"]"#))
+                .await;
+        let client = Arc::new(MemeServerClient::new(None));
+
+        let (items, failed) = search_apis(client, vec![api(&failing.url), api(&working.url)], "cat", &SearchOptions::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(urls(&items), ["https://x/1.png", "https://x/2.png"]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].url, failing.url);
+        assert_eq!(working.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn search_apis_fails_when_every_endpoint_fails() {
+        let failing = MockServer::start(|_| MockResponse::status(500)).await;
+        let client = Arc::new(MemeServerClient::new(None));
+
+        let result = search_apis(client, vec![api(&failing.url)], "cat", &SearchOptions::default(), None).await;
+        assert!(matches!(result, Err(MemeError::Network(ref message)) if message.contains(&failing.url)));
+    }
+
+    #[tokio::test]
+    async fn search_apis_charges_quota_once_per_search() {
+        let first = MockServer::start(|_| MockResponse::json(r#"["https://x/1.png"]"#)).await;
+        let second = MockServer::start(|_| MockResponse::json(r#"["https://x/2.png"]"#)).await;
+        let client = Arc::new(MemeServerClient::new(None));
+        let used = Arc::new(AtomicU64::new(0));
+        let options = |limit: u64| {
+            let used = Arc::clone(&used);
+            SearchOptions {
+                quota: Some(SearchQuota::new(move || {
+                    if used.fetch_add(1, Ordering::SeqCst) < limit {
+                        Ok(())
+                    } else {
+                        Err("配额已用完".to_string())
                     }
-                }
-                Err(e) => {
-                    error!("获取快捷键配置失败: {}", e);
-                    Err(format!("无法获取快捷键配置: {}", e))
-                }
+                })),
+                ..Default::default()
             }
-        } else {
-            Err("配置管理器未初始化".into())
+        };
+
+        let apis = vec![api(&first.url), api(&second.url)];
+        let (items, _) = search_apis(client.clone(), apis.clone(), "cat", &options(1), None).await.unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(used.load(Ordering::SeqCst), 1);
+
+        // 全部命中缓存时不占用配额，即使配额已用完
+        let (items, _) = search_apis(client.clone(), apis, "cat", &options(0), None).await.unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(used.load(Ordering::SeqCst), 1);
+
+        // 需要发送请求但配额已用完时返回 Locked
+        let result = search_apis(client, vec![api(&first.url)], "dog", &options(0), None).await;
+        assert!(matches!(result, Err(MemeError::Locked(_))), "{:?}", result);
+        assert_eq!(first.requests().len(), 1);
+    }
+
+    fn config_with_apis(dir: &std::path::Path, urls: &[&str]) -> ConfigManager {
+        let config = ConfigManager::open(dir.join("preferences.json"));
+        config
+            .update_preferences(UserPreferences {
+                api_urls: config_manager::ApiUrlConfig {
+                    urls: urls.iter().map(|url| api(url)).collect(),
+                    active_index: 0,
+                    preview_index: None,
+                    full_index: None,
+                },
+                auto_failover: true,
+                ..UserPreferences::default()
+            })
+            .unwrap();
+        config
+    }
+
+    #[tokio::test]
+    async fn fails_over_after_consecutive_failures() {
+        let failing = MockServer::start(|_| MockResponse::status(503)).await;
+        let working = MockServer::start(|_| MockResponse::json(r#"["https://x/1.png"]"#)).await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_with_apis(dir.path(), &[&failing.url, &working.url]);
+        let client = MemeServerClient::new(None);
+        let options = SearchOptions::default();
+
+        for _ in 1..meme_server::FAILOVER_THRESHOLD {
+            let (outcome, failover) = search_with_failover(&config, &client, "cat", &options, None, true).await;
+            assert!(matches!(outcome, Some(Err(_))));
+            assert!(failover.is_none());
         }
+
+        // 达到阈值后切换到第二个API并重试成功
+        let (outcome, failover) = search_with_failover(&config, &client, "cat", &options, None, true).await;
+        let response = outcome.unwrap().unwrap();
+        assert_eq!(response.api_url, working.url);
+        assert_eq!(urls(&response.items), ["https://x/1.png"]);
+        let failover = failover.unwrap();
+        assert_eq!((failover.index, failover.url.as_str()), (1, working.url.as_str()));
+        assert_eq!(config.get_preferences().unwrap().api_urls.active_index, 1);
+        assert_eq!(failing.requests().len(), meme_server::FAILOVER_THRESHOLD as usize);
     }
 
-    #[cfg(not(desktop))]
-    {
-        Ok(())
+    #[tokio::test]
+    async fn does_not_fail_over_when_disabled() {
+        let failing = MockServer::start(|_| MockResponse::status(503)).await;
+        let working = MockServer::start(|_| MockResponse::json("[]")).await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_with_apis(dir.path(), &[&failing.url, &working.url]);
+        let client = MemeServerClient::new(None);
+
+        for _ in 0..meme_server::FAILOVER_THRESHOLD + 1 {
+            let (outcome, failover) =
+                search_with_failover(&config, &client, "cat", &SearchOptions::default(), None, false).await;
+            assert!(matches!(outcome, Some(Err(_))));
+            assert!(failover.is_none());
+        }
+        assert_eq!(config.get_preferences().unwrap().api_urls.active_index, 0);
+        assert!(working.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn probes_reachable_and_unreachable_apis() {
+        let reachable = MockServer::start(|_| MockResponse::status(404)).await;
+        let failing = MockServer::start(|_| MockResponse::status(502)).await;
+        // 绑定后立即释放的端口，连接会被拒绝
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+
+        let apis = vec![api(&reachable.url), api(&closed), api(&failing.url)];
+        let health = probe_apis(apis, Duration::from_secs(3)).await;
+
+        assert_eq!(urls_of(&health), [reachable.url.as_str(), closed.as_str(), failing.url.as_str()]);
+        assert!(health[0].reachable);
+        assert_eq!(health[0].status, Some(404));
+        assert!(health[0].latency_ms.is_some());
+        assert!(!health[1].reachable);
+        assert_eq!((health[1].status, health[1].latency_ms), (None, None));
+        assert!(!health[2].reachable);
+        assert_eq!(health[2].status, Some(502));
+        assert_eq!(reachable.requests()[0].method, "HEAD");
+    }
+
+    fn urls_of(health: &[ApiHealth]) -> Vec<&str> {
+        health.iter().map(|h| h.url.as_str()).collect()
+    }
+
+    #[test]
+    fn merges_fresh_results_before_previous_ones() {
+        let mut previous = HashMap::new();
+        let key = || "api|cat|".to_string();
+
+        let (merged, fresh_count) =
+            merge_and_remember(&mut previous, key(), vec![meme("a"), meme("b")]);
+        assert_eq!((urls(&merged), fresh_count), (vec!["a", "b"], 2));
+
+        let (merged, fresh_count) =
+            merge_and_remember(&mut previous, key(), vec![meme("b"), meme("c")]);
+        assert_eq!((urls(&merged), fresh_count), (vec!["b", "c", "a"], 2));
+
+        // 只记录上一次的本次结果，更早的结果不会一直累积
+        let (merged, fresh_count) = merge_and_remember(&mut previous, key(), vec![meme("d")]);
+        assert_eq!((urls(&merged), fresh_count), (vec!["d", "b", "c"], 1));
+        assert_eq!(urls(&previous[&key()]), ["d"]);
+    }
+
+    #[test]
+    fn previous_results_are_capped() {
+        let mut previous = HashMap::new();
+        for i in 0..PREVIOUS_RESULTS_LIMIT + 5 {
+            merge_and_remember(&mut previous, i.to_string(), vec![meme("a")]);
+        }
+        assert_eq!(previous.len(), PREVIOUS_RESULTS_LIMIT);
+    }
+
+    // 暂停状态是全局的，修改它的测试依次执行
+    static SUSPEND_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn suspending_shortcuts_is_idempotent() {
+        let _guard = SUSPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert!(set_shortcuts_suspended(true));
+        assert!(!set_shortcuts_suspended(true));
+        assert!(SHORTCUTS_SUSPENDED.load(Ordering::SeqCst));
+
+        assert!(set_shortcuts_suspended(false));
+        assert!(!set_shortcuts_suspended(false));
+        assert!(!SHORTCUTS_SUSPENDED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn toggle_hides_visible_window_and_shows_hidden_one() {
+        assert_eq!(window_toggle_for(true), WindowToggle::Hide);
+        assert_eq!(window_toggle_for(false), WindowToggle::Show);
+    }
+
+    #[test]
+    fn first_toggle_is_not_debounced() {
+        assert!(!is_debounced_toggle(None, Instant::now(), TOGGLE_SHORTCUT_DEBOUNCE));
+    }
+
+    #[test]
+    fn toggle_within_interval_is_debounced() {
+        let last = Instant::now();
+        assert!(is_debounced_toggle(Some(last), last, TOGGLE_SHORTCUT_DEBOUNCE));
+        assert!(is_debounced_toggle(
+            Some(last),
+            last + Duration::from_millis(249),
+            TOGGLE_SHORTCUT_DEBOUNCE
+        ));
+    }
+
+    #[test]
+    fn toggle_at_or_after_interval_is_accepted() {
+        let last = Instant::now();
+        assert!(!is_debounced_toggle(
+            Some(last),
+            last + Duration::from_millis(250),
+            TOGGLE_SHORTCUT_DEBOUNCE
+        ));
+        assert!(!is_debounced_toggle(
+            Some(last),
+            last + Duration::from_millis(400),
+            TOGGLE_SHORTCUT_DEBOUNCE
+        ));
+    }
+
+    #[test]
+    fn clock_going_backwards_is_debounced() {
+        // 时间早于上一次记录时按间隔为零处理
+        let now = Instant::now();
+        let last = now + Duration::from_millis(10);
+        assert!(is_debounced_toggle(Some(last), now, TOGGLE_SHORTCUT_DEBOUNCE));
+    }
+
+    #[tokio::test]
+    async fn saves_png_and_infers_extension_from_content_type() {
+        // 内容无法识别格式，扩展名只能来自 Content-Type
+        let server = MockServer::start(|_| MockResponse::bytes("image/png", b"meme bytes".to_vec())).await;
+        let url = format!("{}/meme", server.url);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("meme.jpg");
+
+        let saved = save_meme_to_file(url, dest.to_string_lossy().to_string())
+            .await
+            .unwrap();
+        let saved = std::path::PathBuf::from(saved);
+        assert_eq!(saved, dir.path().join("meme.png"));
+        assert_eq!(std::fs::read(&saved).unwrap(), b"meme bytes");
+
+        // 不覆盖已存在的文件
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        let server = MockServer::start(move |_| MockResponse::bytes("image/png", png.to_vec())).await;
+        let again = save_meme_to_file(format!("{}/meme", server.url), dest.to_string_lossy().to_string())
+            .await
+            .unwrap();
+        assert_eq!(std::path::PathBuf::from(again), dir.path().join("meme (1).png"));
+        assert_eq!(std::fs::read(dir.path().join("meme (1).png")).unwrap(), png);
+    }
+
+    // 记录自动粘贴各步骤的执行顺序
+    async fn paste_steps(
+        copy_result: Result<(), MemeError>,
+        synthetic_paste: bool,
+    ) -> (Result<(), MemeError>, Vec<&'static str>) {
+        let steps = RefCell::new(Vec::new());
+        let result = run_paste_steps(
+            async {
+                steps.borrow_mut().push("copy");
+                copy_result
+            },
+            synthetic_paste,
+            || steps.borrow_mut().push("hide"),
+            Duration::ZERO,
+            async {
+                steps.borrow_mut().push("paste");
+                Ok(())
+            },
+        )
+        .await;
+        (result, steps.into_inner())
+    }
+
+    #[tokio::test]
+    async fn paste_copies_then_hides_then_pastes() {
+        let (result, steps) = paste_steps(Ok(()), true).await;
+        assert!(result.is_ok());
+        assert_eq!(steps, ["copy", "hide", "paste"]);
+    }
+
+    #[tokio::test]
+    async fn paste_stops_after_copy_when_disabled_or_failed() {
+        let (result, steps) = paste_steps(Ok(()), false).await;
+        assert!(result.is_ok());
+        assert_eq!(steps, ["copy"]);
+
+        let (result, steps) = paste_steps(Err(MemeError::Clipboard("失败".to_string())), true).await;
+        assert!(matches!(result, Err(MemeError::Clipboard(_))));
+        assert_eq!(steps, ["copy"]);
+    }
+
+    #[tokio::test]
+    async fn search_complete_payload_describes_the_search() {
+        let server = MockServer::start(|_| MockResponse::json(r#"["https://x/1.png", "https://x/2.png"]"#)).await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_with_apis(dir.path(), &[&server.url]);
+        let client = MemeServerClient::new(None);
+
+        // 与 perform_search 相同，由搜索响应得到结果
+        let search = || async {
+            let (outcome, _) =
+                search_with_failover(&config, &client, "cat", &SearchOptions::default(), None, false).await;
+            let response = outcome.unwrap().unwrap();
+            let mut result = SearchResult::new(response.items);
+            result.from_cache = response.from_cache;
+            result.api_url = Some(response.api_url);
+            result
+        };
+
+        let result = search().await;
+        let payload = serde_json::to_value(SearchComplete::new("cat", &result, Duration::from_millis(42))).unwrap();
+        assert_eq!(payload["keyword"], "cat");
+        assert_eq!(payload["status"], serde_json::to_value(result.status).unwrap());
+        assert_eq!(payload["count"], 2);
+        assert_eq!(payload["elapsed_ms"], 42);
+        assert_eq!(payload["from_cache"], false);
+        assert_eq!(payload["api_url"], server.url.as_str());
+        assert_eq!(payload["blocked_count"], 0);
+
+        // 第二次搜索命中缓存
+        let cached = search().await;
+        let payload = serde_json::to_value(SearchComplete::new("cat", &cached, Duration::ZERO)).unwrap();
+        assert_eq!(payload["from_cache"], true);
+        assert_eq!(payload["count"], 2);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[cfg(desktop)]
+    #[test]
+    fn suspend_unregisters_and_resume_restores_shortcuts() {
+        let _guard = SUSPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let config = ConfigManager::open(dir.path().join("preferences.json"));
+        let copy_last: ShortcutConfig = "Ctrl+Shift+L".parse().unwrap();
+        config
+            .update_shortcuts(ShortcutConfigs {
+                toggle_app: "Ctrl+Alt+N".parse().unwrap(),
+                copy_last: Some(copy_last.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let shortcuts = config.get_shortcuts().unwrap();
+        let configured = [global_shortcut(&shortcuts.toggle_app), global_shortcut(&copy_last)];
+
+        let registry = MockRegistry::default();
+        refresh_shortcuts_in(&registry, &config, &|message| panic!("{}", message)).unwrap();
+        assert!(configured.iter().all(|&shortcut| registry.is_registered(shortcut)));
+
+        suspend_shortcuts_in(&registry).unwrap();
+        assert!(registry.registered.borrow().is_empty());
+        // 暂停期间刷新不会重新注册
+        refresh_shortcuts_in(&registry, &config, &|message| panic!("{}", message)).unwrap();
+        assert!(registry.registered.borrow().is_empty());
+
+        // 与 resume_shortcuts 相同：解除暂停后按配置重新注册
+        set_shortcuts_suspended(false);
+        refresh_shortcuts_in(&registry, &config, &|message| panic!("{}", message)).unwrap();
+        assert_eq!(registry.registered.borrow().len(), 2);
+        assert!(configured.iter().all(|&shortcut| registry.is_registered(shortcut)));
+    }
+
+    #[test]
+    fn meme_qr_rejects_invalid_urls_as_invalid_input() {
+        for url in ["not a url", "ftp://example.com/a.png"] {
+            assert!(matches!(get_meme_qr(url.to_string()), Err(MemeError::InvalidInput(_))), "{}", url);
+        }
+        assert!(get_meme_qr("https://example.com/a.png".to_string()).is_ok());
     }
 }
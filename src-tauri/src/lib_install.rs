@@ -0,0 +1,614 @@
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest;
+
+use crate::error::MemeError;
+use crate::meme_community::{
+    get_enabled_libs_path, get_meme_lib, load_enabled_libs_from, local_libs_dir, meme_packs_dir,
+    parse_lib_listing, save_enabled_libs_to,
+};
+use crate::utils::misc::{app_dirs, write_atomic};
+use crate::utils::network::{download_bytes_with_progress, download_image, image_extension};
+
+// 已安装到本地的表情库记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledLib {
+    pub uuid: String,
+    pub name: String,
+    pub version: String,
+    // 安装目录
+    pub path: String,
+    // 安装的图片数量
+    pub file_count: usize,
+    // 安装时间（Unix秒）
+    pub installed_at: u64,
+}
+
+// 本地安装记录，以UUID为键
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstalledLibs {
+    #[serde(default)]
+    pub libs: HashMap<String, InstalledLib>,
+}
+
+// 串行化安装记录的读-改-写
+static INSTALLED_LOCK: Mutex<()> = Mutex::new(());
+
+fn installed_libs_path() -> PathBuf {
+    app_dirs().config_dir().join("installed_libs.json")
+}
+
+// 加载安装记录，文件不存在时返回空记录
+pub fn load_installed_libs() -> Result<InstalledLibs, String> {
+    load_installed_libs_from(&installed_libs_path())
+}
+
+fn load_installed_libs_from(file_path: &Path) -> Result<InstalledLibs, String> {
+    if !file_path.exists() {
+        return Ok(InstalledLibs::default());
+    }
+
+    let content =
+        fs::read_to_string(file_path).map_err(|e| format!("读取安装记录失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析安装记录失败: {}", e))
+}
+
+fn save_installed_libs(installed: &InstalledLibs) -> Result<(), String> {
+    save_installed_libs_to(&installed_libs_path(), installed)
+}
+
+fn save_installed_libs_to(file_path: &Path, installed: &InstalledLibs) -> Result<(), String> {
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(installed)
+        .map_err(|e| format!("序列化安装记录失败: {}", e))?;
+    write_atomic(file_path, json.as_bytes()).map_err(|e| format!("保存安装记录失败: {}", e))
+}
+
+fn record_install(record: InstalledLib) -> Result<(), String> {
+    let _guard = INSTALLED_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut installed = load_installed_libs()?;
+    installed.libs.insert(record.uuid.clone(), record);
+    save_installed_libs(&installed)
+}
+
+// 校验表情库UUID，UUID会作为目录名使用，拒绝可能跳出缓存目录的值
+fn validate_lib_uuid(uuid: &str) -> Result<(), String> {
+    if uuid.trim().is_empty() || uuid.contains(['/', '\\', '.']) {
+        return Err(format!("无效的表情库UUID: {}", uuid));
+    }
+    Ok(())
+}
+
+// 计算数据的SHA-256，返回小写十六进制字符串
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// 校验资源包内容与清单中的校验值是否一致，清单未提供校验值时跳过
+fn verify_checksum(uuid: &str, bytes: &[u8], expected: Option<&str>) -> Result<(), String> {
+    let expected = match expected.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(expected) => expected.to_lowercase(),
+        None => {
+            warn!("清单未提供表情库 {} 的校验值，跳过校验", uuid);
+            return Ok(());
+        }
+    };
+
+    let actual = sha256_hex(bytes);
+    if actual != expected {
+        return Err(format!(
+            "资源包校验失败: 期望 {}，实际 {}",
+            expected, actual
+        ));
+    }
+    debug!("表情库 {} 校验通过", uuid);
+    Ok(())
+}
+
+// 资源包的格式
+#[derive(Debug, PartialEq, Eq)]
+enum PackFormat {
+    TarGz,
+    Tar,
+    // 列出图片地址的JSON清单
+    Listing(serde_json::Value),
+    // 单张图片
+    Image(&'static str),
+}
+
+// 根据文件头识别资源包格式
+fn detect_pack_format(bytes: &[u8]) -> Option<PackFormat> {
+    if bytes.starts_with(&[0x1F, 0x8B]) {
+        return Some(PackFormat::TarGz);
+    }
+    if bytes.len() > 262 && &bytes[257..262] == b"ustar" {
+        return Some(PackFormat::Tar);
+    }
+    if let Some(ext) = image_extension(None, bytes) {
+        return Some(PackFormat::Image(ext));
+    }
+    serde_json::from_slice::<serde_json::Value>(bytes)
+        .ok()
+        .map(PackFormat::Listing)
+}
+
+// 把资源包解压或下载到目标目录
+async fn unpack_into(format: PackFormat, bytes: Vec<u8>, pack_url: &str, dir: &Path) -> Result<(), String> {
+    match format {
+        PackFormat::TarGz => {
+            let dir = dir.to_path_buf();
+            tokio::task::spawn_blocking(move || {
+                let decoder = flate2::read::GzDecoder::new(Cursor::new(bytes));
+                tar::Archive::new(decoder).unpack(&dir)
+            })
+            .await
+            .map_err(|e| format!("解压资源包失败: {}", e))?
+            .map_err(|e| format!("解压资源包失败: {}", e))
+        }
+        PackFormat::Tar => {
+            let dir = dir.to_path_buf();
+            tokio::task::spawn_blocking(move || tar::Archive::new(Cursor::new(bytes)).unpack(&dir))
+                .await
+                .map_err(|e| format!("解压资源包失败: {}", e))?
+                .map_err(|e| format!("解压资源包失败: {}", e))
+        }
+        PackFormat::Image(ext) => fs::write(dir.join(format!("0.{}", ext)), bytes)
+            .map_err(|e| format!("写入图片失败: {}", e)),
+        PackFormat::Listing(listing) => {
+            let base = reqwest::Url::parse(pack_url).map_err(|e| format!("表情库地址无效: {}", e))?;
+            let urls = parse_lib_listing(&listing, &base)
+                .ok_or_else(|| "不支持的资源包格式".to_string())?;
+
+            for (index, url) in urls.iter().enumerate() {
+                match download_image(url).await {
+                    Ok(image) => {
+                        let ext = image_extension(image.content_type.as_deref(), &image.bytes)
+                            .unwrap_or("png");
+                        fs::write(dir.join(format!("{}.{}", index, ext)), &image.bytes)
+                            .map_err(|e| format!("写入图片失败: {}", e))?;
+                    }
+                    // 个别图片失效不影响整个表情库的安装
+                    Err(e) => warn!("下载表情库图片失败: {} - {}", url, e),
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+// 统计目录下的图片数量
+fn count_images(dir: &Path) -> usize {
+    crate::meme_community::list_local_memes(
+        dir.parent().unwrap_or(dir),
+        &[dir.file_name().unwrap_or_default().to_string_lossy().into_owned()],
+    )
+    .len()
+}
+
+// 下载并安装表情库到本地，已安装时覆盖旧版本
+//
+// 先解压到临时目录，成功后再替换安装目录，失败时保留旧版本。
+// 下载过程中向前端发送 download-progress 事件，返回安装目录。
+#[tauri::command]
+pub async fn download_meme_lib(app: AppHandle, uuid: String) -> Result<String, MemeError> {
+    info!("接收到安装表情库请求: {}", uuid);
+    validate_lib_uuid(&uuid).map_err(MemeError::InvalidInput)?;
+    let lib = get_meme_lib(uuid.clone())
+        .ok_or_else(|| MemeError::NotFound(format!("未找到表情库: {}", uuid)))?;
+    let pack_url = lib.url.trim().to_string();
+    if pack_url.is_empty() {
+        return Err(MemeError::NotFound(format!("表情库未提供下载地址: {}", uuid)));
+    }
+
+    let bytes = download_bytes_with_progress([pack_url.as_str()], |progress| {
+        if let Err(e) = app.emit("download-progress", &progress) {
+            debug!("发送下载进度事件失败: {}", e);
+        }
+    })
+    .await
+    .map_err(MemeError::Network)?;
+    if bytes.is_empty() {
+        return Err(MemeError::Network("下载的资源包为空".to_string()));
+    }
+    // 资源包来自任意社区地址，解压前先校验，不一致时不写入任何文件
+    verify_checksum(&uuid, &bytes, lib.sha256.as_deref()).map_err(MemeError::Parse)?;
+
+    let format = detect_pack_format(&bytes)
+        .ok_or_else(|| MemeError::Parse("不支持的资源包格式".to_string()))?;
+
+    let libs_dir = local_libs_dir();
+    let install_dir = libs_dir.join(&uuid);
+    let staging_dir = libs_dir.join(format!("{}.tmp", uuid));
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir).map_err(|e| MemeError::io("清理临时目录失败", e))?;
+    }
+    fs::create_dir_all(&staging_dir).map_err(|e| MemeError::io("创建临时目录失败", e))?;
+
+    if let Err(e) = unpack_into(format, bytes, &pack_url, &staging_dir).await {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(MemeError::Parse(e));
+    }
+
+    let file_count = count_images(&staging_dir);
+    if file_count == 0 {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(MemeError::Parse("资源包中没有图片".to_string()));
+    }
+
+    // 覆盖已安装的旧版本
+    if install_dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&install_dir) {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(MemeError::io("删除旧版本失败", e));
+        }
+    }
+    fs::rename(&staging_dir, &install_dir).map_err(|e| MemeError::io("移动安装目录失败", e))?;
+
+    let path = install_dir.to_string_lossy().into_owned();
+    let installed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Err(e) = record_install(InstalledLib {
+        uuid: uuid.clone(),
+        name: lib.name,
+        version: lib.version,
+        path: path.clone(),
+        file_count,
+        installed_at,
+    }) {
+        error!("记录表情库安装信息失败: {}", e);
+    }
+
+    info!("表情库 {} 已安装到: {}（{} 张图片）", uuid, path, file_count);
+    Ok(path)
+}
+
+// 获取已安装到本地的表情库
+#[tauri::command]
+pub fn get_installed_libs() -> Result<Vec<InstalledLib>, String> {
+    let mut libs: Vec<InstalledLib> = load_installed_libs()?.libs.into_values().collect();
+    libs.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+    Ok(libs)
+}
+
+// 本地表情库缓存的磁盘占用
+#[derive(Debug, Default, Serialize)]
+pub struct CacheUsage {
+    pub total_bytes: u64,
+    // 每个表情库占用的字节数，包括安装目录与下载的资源包
+    pub per_lib: HashMap<String, u64>,
+}
+
+// 递归统计目录或文件的大小
+fn path_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| path_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+// 从安装目录或资源包文件名中取出表情库UUID
+fn lib_uuid_of(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let uuid = if path.is_dir() {
+        name.strip_suffix(".tmp").unwrap_or(name)
+    } else {
+        name.split('.').next().unwrap_or(name)
+    };
+    (!uuid.is_empty()).then(|| uuid.to_string())
+}
+
+// 统计 libs 与 packs 目录的占用
+pub fn cache_usage_in(dirs: &[PathBuf]) -> CacheUsage {
+    let mut usage = CacheUsage::default();
+    for dir in dirs {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(uuid) = lib_uuid_of(&path) else { continue };
+            let size = path_size(&path);
+            usage.total_bytes += size;
+            *usage.per_lib.entry(uuid).or_insert(0) += size;
+        }
+    }
+    usage
+}
+
+// 获取本地表情库缓存的磁盘占用
+#[tauri::command]
+pub async fn get_cache_usage() -> Result<CacheUsage, String> {
+    tokio::task::spawn_blocking(|| cache_usage_in(&[local_libs_dir(), meme_packs_dir()]))
+        .await
+        .map_err(|e| format!("统计缓存占用失败: {}", e))
+}
+
+// 表情库缓存涉及的目录与记录文件，测试时可指向临时目录
+struct LibCache {
+    // libs 与 packs 目录
+    dirs: Vec<PathBuf>,
+    enabled_libs_path: PathBuf,
+    installed_libs_path: PathBuf,
+}
+
+impl LibCache {
+    fn current() -> Result<Self, String> {
+        Ok(Self {
+            dirs: vec![local_libs_dir(), meme_packs_dir()],
+            enabled_libs_path: get_enabled_libs_path()?,
+            installed_libs_path: installed_libs_path(),
+        })
+    }
+
+    // 删除指定表情库在各缓存目录中的所有文件
+    fn remove_lib_files(&self, uuid: &str) -> Result<(), String> {
+        for dir in &self.dirs {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if lib_uuid_of(&path).as_deref() != Some(uuid) {
+                    continue;
+                }
+                let removed = if path.is_dir() {
+                    fs::remove_dir_all(&path)
+                } else {
+                    fs::remove_file(&path)
+                };
+                removed.map_err(|e| format!("删除缓存失败: {:?} - {}", path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    // 从启用列表中移除给定的表情库
+    fn disable_libs(&self, uuids: &[String]) -> Result<(), String> {
+        let mut enabled = load_enabled_libs_from(&self.enabled_libs_path)?;
+        let before = enabled.enabled_libs.len();
+        enabled.enabled_libs.retain(|uuid| !uuids.contains(uuid));
+        if enabled.enabled_libs.len() != before {
+            save_enabled_libs_to(&self.enabled_libs_path, &enabled)?;
+        }
+        Ok(())
+    }
+
+    fn prune(&self, uuid: &str) -> Result<(), String> {
+        self.remove_lib_files(uuid)?;
+        self.disable_libs(&[uuid.to_string()])?;
+
+        let _guard = INSTALLED_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut installed = load_installed_libs_from(&self.installed_libs_path)?;
+        if installed.libs.remove(uuid).is_some() {
+            save_installed_libs_to(&self.installed_libs_path, &installed)?;
+        }
+        Ok(())
+    }
+
+    fn clear_all(&self) -> Result<usize, String> {
+        let usage = cache_usage_in(&self.dirs);
+        let _guard = INSTALLED_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let installed = load_installed_libs_from(&self.installed_libs_path)?;
+
+        let mut uuids: Vec<String> = usage.per_lib.into_keys().collect();
+        uuids.extend(installed.libs.into_keys());
+        uuids.sort();
+        uuids.dedup();
+
+        for dir in &self.dirs {
+            if dir.exists() {
+                fs::remove_dir_all(dir).map_err(|e| format!("删除缓存失败: {:?} - {}", dir, e))?;
+            }
+        }
+
+        self.disable_libs(&uuids)?;
+        save_installed_libs_to(&self.installed_libs_path, &InstalledLibs::default())?;
+        Ok(uuids.len())
+    }
+}
+
+// 删除本地缓存的表情库并禁用它
+#[tauri::command]
+pub fn prune_meme_lib(uuid: String) -> Result<(), String> {
+    validate_lib_uuid(&uuid)?;
+
+    LibCache::current()?.prune(&uuid)?;
+    info!("已删除表情库缓存: {}", uuid);
+    Ok(())
+}
+
+// 删除所有本地缓存的表情库，并禁用这些表情库，返回删除的表情库数量
+#[tauri::command]
+pub fn clear_all_cached_libs() -> Result<usize, String> {
+    let count = LibCache::current()?.clear_all()?;
+    info!("已清空表情库缓存，共 {} 个表情库", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meme_community::EnabledMemeLibs;
+    use std::io::Write;
+
+    // 构造包含一张PNG图片的 tar 包
+    fn tar_with_image() -> Vec<u8> {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0];
+        let mut header = tar::Header::new_gnu();
+        header.set_size(png.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append_data(&mut header, "memes/cat.png", &png[..]).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn rejects_uuids_that_escape_the_libs_dir() {
+        for uuid in ["", " ", "..", "../..", "a/b", "a\\b", "lib.tmp"] {
+            assert!(validate_lib_uuid(uuid).is_err(), "{:?} 应被拒绝", uuid);
+        }
+        assert!(validate_lib_uuid("3f2b6c1e-8d4a-4f0e-9b7a-2c5d1e6f7a8b").is_ok());
+    }
+
+    // "abc" 的 SHA-256
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn checksum_matches() {
+        assert_eq!(sha256_hex(b"abc"), ABC_SHA256);
+        assert!(verify_checksum("lib", b"abc", Some(ABC_SHA256)).is_ok());
+        // 忽略大小写与首尾空白
+        let upper = format!("  {}\n", ABC_SHA256.to_uppercase());
+        assert!(verify_checksum("lib", b"abc", Some(&upper)).is_ok());
+    }
+
+    #[test]
+    fn checksum_mismatch_is_rejected() {
+        let err = verify_checksum("lib", b"abd", Some(ABC_SHA256)).unwrap_err();
+        assert!(err.contains(ABC_SHA256));
+    }
+
+    #[test]
+    fn missing_checksum_skips_verification() {
+        assert!(verify_checksum("lib", b"abc", None).is_ok());
+        assert!(verify_checksum("lib", b"abc", Some(" ")).is_ok());
+    }
+
+    #[test]
+    fn detects_pack_formats() {
+        let tar = tar_with_image();
+        assert_eq!(detect_pack_format(&tar), Some(PackFormat::Tar));
+        assert_eq!(detect_pack_format(&gzip(&tar)), Some(PackFormat::TarGz));
+        assert_eq!(
+            detect_pack_format(br#"["a.png"]"#),
+            Some(PackFormat::Listing(serde_json::json!(["a.png"])))
+        );
+        assert_eq!(detect_pack_format(b"not a pack"), None);
+    }
+
+    #[tokio::test]
+    async fn extracts_tar_gz_into_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib_dir = dir.path().join("lib");
+        fs::create_dir_all(&lib_dir).unwrap();
+
+        let bytes = gzip(&tar_with_image());
+        unpack_into(PackFormat::TarGz, bytes, "https://example.com/lib.tar.gz", &lib_dir)
+            .await
+            .unwrap();
+
+        assert!(lib_dir.join("memes/cat.png").is_file());
+        assert_eq!(count_images(&lib_dir), 1);
+    }
+
+    #[tokio::test]
+    async fn extracting_corrupt_archive_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut bytes = gzip(&tar_with_image());
+        bytes.truncate(bytes.len() / 2);
+        let result = unpack_into(PackFormat::TarGz, bytes, "https://example.com/lib.tar.gz", dir.path()).await;
+        assert!(result.is_err());
+    }
+
+    fn write_file(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; len]).unwrap();
+    }
+
+    // libs 与 packs 目录中各放入几个表情库的文件
+    fn seeded_cache(root: &Path) -> LibCache {
+        let libs = root.join("libs");
+        let packs = root.join("packs");
+        write_file(&libs.join("lib-a/cat.png"), 100);
+        write_file(&libs.join("lib-a/memes/dog.png"), 50);
+        write_file(&libs.join("lib-b.tmp/half.png"), 10);
+        write_file(&packs.join("lib-a.tar.gz"), 30);
+        write_file(&packs.join("lib-c.zip"), 7);
+        LibCache {
+            dirs: vec![libs, packs],
+            enabled_libs_path: root.join("enabled_libs.json"),
+            installed_libs_path: root.join("installed_libs.json"),
+        }
+    }
+
+    #[test]
+    fn reports_cache_usage_per_lib() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = seeded_cache(dir.path());
+
+        let usage = cache_usage_in(&cache.dirs);
+        assert_eq!(usage.total_bytes, 197);
+        assert_eq!(usage.per_lib.len(), 3);
+        assert_eq!(usage.per_lib["lib-a"], 180);
+        // 未完成的安装目录也计入对应的表情库
+        assert_eq!(usage.per_lib["lib-b"], 10);
+        assert_eq!(usage.per_lib["lib-c"], 7);
+    }
+
+    #[test]
+    fn pruning_removes_files_and_enabled_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = seeded_cache(dir.path());
+        let enabled = EnabledMemeLibs {
+            enabled_libs: ["lib-a", "lib-c"].iter().map(|uuid| uuid.to_string()).collect(),
+        };
+        save_enabled_libs_to(&cache.enabled_libs_path, &enabled).unwrap();
+        let mut installed = InstalledLibs::default();
+        installed.libs.insert(
+            "lib-a".to_string(),
+            InstalledLib {
+                uuid: "lib-a".to_string(),
+                name: "A".to_string(),
+                version: "1".to_string(),
+                path: cache.dirs[0].join("lib-a").to_string_lossy().to_string(),
+                file_count: 2,
+                installed_at: 0,
+            },
+        );
+        save_installed_libs_to(&cache.installed_libs_path, &installed).unwrap();
+
+        cache.prune("lib-a").unwrap();
+
+        assert!(!cache.dirs[0].join("lib-a").exists());
+        assert!(!cache.dirs[1].join("lib-a.tar.gz").exists());
+        assert!(cache.dirs[1].join("lib-c.zip").exists());
+        let usage = cache_usage_in(&cache.dirs);
+        assert!(!usage.per_lib.contains_key("lib-a"));
+        assert_eq!(usage.total_bytes, 17);
+
+        let enabled = load_enabled_libs_from(&cache.enabled_libs_path).unwrap();
+        assert_eq!(enabled.enabled_libs.into_iter().collect::<Vec<_>>(), ["lib-c"]);
+        assert!(load_installed_libs_from(&cache.installed_libs_path).unwrap().libs.is_empty());
+    }
+}
@@ -2,14 +2,25 @@ use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 use tauri::utils::acl::manifest;
 use std::collections::{HashMap, HashSet};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
 use tauri_plugin_http::reqwest;
 use tauri_plugin_http::reqwest::{Client, Error, Method, Request, RequestBuilder, StatusCode};
 
 
-use crate::utils::network::download_with_fallback_urls;
-use crate::utils::misc::{ApiUrl, ApiServerUrlsConfig};
+use crate::utils::network::{
+    download_outcome_with_fallback_urls, download_with_fallback_urls, fetch_content_type,
+    DownloadMode, TimeoutGrowth, TimeoutPolicy,
+};
+use crate::utils::misc::{app_dirs, asset_url, write_atomic, ApiUrl, ApiServerUrlsConfig, RequestFormat, SearchMethod};
+use crate::utils::recent_errors;
+use crate::error::MemeError;
+use crate::meme_server::MemeItem;
+use crate::favorites::TagMatchMode;
 
 // 定义manifest.json的数据结构
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,8 +31,10 @@ pub struct CommunityManifest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CommunityInfo {
-    // pub resource_url: String,
-    // pub update_url: String,
+    #[serde(default)]
+    pub resource_url: String,
+    #[serde(default)]
+    pub update_url: String,
     pub timestamp: u64,
 }
 
@@ -37,6 +50,9 @@ pub struct MemeLib {
     pub url: String,
     pub update_url: String,
     pub uuid: String,
+    // 资源包的SHA-256校验值（十六进制），旧版清单没有该字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
 }
 
 // 添加表情包库启用状态配置结构
@@ -54,28 +70,55 @@ impl Default for EnabledMemeLibs {
 }
 
 // 定义下载manifest的函数
-pub async fn download_community_manifest() -> Result<CommunityManifest, String> {
+pub async fn download_community_manifest() -> Result<CommunityManifest, MemeError> {
+    let (manifest, manifest_text) = fetch_remote_manifest().await?;
+
+    // 将manifest保存到缓存目录
+    if let Err(e) = save_manifest_to_cache(&manifest_text) {
+        error!("保存社区表情库清单到缓存失败: {}", e);
+        // 这里我们只记录错误，但不中断流程，因为我们已经有了内存中的数据
+        debug!("将继续使用内存中的数据而不保存到缓存");
+    }
+
+    info!("社区表情库清单下载成功，包含 {} 个表情库", manifest.meme_libs.len());
+    Ok(manifest)
+}
+
+// 下载并解析远程清单，不写入缓存，同时返回原始文本
+async fn fetch_remote_manifest() -> Result<(CommunityManifest, String), MemeError> {
     // const MANIFEST_URLS: [&str; 2] = 
     // ["https://github.com/MemeMeow-Studio/Memes-Community/raw/main/community_manifest.json",
     //  "https://gitee.com/infstellar/Memes-Community/raw/main/community_manifest.json"];
     let manifest_url = match crate::get_config_manager().get_active_api_url() {
             Ok(url) => url+"/libs_manifest",
-            Err(_) => {
-                // error!("错误：{_} 获取API URL失败，使用默认值");
-                panic!("获取API URL失败，使用默认值")
-            }, // 如果获取失败，则使用默认值
+            Err(e) => {
+                error!("获取API URL失败: {}", e);
+                return Err(MemeError::Config(format!("获取API URL失败: {}", e)));
+            }
         };
-    info!("开始下载社区表情库清单");
+    // 清单使用独立的超时设置，避免被屏蔽的源长时间挂起
+    let (manifest_timeout, mode) = crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| (prefs.manifest_timeout_secs, prefs.download_mode))
+        .unwrap_or((15, DownloadMode::default()));
+    let policy = TimeoutPolicy {
+        initial_secs: manifest_timeout,
+        max_secs: manifest_timeout,
+        growth: TimeoutGrowth::Fixed,
+        max_rounds: 1,
+    };
+
+    info!("开始下载社区表情库清单，超时{}秒", manifest_timeout);
 
     // 下载manifest文件
-    let manifest_text = match download_with_fallback_urls([manifest_url]).await {
-        Ok(text) => {
-            debug!("下载社区表情库清单成功");
-            text
+    let manifest_text = match download_outcome_with_fallback_urls([manifest_url], policy, mode).await {
+        Ok(outcome) => {
+            debug!("下载社区表情库清单成功: {}，耗时 {:?}", outcome.url, outcome.elapsed);
+            outcome.text()
         }
         Err(e) => {
             error!("下载社区表情库清单失败: {}", e);
-            return Err(format!("下载失败: {}", e));
+            return Err(MemeError::Network(format!("下载失败: {}", e)));
         }
     };
 
@@ -83,62 +126,177 @@ pub async fn download_community_manifest() -> Result<CommunityManifest, String>
         Ok(data) => data,
         Err(e) => {
             error!("解析社区表情库清单JSON失败: {}", e);
-            return Err(format!("解析JSON失败: {}", e));
+            return Err(MemeError::Parse(format!("解析JSON失败: {}", e)));
         }
     };
 
-    // 将manifest保存到缓存目录
-    if let Err(e) = save_manifest_to_cache(&manifest_text) {
-        error!("保存社区表情库清单到缓存失败: {}", e);
-        // 这里我们只记录错误，但不中断流程，因为我们已经有了内存中的数据
-        debug!("将继续使用内存中的数据而不保存到缓存");
+    Ok((manifest, manifest_text))
+}
+
+// 缓存的清单与远程清单之间的差异，均为表情库UUID
+#[derive(Debug, Default, Serialize, Clone, PartialEq, Eq)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+// 以表情库的 uuid 字段为准建立索引，字段为空时使用清单中的键
+fn libs_by_uuid(manifest: &CommunityManifest) -> HashMap<&str, &MemeLib> {
+    manifest
+        .meme_libs
+        .iter()
+        .map(|(key, lib)| {
+            let uuid = if lib.uuid.is_empty() { key.as_str() } else { lib.uuid.as_str() };
+            (uuid, lib)
+        })
+        .collect()
+}
+
+// 比较两份清单，版本号或时间戳变化的表情库视为已更新
+pub fn diff_manifests(cached: &CommunityManifest, remote: &CommunityManifest) -> ManifestDiff {
+    let cached_libs = libs_by_uuid(cached);
+    let remote_libs = libs_by_uuid(remote);
+
+    let mut diff = ManifestDiff::default();
+    for (uuid, lib) in &remote_libs {
+        match cached_libs.get(uuid) {
+            None => diff.added.push(uuid.to_string()),
+            Some(old) if old.version != lib.version || old.timestamp != lib.timestamp => {
+                diff.updated.push(uuid.to_string())
+            }
+            Some(_) => {}
+        }
     }
+    diff.removed = cached_libs
+        .keys()
+        .filter(|uuid| !remote_libs.contains_key(*uuid))
+        .map(|uuid| uuid.to_string())
+        .collect();
 
-    info!("社区表情库清单下载成功，包含 {} 个表情库", manifest.meme_libs.len());
-    Ok(manifest)
+    diff.added.sort();
+    diff.updated.sort();
+    diff.removed.sort();
+    diff
 }
 
-// 保存manifest到缓存
-fn save_manifest_to_cache(content: &str) -> Result<PathBuf, String> {
-    // 使用 dirs 库获取缓存目录
-    let cache_dir = match dirs::cache_dir() {
-        Some(dir) => dir,
-        None => {
-            return Err("无法获取系统缓存目录".to_string());
+// 检查社区清单是否有更新，不会替换缓存
+//
+// 没有缓存时所有远程表情库都视为新增。
+#[tauri::command]
+pub async fn check_manifest_updates() -> Result<ManifestDiff, MemeError> {
+    info!("接收到检查社区清单更新请求");
+    let (remote, _) = fetch_remote_manifest().await?;
+    let cached = match load_manifest_from_cache() {
+        Ok(cached) => cached,
+        Err(e) => {
+            debug!("读取缓存的清单失败: {}，所有表情库视为新增", e);
+            CommunityManifest {
+                community_info: CommunityInfo {
+                    resource_url: String::new(),
+                    update_url: String::new(),
+                    timestamp: 0,
+                },
+                meme_libs: HashMap::new(),
+            }
         }
     };
 
-    // 创建MemeMeow子目录
-    let meme_cache_dir = cache_dir.join("MemeMeow");
-    if !meme_cache_dir.exists() {
-        if let Err(e) = fs::create_dir_all(&meme_cache_dir) {
+    let diff = diff_manifests(&cached, &remote);
+    info!(
+        "社区清单更新: 新增 {} 个，更新 {} 个，移除 {} 个",
+        diff.added.len(),
+        diff.updated.len(),
+        diff.removed.len()
+    );
+    Ok(diff)
+}
+
+// 保存manifest到缓存
+fn save_manifest_to_cache(content: &str) -> Result<PathBuf, String> {
+    save_manifest_in(&app_dirs().cache_dir(), content, unix_now())
+}
+
+// 将清单与下载时间写入 `dir`，两个文件都以原子方式替换，中途失败不会留下不完整的文件
+fn save_manifest_in(dir: &Path, content: &str, fetched_at: u64) -> Result<PathBuf, String> {
+    // 创建MemeMeow缓存目录
+    if !dir.exists() {
+        if let Err(e) = fs::create_dir_all(dir) {
             return Err(format!("创建缓存目录失败: {}", e));
         }
     }
 
     // 创建文件路径
-    let file_path = meme_cache_dir.join("community_manifest.json");
+    let file_path = dir.join(MANIFEST_CACHE_FILE);
 
     // 写入文件
-    if let Err(e) = fs::write(&file_path, content) {
+    if let Err(e) = write_atomic(&file_path, content.as_bytes()) {
         return Err(format!("写入缓存文件失败: {}", e));
     }
 
     debug!("社区表情库清单已保存到: {:?}", file_path);
+
+    // 记录下载时间，用于判断缓存是否过期
+    let meta = ManifestCacheMeta { fetched_at };
+    match serde_json::to_string(&meta) {
+        Ok(json) => {
+            if let Err(e) = write_atomic(&dir.join(MANIFEST_CACHE_META_FILE), json.as_bytes()) {
+                error!("写入清单缓存信息失败: {}", e);
+            }
+        }
+        Err(e) => error!("序列化清单缓存信息失败: {}", e),
+    }
+
     Ok(file_path)
 }
 
+// 缓存的清单的附加信息
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestCacheMeta {
+    // 下载清单时的Unix时间戳（秒）
+    fetched_at: u64,
+}
+
+// 缓存目录下的清单文件与下载时间记录文件
+const MANIFEST_CACHE_FILE: &str = "community_manifest.json";
+const MANIFEST_CACHE_META_FILE: &str = "community_manifest.meta.json";
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// 读取缓存清单的下载时间，旧版本没有记录时使用缓存文件的修改时间
+fn manifest_fetched_at() -> Option<u64> {
+    manifest_fetched_at_in(&app_dirs().cache_dir())
+}
+
+fn manifest_fetched_at_in(dir: &Path) -> Option<u64> {
+    let recorded = fs::read_to_string(dir.join(MANIFEST_CACHE_META_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<ManifestCacheMeta>(&content).ok())
+        .map(|meta| meta.fetched_at);
+
+    recorded.or_else(|| {
+        fs::metadata(dir.join(MANIFEST_CACHE_FILE))
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+    })
+}
+
+// 判断在 now 时刻，fetched_at 下载的缓存是否仍在有效期内
+// 下载时间晚于当前时间（系统时钟被调整）时视为有效
+pub fn is_manifest_cache_fresh(fetched_at: u64, now: u64, max_age_secs: u64) -> bool {
+    now.saturating_sub(fetched_at) < max_age_secs
+}
+
 // 从缓存加载manifest
 pub fn load_manifest_from_cache() -> Result<CommunityManifest, String> {
-    // 使用 dirs 库获取缓存目录
-    let cache_dir = match dirs::cache_dir() {
-        Some(dir) => dir,
-        None => {
-            return Err("无法获取系统缓存目录".to_string());
-        }
-    };
-
-    let file_path = cache_dir.join("MemeMeow").join("community_manifest.json");
+    let file_path = app_dirs().cache_dir().join(MANIFEST_CACHE_FILE);
 
     // 检查文件是否存在
     if !file_path.exists() {
@@ -164,16 +322,9 @@ pub fn load_manifest_from_cache() -> Result<CommunityManifest, String> {
 }
 
 // 获取启用状态配置文件路径
-fn get_enabled_libs_path() -> Result<PathBuf, String> {
-    let config_dir = match dirs::config_dir() {
-        Some(dir) => dir,
-        None => {
-            return Err("无法获取系统配置目录".to_string());
-        }
-    };
-
-    // 创建MemeMeow子目录
-    let meme_config_dir = config_dir.join("MemeMeow");
+pub(crate) fn get_enabled_libs_path() -> Result<PathBuf, String> {
+    // 创建MemeMeow配置目录
+    let meme_config_dir = app_dirs().config_dir();
     if !meme_config_dir.exists() {
         if let Err(e) = fs::create_dir_all(&meme_config_dir) {
             return Err(format!("创建配置目录失败: {}", e));
@@ -186,18 +337,24 @@ fn get_enabled_libs_path() -> Result<PathBuf, String> {
 
 // 加载已启用的表情库
 pub fn load_enabled_meme_libs() -> Result<EnabledMemeLibs, String> {
-    let file_path = get_enabled_libs_path()?;
+    load_enabled_libs_from(&get_enabled_libs_path()?)
+}
 
+// 从指定文件加载已启用的表情库
+//
+// 文件损坏时移到备份位置并写入空配置，之后的加载读取的是重置后的文件，
+// 不会重复备份或重复记录错误。
+pub(crate) fn load_enabled_libs_from(file_path: &Path) -> Result<EnabledMemeLibs, String> {
     // 如果文件不存在，返回默认空列表
     if !file_path.exists() {
         info!("启用的表情库配置文件不存在，将创建新文件");
         let default_config = EnabledMemeLibs::default();
-        save_enabled_meme_libs(&default_config)?;
+        save_enabled_libs_to(file_path, &default_config)?;
         return Ok(default_config);
     }
 
     // 读取文件内容
-    let content = match fs::read_to_string(&file_path) {
+    let content = match fs::read_to_string(file_path) {
         Ok(content) => content,
         Err(e) => {
             error!("读取启用的表情库配置失败: {}", e);
@@ -213,35 +370,123 @@ pub fn load_enabled_meme_libs() -> Result<EnabledMemeLibs, String> {
         },
         Err(e) => {
             error!("解析启用的表情库配置失败: {}", e);
-            // 如果解析失败，返回默认值
-            Ok(EnabledMemeLibs::default())
+            // 移走损坏的文件，以便之后通过 repair_enabled_libs 恢复
+            let backup_path = enabled_libs_backup_path(file_path);
+            let default_config = EnabledMemeLibs::default();
+            match fs::rename(file_path, &backup_path) {
+                Ok(()) => {
+                    info!("已将损坏的配置移到: {:?}", backup_path);
+                    if let Err(save_err) = save_enabled_libs_to(file_path, &default_config) {
+                        error!("重置启用的表情库配置失败: {}", save_err);
+                    }
+                }
+                // 无法备份时保留原文件，避免覆盖后无法恢复
+                Err(rename_err) => error!("备份损坏的配置失败: {}", rename_err),
+            }
+            recent_errors::record(
+                "enabled_meme_libs",
+                format!("启用的表情库配置已损坏，已重置为空并备份到 {:?}: {}", backup_path, e),
+            );
+            Ok(default_config)
         }
     }
 }
 
+// 损坏配置的备份路径
+fn enabled_libs_backup_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+// 从任意文本中提取合法的UUID，保持首次出现的顺序并去重
+fn salvage_uuids(content: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    content
+        .split(|c: char| !(c.is_ascii_hexdigit() || c == '-'))
+        .filter_map(|token| uuid::Uuid::parse_str(token).ok())
+        .map(|uuid| uuid.to_string())
+        .filter(|uuid| seen.insert(uuid.clone()))
+        .collect()
+}
+
+// 尝试从损坏的配置中恢复启用的表情库，返回恢复后的UUID列表
+#[tauri::command]
+pub fn repair_enabled_libs() -> Result<Vec<String>, String> {
+    let file_path = get_enabled_libs_path()?;
+    let backup_path = enabled_libs_backup_path(&file_path);
+
+    // 当前文件仍无法解析时直接从中恢复，否则从备份恢复
+    let current = fs::read_to_string(&file_path).ok();
+    let current_libs = current
+        .as_deref()
+        .and_then(|content| serde_json::from_str::<EnabledMemeLibs>(content).ok());
+    let corrupt_content = match (&current_libs, current) {
+        (None, Some(content)) => content,
+        _ => fs::read_to_string(&backup_path)
+            .map_err(|e| format!("没有可修复的配置: {:?} - {}", backup_path, e))?,
+    };
+
+    let salvaged = salvage_uuids(&corrupt_content);
+    let mut enabled_libs = current_libs.unwrap_or_default();
+    enabled_libs.enabled_libs.extend(salvaged.iter().cloned());
+    save_enabled_meme_libs(&enabled_libs)?;
+    info!("已从损坏的配置中恢复 {} 个表情库", salvaged.len());
+
+    let mut libs: Vec<String> = enabled_libs.enabled_libs.into_iter().collect();
+    libs.sort();
+    Ok(libs)
+}
+
 // 表情包社区相关命令
+//
+// 缓存未过期时直接使用缓存，否则从网络下载；下载失败时退回到过期的缓存。
+// max_age_seconds 指定可接受的最大缓存时长，不传则使用偏好设置中的有效期。
 #[tauri::command]
-pub async fn fetch_community_manifest() -> Result<CommunityManifest, String> {
+pub async fn fetch_community_manifest(max_age_seconds: Option<u64>) -> Result<CommunityManifest, MemeError> {
     info!("接收到获取社区表情库清单请求");
-    
+    let max_age = max_age_seconds.unwrap_or_else(|| {
+        crate::get_config_manager()
+            .get_preferences()
+            .map(|prefs| prefs.manifest_cache_ttl_secs)
+            .unwrap_or(6 * 60 * 60)
+    });
+
+    let fresh = manifest_fetched_at()
+        .is_some_and(|fetched_at| is_manifest_cache_fresh(fetched_at, unix_now(), max_age));
+
     // 先尝试从缓存加载
-    match load_manifest_from_cache() {
-        Ok(manifest) => {
+    let cached = match load_manifest_from_cache() {
+        Ok(manifest) if fresh => {
             info!("从缓存加载社区表情库清单成功");
-            Ok(manifest)
+            return Ok(manifest);
+        }
+        Ok(manifest) => {
+            debug!("缓存的社区表情库清单已过期，将从网络下载");
+            Some(manifest)
         }
         Err(e) => {
             debug!("从缓存加载失败: {}，将从网络下载", e);
-            // 缓存加载失败，从网络下载
-            download_community_manifest().await
+            None
+        }
+    };
+
+    match (download_community_manifest().await, cached) {
+        (Ok(manifest), _) => Ok(manifest),
+        (Err(e), Some(manifest)) => {
+            error!("下载社区表情库清单失败: {}，使用过期的缓存", e);
+            Ok(manifest)
         }
+        (Err(e), None) => Err(e),
     }
 }
 
 // 保存已启用的表情库
 pub fn save_enabled_meme_libs(enabled_libs: &EnabledMemeLibs) -> Result<(), String> {
-    let file_path = get_enabled_libs_path()?;
+    save_enabled_libs_to(&get_enabled_libs_path()?, enabled_libs)
+}
 
+pub(crate) fn save_enabled_libs_to(file_path: &Path, enabled_libs: &EnabledMemeLibs) -> Result<(), String> {
     // 序列化为JSON
     let json_content = match serde_json::to_string_pretty(enabled_libs) {
         Ok(content) => content,
@@ -251,8 +496,8 @@ pub fn save_enabled_meme_libs(enabled_libs: &EnabledMemeLibs) -> Result<(), Stri
         }
     };
 
-    // 写入文件
-    if let Err(e) = fs::write(&file_path, json_content) {
+    // 先写入临时文件再替换，避免写入中断导致配置损坏
+    if let Err(e) = write_atomic(file_path, json_content.as_bytes()) {
         error!("保存启用的表情库配置失败: {}", e);
         return Err(format!("保存配置失败: {}", e));
     }
@@ -263,14 +508,14 @@ pub fn save_enabled_meme_libs(enabled_libs: &EnabledMemeLibs) -> Result<(), Stri
 
 // 启用表情库
 #[tauri::command]
-pub fn enable_meme_lib(uuid: &str) -> Result<(), String> {
-    let mut enabled_libs = load_enabled_meme_libs()?;
+pub fn enable_meme_lib(uuid: &str) -> Result<(), MemeError> {
+    let mut enabled_libs = load_enabled_meme_libs().map_err(MemeError::Config)?;
     
     // 添加到集合中
     enabled_libs.enabled_libs.insert(uuid.to_string());
     
     // 保存更改
-    save_enabled_meme_libs(&enabled_libs)?;
+    save_enabled_meme_libs(&enabled_libs).map_err(MemeError::Config)?;
     info!("表情库已启用: {}", uuid);
     
     Ok(())
@@ -278,14 +523,14 @@ pub fn enable_meme_lib(uuid: &str) -> Result<(), String> {
 
 // 禁用表情库
 #[tauri::command]
-pub fn disable_meme_lib(uuid: &str) -> Result<(), String> {
-    let mut enabled_libs = load_enabled_meme_libs()?;
+pub fn disable_meme_lib(uuid: &str) -> Result<(), MemeError> {
+    let mut enabled_libs = load_enabled_meme_libs().map_err(MemeError::Config)?;
     
     // 从集合中移除
     enabled_libs.enabled_libs.remove(uuid);
     
     // 保存更改
-    save_enabled_meme_libs(&enabled_libs)?;
+    save_enabled_meme_libs(&enabled_libs).map_err(MemeError::Config)?;
     info!("表情库已禁用: {}", uuid);
     
     Ok(())
@@ -293,20 +538,20 @@ pub fn disable_meme_lib(uuid: &str) -> Result<(), String> {
 
 // 获取所有已启用的表情库UUID列表
 #[tauri::command]
-pub fn get_enabled_meme_libs() -> Result<Vec<String>, String> {
-    let enabled_libs = load_enabled_meme_libs()?;
+pub fn get_enabled_meme_libs() -> Result<Vec<String>, MemeError> {
+    let enabled_libs = load_enabled_meme_libs().map_err(MemeError::Config)?;
     Ok(enabled_libs.enabled_libs.into_iter().collect())
 }
 
 #[tauri::command]
-pub async fn refresh_community_manifest() -> Result<CommunityManifest, String> {
+pub async fn refresh_community_manifest() -> Result<CommunityManifest, MemeError> {
     info!("接收到刷新社区表情库清单请求");
     // 强制从网络刷新
     download_community_manifest().await
 }
 
 #[tauri::command]
-pub async fn get_api_server_urls_config() -> Result<Vec<ApiUrl>, String> {
+pub async fn get_api_server_urls_config() -> Result<Vec<ApiUrl>, MemeError> {
     // 获取当前配置的API服务器URL列表
     const SERVER_URLS: [&str; 1] = [
         "https://github.com/MemeMeow-Studio/Memes-Community/raw/main/community_server_urls.json",
@@ -319,22 +564,759 @@ pub async fn get_api_server_urls_config() -> Result<Vec<ApiUrl>, String> {
         }
         Err(e) => {
             error!("下载社区表情库清单失败: {}", e);
-            return Err(format!("下载失败: {}", e));
+            return Err(MemeError::Network(format!("下载失败: {}", e)));
         }
     };
 
     let manifest: Vec<ApiUrl> = match serde_json::from_str::<HashMap<String, String>>(&community_server_urls) {
         Ok(data) => {
             data.into_iter()
-                .map(|(name, url)| ApiUrl { name, url })
+                .map(|(name, url)| ApiUrl {
+                    name,
+                    url,
+                    request_format: RequestFormat::default(),
+                    search_method: SearchMethod::default(),
+                    results_key: None,
+                    headers: HashMap::new(),
+                })
                 .collect()
         },
         Err(e) => {
             error!("解析社区表情库清单JSON失败: {}", e);
-            return Err(format!("解析JSON失败: {}", e));
+            return Err(MemeError::Parse(format!("解析JSON失败: {}", e)));
         }
     };
 
     Ok(manifest)
 
-}
\ No newline at end of file
+}
+
+// 本地已安装表情包库的根目录
+pub fn local_libs_dir() -> PathBuf {
+    app_dirs().cache_dir().join("libs")
+}
+
+// 常见图片扩展名
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+// 递归收集目录下的图片文件
+fn collect_image_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("读取目录失败: {:?} - {}", dir, e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_image_files(&path, files);
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false)
+        {
+            files.push(path);
+        }
+    }
+}
+
+// 计算文件内容的SHA-256，结果跨版本、跨平台稳定
+fn hash_file(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// 重复表情包中的一项
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateEntry {
+    pub lib_uuid: String,
+    pub lib_name: Option<String>,
+    pub path: String,
+}
+
+// 内容相同的一组表情包
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub entries: Vec<DuplicateEntry>,
+}
+
+// 在已启用且已安装到本地的表情包库之间查找内容重复的表情包
+pub fn find_duplicate_memes_in(libs_dir: &Path, enabled: &[String]) -> Vec<DuplicateGroup> {
+    let manifest = load_manifest_from_cache().ok();
+    let mut groups: HashMap<String, Vec<DuplicateEntry>> = HashMap::new();
+
+    for uuid in enabled {
+        let lib_dir = libs_dir.join(uuid);
+        if !lib_dir.is_dir() {
+            continue;
+        }
+
+        let lib_name = manifest
+            .as_ref()
+            .and_then(|m| m.meme_libs.values().find(|lib| &lib.uuid == uuid))
+            .map(|lib| lib.name.clone());
+
+        let mut files = Vec::new();
+        collect_image_files(&lib_dir, &mut files);
+        for file in files {
+            if let Some(hash) = hash_file(&file) {
+                groups.entry(hash).or_default().push(DuplicateEntry {
+                    lib_uuid: uuid.clone(),
+                    lib_name: lib_name.clone(),
+                    path: file.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+
+    // 只保留跨表情包库的重复
+    let mut duplicates: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter(|(_, entries)| {
+            entries
+                .iter()
+                .map(|e| &e.lib_uuid)
+                .collect::<HashSet<_>>()
+                .len()
+                > 1
+        })
+        .map(|(hash, entries)| DuplicateGroup { hash, entries })
+        .collect();
+    duplicates.sort_by(|a, b| b.entries.len().cmp(&a.entries.len()).then(a.hash.cmp(&b.hash)));
+    duplicates
+}
+
+#[tauri::command]
+pub async fn find_duplicate_memes() -> Result<Vec<DuplicateGroup>, String> {
+    info!("接收到查找重复表情包请求");
+    let enabled = get_enabled_meme_libs()?;
+
+    let groups = tokio::task::spawn_blocking(move || {
+        find_duplicate_memes_in(&local_libs_dir(), &enabled)
+    })
+    .await
+    .map_err(|e| format!("查找重复表情包失败: {}", e))?;
+
+    info!("发现 {} 组跨表情包库的重复表情包", groups.len());
+    Ok(groups)
+}
+
+// 列出已启用且已安装到本地的表情包，url为 asset 协议地址，path为本地文件路径
+pub fn list_local_memes(libs_dir: &Path, enabled: &[String]) -> Vec<MemeItem> {
+    let mut items = Vec::new();
+    for uuid in enabled {
+        let lib_dir = libs_dir.join(uuid);
+        if !lib_dir.is_dir() {
+            continue;
+        }
+
+        let mut files = Vec::new();
+        collect_image_files(&lib_dir, &mut files);
+        for file in files {
+            let path = file.to_string_lossy().to_string();
+            items.push(MemeItem {
+                id: path.clone(),
+                url: asset_url(&file),
+                description: None,
+                score: None,
+                source: Some(uuid.clone()),
+                updated_at: None,
+                path: Some(path),
+            });
+        }
+    }
+    items
+}
+
+// 已启用表情库在清单中的标签，用于本地没有表情包时随机搜索
+pub fn enabled_lib_tags(enabled: &[String]) -> Vec<String> {
+    let manifest = match load_manifest_from_cache() {
+        Ok(manifest) => manifest,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut tags: Vec<String> = manifest
+        .meme_libs
+        .values()
+        .filter(|lib| enabled.contains(&lib.uuid))
+        .flat_map(|lib| lib.tags.iter().cloned())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    tags.sort();
+    tags
+}
+
+// 资源索引，来自清单的 resource_url，不可用时由各表情库的 url 构成
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResourceIndex {
+    // 索引来源: "resource_url" 或 "lib_urls"
+    pub source: String,
+    pub index: serde_json::Value,
+}
+
+fn resource_index_cache_path() -> PathBuf {
+    app_dirs().cache_dir().join("resource_index.json")
+}
+
+// 从缓存加载资源索引
+pub fn load_resource_index_from_cache() -> Result<ResourceIndex, String> {
+    let file_path = resource_index_cache_path();
+    let content = fs::read_to_string(&file_path)
+        .map_err(|e| format!("读取资源索引缓存失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析资源索引缓存失败: {}", e))
+}
+
+// 由各表情库的 url 构成的回退索引
+fn lib_urls_index(manifest: &CommunityManifest) -> ResourceIndex {
+    let index: serde_json::Map<String, serde_json::Value> = manifest
+        .meme_libs
+        .values()
+        .map(|lib| (lib.uuid.clone(), serde_json::Value::String(lib.url.clone())))
+        .collect();
+
+    ResourceIndex {
+        source: "lib_urls".to_string(),
+        index: serde_json::Value::Object(index),
+    }
+}
+
+// 下载并缓存清单中 resource_url 指向的资源索引
+#[tauri::command]
+pub async fn fetch_resource_index() -> Result<ResourceIndex, String> {
+    info!("接收到获取资源索引请求");
+    let manifest = fetch_community_manifest(None).await?;
+    let resource_url = manifest.community_info.resource_url.trim().to_string();
+
+    let resource_index = if resource_url.is_empty() {
+        info!("清单未提供 resource_url，使用各表情库的 url 作为资源索引");
+        lib_urls_index(&manifest)
+    } else {
+        let downloaded = match download_with_fallback_urls([resource_url.as_str()]).await {
+            Ok(text) => serde_json::from_str::<serde_json::Value>(&text)
+                .map_err(|e| format!("解析资源索引失败: {}", e)),
+            Err(e) => Err(e),
+        };
+
+        match downloaded {
+            Ok(index) => ResourceIndex {
+                source: "resource_url".to_string(),
+                index,
+            },
+            Err(e) => {
+                error!("获取资源索引失败: {}，使用各表情库的 url 作为资源索引", e);
+                lib_urls_index(&manifest)
+            }
+        }
+    };
+
+    // 缓存资源索引
+    match serde_json::to_string_pretty(&resource_index) {
+        Ok(json) => {
+            let file_path = resource_index_cache_path();
+            let saved = file_path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&file_path, json));
+            if let Err(e) = saved {
+                error!("保存资源索引缓存失败: {}", e);
+            }
+        }
+        Err(e) => error!("序列化资源索引失败: {}", e),
+    }
+
+    Ok(resource_index)
+}
+
+// 获取已缓存的资源索引，尚未获取过时返回None
+#[tauri::command]
+pub fn get_resource_index() -> Option<ResourceIndex> {
+    load_resource_index_from_cache().ok()
+}
+
+// 从缓存的清单中查找单个表情库，未找到时返回None
+#[tauri::command]
+pub fn get_meme_lib(uuid: String) -> Option<MemeLib> {
+    let mut manifest = match load_manifest_from_cache() {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            debug!("读取缓存的清单失败: {}", e);
+            return None;
+        }
+    };
+
+    // 清单通常以UUID为键，键不一致时再按字段查找
+    if let Some(lib) = manifest.meme_libs.remove(&uuid) {
+        return Some(lib);
+    }
+    manifest
+        .meme_libs
+        .into_values()
+        .find(|lib| lib.uuid == uuid)
+}
+
+// 表情库查询条件，未设置的条件不参与筛选
+#[derive(Debug, Default, Deserialize)]
+pub struct LibFilter {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // 标签匹配方式，默认需要包含全部标签
+    #[serde(default)]
+    pub tag_mode: TagMatchMode,
+    // 在名称和描述中查找的文本
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl LibFilter {
+    // 判断表情库是否满足全部条件，比较时忽略大小写
+    pub fn matches(&self, lib: &MemeLib) -> bool {
+        let lib_tags: HashSet<String> = lib.tags.iter().map(|t| t.trim().to_lowercase()).collect();
+        let wanted: Vec<String> = self
+            .tags
+            .iter()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let tags_match = wanted.is_empty()
+            || match self.tag_mode {
+                TagMatchMode::All => wanted.iter().all(|t| lib_tags.contains(t)),
+                TagMatchMode::Any => wanted.iter().any(|t| lib_tags.contains(t)),
+            };
+
+        let text_match = match self.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(text) => {
+                let text = text.to_lowercase();
+                lib.name.to_lowercase().contains(&text)
+                    || lib.description.to_lowercase().contains(&text)
+            }
+            None => true,
+        };
+
+        let author_match = match self.author.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+            Some(author) => lib.author.trim().eq_ignore_ascii_case(author),
+            None => true,
+        };
+
+        tags_match && text_match && author_match
+    }
+}
+
+// 按条件筛选清单中的表情库，结果按时间戳从新到旧排序
+pub fn filter_meme_libs(manifest: CommunityManifest, filter: &LibFilter) -> Vec<MemeLib> {
+    let mut libs: Vec<MemeLib> = manifest
+        .meme_libs
+        .into_values()
+        .filter(|lib| filter.matches(lib))
+        .collect();
+    libs.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.name.cmp(&b.name)));
+    libs
+}
+
+// 在缓存的社区清单中查询表情库
+#[tauri::command]
+pub fn query_meme_libs(filter: LibFilter) -> Result<Vec<MemeLib>, MemeError> {
+    let manifest = load_manifest_from_cache().map_err(MemeError::NotFound)?;
+    Ok(filter_meme_libs(manifest, &filter))
+}
+
+// 浏览表情库时单页的最大条数
+const BROWSE_PAGE_LIMIT: usize = 100;
+
+// 从表情库的JSON清单中提取图片地址，支持字符串数组、带url字段的对象数组，
+// 以及把上述数组放在 memes / images / files 字段中的对象
+pub fn parse_lib_listing(listing: &serde_json::Value, base: &reqwest::Url) -> Option<Vec<String>> {
+    let entries = match listing {
+        serde_json::Value::Array(entries) => entries,
+        serde_json::Value::Object(fields) => ["memes", "images", "files"]
+            .iter()
+            .find_map(|key| fields.get(*key).and_then(|v| v.as_array()))?,
+        _ => return None,
+    };
+
+    Some(
+        entries
+            .iter()
+            .filter_map(|entry| match entry {
+                serde_json::Value::String(url) => Some(url.as_str()),
+                serde_json::Value::Object(fields) => fields.get("url").and_then(|v| v.as_str()),
+                _ => None,
+            })
+            // 清单中可能是相对地址
+            .filter_map(|url| base.join(url).ok())
+            .map(|url| url.to_string())
+            .collect(),
+    )
+}
+
+// 分页浏览表情库内容，无需安装整个表情库
+//
+// 已安装到本地的表情库直接分页读取本地文件；未安装时，表情库地址须指向
+// JSON格式的图片清单，其他格式（如压缩包）需要先完整下载。
+#[tauri::command]
+pub async fn browse_lib(
+    uuid: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<MemeItem>, MemeError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(20).clamp(1, BROWSE_PAGE_LIMIT);
+
+    let local_dir = local_libs_dir().join(&uuid);
+    if local_dir.is_dir() {
+        let lib_uuid = uuid.clone();
+        let items = tokio::task::spawn_blocking(move || {
+            let mut items = list_local_memes(&local_libs_dir(), &[lib_uuid]);
+            items.sort_by(|a, b| a.path.cmp(&b.path));
+            items
+        })
+        .await
+        .map_err(|e| MemeError::Other(format!("读取本地表情库失败: {}", e)))?;
+        return Ok(items.into_iter().skip(offset).take(limit).collect());
+    }
+
+    let lib = get_meme_lib(uuid.clone())
+        .ok_or_else(|| MemeError::NotFound(format!("未找到表情库: {}", uuid)))?;
+    let base = reqwest::Url::parse(&lib.url)
+        .map_err(|e| MemeError::Parse(format!("表情库地址无效: {}", e)))?;
+
+    // 先确认是JSON清单，避免为了预览下载整个压缩包
+    let content_type = fetch_content_type(&lib.url).await.map_err(MemeError::Network)?;
+    if !content_type.as_deref().is_some_and(|ct| ct.contains("json")) {
+        return Err(MemeError::InvalidInput(format!(
+            "该表情库的格式（{}）不支持分页浏览，请先下载表情库",
+            content_type.as_deref().unwrap_or("未知")
+        )));
+    }
+
+    let listing_text = download_with_fallback_urls([lib.url.clone()])
+        .await
+        .map_err(MemeError::Network)?;
+    let listing: serde_json::Value = serde_json::from_str(&listing_text)
+        .map_err(|e| MemeError::Parse(format!("解析表情库清单失败: {}", e)))?;
+    let urls = parse_lib_listing(&listing, &base).ok_or_else(|| {
+        MemeError::InvalidInput("该表情库的清单格式不支持分页浏览，请先下载表情库".to_string())
+    })?;
+
+    Ok(urls
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|url| MemeItem {
+            id: url.clone(),
+            url,
+            description: None,
+            score: None,
+            source: Some(uuid.clone()),
+            updated_at: None,
+            path: None,
+        })
+        .collect())
+}
+
+// 旧版本 download_meme_pack 保存资源包的目录，其中残留的文件仍计入缓存占用并随缓存清理删除
+pub fn meme_packs_dir() -> PathBuf {
+    app_dirs().cache_dir().join("packs")
+}
+
+// 下载并安装表情库，与 download_meme_lib 相同，保留给仍在使用旧命令名的前端
+#[tauri::command]
+pub async fn download_meme_pack(app: AppHandle, uuid: String) -> Result<String, MemeError> {
+    crate::lib_install::download_meme_lib(app, uuid).await
+}
+
+// 自动刷新间隔设置变化时唤醒后台刷新任务
+static AUTO_REFRESH_CHANGED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+// 通知后台刷新任务重新读取自动刷新间隔
+pub fn notify_auto_refresh_changed() {
+    AUTO_REFRESH_CHANGED.notify_one();
+}
+
+// 等待下一次自动刷新，间隔为 0 时一直等待；设置变化时提前返回 false
+async fn wait_for_refresh(interval_secs: u64, changed: &tokio::sync::Notify) -> bool {
+    if interval_secs == 0 {
+        changed.notified().await;
+        return false;
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => true,
+        _ = changed.notified() => false,
+    }
+}
+
+// 启动社区清单后台自动刷新任务
+//
+// 每轮读取最新的 manifest_auto_refresh_secs 设置，设置变化时立即按新的间隔重新计时。
+// 清单时间戳发生变化时向前端发送 manifest-updated 事件。
+pub fn spawn_manifest_auto_refresh(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = crate::get_config_manager()
+                .get_preferences()
+                .map(|prefs| prefs.manifest_auto_refresh_secs)
+                .unwrap_or(0);
+
+            if !wait_for_refresh(interval, &AUTO_REFRESH_CHANGED).await {
+                debug!("自动刷新间隔已变化，重新计时");
+                continue;
+            }
+
+            let previous = load_manifest_from_cache()
+                .map(|m| m.community_info.timestamp)
+                .ok();
+
+            match download_community_manifest().await {
+                Ok(manifest) => {
+                    let timestamp = manifest.community_info.timestamp;
+                    if previous != Some(timestamp) {
+                        info!("社区表情库清单已自动更新，时间戳: {}", timestamp);
+                        if let Err(e) = app.emit("manifest-updated", timestamp) {
+                            error!("发送清单更新事件失败: {}", e);
+                        }
+                    } else {
+                        debug!("社区表情库清单未变化");
+                    }
+                }
+                Err(e) => error!("自动刷新社区表情库清单失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lib(uuid: &str, version: &str, timestamp: u64) -> MemeLib {
+        MemeLib {
+            name: uuid.to_string(),
+            version: version.to_string(),
+            author: String::new(),
+            description: String::new(),
+            created_at: String::new(),
+            timestamp,
+            tags: Vec::new(),
+            url: String::new(),
+            update_url: String::new(),
+            uuid: uuid.to_string(),
+            sha256: None,
+        }
+    }
+
+    fn manifest(libs: Vec<MemeLib>) -> CommunityManifest {
+        CommunityManifest {
+            community_info: CommunityInfo {
+                resource_url: String::new(),
+                update_url: String::new(),
+                timestamp: 0,
+            },
+            meme_libs: libs.into_iter().map(|lib| (lib.uuid.clone(), lib)).collect(),
+        }
+    }
+
+    #[test]
+    fn diff_detects_bumped_new_and_removed_libs() {
+        let cached = manifest(vec![lib("same", "1.0", 1), lib("bumped", "1.0", 1), lib("gone", "1.0", 1)]);
+        let remote = manifest(vec![lib("same", "1.0", 1), lib("bumped", "1.1", 1), lib("new", "1.0", 2)]);
+
+        let diff = diff_manifests(&cached, &remote);
+        assert_eq!(diff.added, ["new"]);
+        assert_eq!(diff.updated, ["bumped"]);
+        assert_eq!(diff.removed, ["gone"]);
+    }
+
+    #[test]
+    fn diff_treats_timestamp_change_as_update() {
+        let cached = manifest(vec![lib("a", "1.0", 1)]);
+        let remote = manifest(vec![lib("a", "1.0", 2)]);
+        assert_eq!(diff_manifests(&cached, &remote).updated, ["a"]);
+        assert_eq!(diff_manifests(&remote, &remote), ManifestDiff::default());
+    }
+
+    #[test]
+    fn diff_against_empty_cache_adds_everything() {
+        let remote = manifest(vec![lib("b", "1.0", 1), lib("a", "1.0", 1)]);
+        let diff = diff_manifests(&manifest(Vec::new()), &remote);
+        assert_eq!(diff.added, ["a", "b"]);
+        assert!(diff.updated.is_empty() && diff.removed.is_empty());
+    }
+
+    fn tagged(uuid: &str, tags: &[&str], description: &str, timestamp: u64) -> MemeLib {
+        MemeLib {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            description: description.to_string(),
+            ..lib(uuid, "1.0", timestamp)
+        }
+    }
+
+    fn query(filter: &LibFilter) -> Vec<String> {
+        let libs = manifest(vec![
+            tagged("cats", &["Cute", "animal"], "Lots of cats", 3),
+            tagged("dogs", &["animal"], "Good dogs", 2),
+            tagged("anime", &["cute", "anime"], "Reaction images", 1),
+        ]);
+        filter_meme_libs(libs, filter).into_iter().map(|lib| lib.uuid).collect()
+    }
+
+    fn tags(tags: &[&str], tag_mode: TagMatchMode) -> LibFilter {
+        LibFilter {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            tag_mode,
+            ..LibFilter::default()
+        }
+    }
+
+    #[test]
+    fn query_matches_all_tags() {
+        assert_eq!(query(&tags(&["cute", " ANIMAL "], TagMatchMode::All)), ["cats"]);
+        assert!(query(&tags(&["cute", "missing"], TagMatchMode::All)).is_empty());
+    }
+
+    #[test]
+    fn query_matches_any_tag() {
+        // 结果按时间戳从新到旧排序
+        assert_eq!(query(&tags(&["cute", "missing"], TagMatchMode::Any)), ["cats", "anime"]);
+        assert_eq!(query(&tags(&["anime", "animal"], TagMatchMode::Any)), ["cats", "dogs", "anime"]);
+    }
+
+    #[test]
+    fn query_matches_description_text() {
+        let filter = |text: &str| LibFilter {
+            text: Some(text.to_string()),
+            ..LibFilter::default()
+        };
+        assert_eq!(query(&filter("good")), ["dogs"]);
+        assert_eq!(query(&filter("REACTION")), ["anime"]);
+        // 名称同样参与匹配，空白文本不筛选
+        assert_eq!(query(&filter("cats")), ["cats"]);
+        assert_eq!(query(&filter("  ")).len(), 3);
+    }
+
+    #[test]
+    fn saves_manifest_and_fetch_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+
+        let path = save_manifest_in(&cache, "{}", 1_700_000_000).unwrap();
+        assert_eq!(path, cache.join(MANIFEST_CACHE_FILE));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        assert_eq!(manifest_fetched_at_in(&cache), Some(1_700_000_000));
+
+        save_manifest_in(&cache, "{\"v\":2}", 1_700_000_100).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"v\":2}");
+        assert_eq!(manifest_fetched_at_in(&cache), Some(1_700_000_100));
+        // 原子写入不留下临时文件
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn manifest_cache_freshness() {
+        let fetched_at = 1_700_000_000;
+        assert!(is_manifest_cache_fresh(fetched_at, fetched_at + 59, 60));
+        assert!(!is_manifest_cache_fresh(fetched_at, fetched_at + 60, 60));
+        assert!(!is_manifest_cache_fresh(fetched_at, fetched_at + 3600, 60));
+        // 系统时钟被调回时仍视为有效
+        assert!(is_manifest_cache_fresh(fetched_at, fetched_at - 100, 60));
+        // 有效期为 0 时总是过期
+        assert!(!is_manifest_cache_fresh(fetched_at, fetched_at, 0));
+    }
+
+    #[test]
+    fn fetch_time_falls_back_to_manifest_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(manifest_fetched_at_in(dir.path()), None);
+
+        fs::write(dir.path().join(MANIFEST_CACHE_FILE), "{}").unwrap();
+        let fetched_at = manifest_fetched_at_in(dir.path()).unwrap();
+        assert!(unix_now().abs_diff(fetched_at) < 60);
+    }
+
+    #[tokio::test]
+    async fn refresh_wait_wakes_on_setting_change() {
+        let changed = tokio::sync::Notify::new();
+        // 通知在等待开始前发出时也不会丢失
+        changed.notify_one();
+        assert!(!wait_for_refresh(3600, &changed).await);
+
+        changed.notify_one();
+        assert!(!wait_for_refresh(0, &changed).await);
+
+        let started = std::time::Instant::now();
+        assert!(wait_for_refresh(1, &changed).await);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn missing_enabled_libs_file_is_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("enabled_meme_libs.json");
+
+        let libs = load_enabled_libs_from(&path).unwrap();
+        assert!(libs.enabled_libs.is_empty());
+        assert!(path.is_file());
+    }
+
+    #[test]
+    fn corrupt_enabled_libs_file_is_moved_aside_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("enabled_meme_libs.json");
+        let backup_path = enabled_libs_backup_path(&path);
+        let corrupt = r#"{"enabled_libs": ["3f2b6c1e-8d4a-4f0e-9b7a-2c5d1e6f7a8b""#;
+        fs::write(&path, corrupt).unwrap();
+        let errors_before = recent_errors::snapshot()
+            .iter()
+            .filter(|e| e.source == "enabled_meme_libs")
+            .count();
+
+        for _ in 0..3 {
+            let libs = load_enabled_libs_from(&path).unwrap();
+            assert!(libs.enabled_libs.is_empty());
+        }
+
+        // 备份保留原始内容，配置文件已重置为可解析的空配置
+        assert_eq!(fs::read_to_string(&backup_path).unwrap(), corrupt);
+        let reset: EnabledMemeLibs =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(reset.enabled_libs.is_empty());
+        let errors_after = recent_errors::snapshot()
+            .iter()
+            .filter(|e| e.source == "enabled_meme_libs")
+            .count();
+        assert_eq!(errors_after - errors_before, 1);
+    }
+
+    #[test]
+    fn hashes_file_content_with_sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.png");
+        let b = dir.path().join("b.gif");
+        let c = dir.path().join("c.png");
+        fs::write(&a, b"abc").unwrap();
+        fs::write(&b, b"abc").unwrap();
+        fs::write(&c, b"abd").unwrap();
+
+        assert_eq!(
+            hash_file(&a).as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(hash_file(&a), hash_file(&b));
+        assert_ne!(hash_file(&a), hash_file(&c));
+        assert_eq!(hash_file(&dir.path().join("missing.png")), None);
+    }
+
+    #[test]
+    fn salvages_uuids_from_corrupt_content() {
+        let content = r#"{"enabled_libs": ["3F2B6C1E-8D4A-4F0E-9B7A-2C5D1E6F7A8B", "bad", "3f2b6c1e-8d4a-4f0e-9b7a-2c5d1e6f7a8b""#;
+        assert_eq!(salvage_uuids(content), ["3f2b6c1e-8d4a-4f0e-9b7a-2c5d1e6f7a8b"]);
+    }
+}
@@ -1,11 +1,17 @@
 use log::{debug, error, info};
 use serde::{de, Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::Url;
 use tauri_plugin_http::reqwest;
 use tauri_plugin_http::reqwest::{Client, Error, Method, Request, RequestBuilder, StatusCode};
 
+use crate::error::MemeError;
 use crate::meme_community::get_enabled_meme_libs;
+use crate::utils::misc::{ApiUrl, RequestFormat, SearchMethod};
+use crate::utils::request_trace;
 
 /// 表情包项目的数据结构，与服务器返回的JSON对应
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,6 +19,287 @@ pub struct MemeItem {
     pub id: String,
     pub url: String,
     pub description: Option<String>,
+    /// 服务器给出的相关度分数（越高越相关）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// 结果所属的表情包库UUID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// 表情包最后更新时间（Unix秒），仅当服务器提供时存在
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    /// 本地表情包的文件路径，此时 `url` 为可在前端显示的 asset 协议地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// 一次搜索的结果状态
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStatus {
+    /// 搜索成功且有结果
+    Ok,
+    /// 搜索成功但没有结果
+    Empty,
+    /// 关键词为空，未发起搜索
+    EmptyKeyword,
+    /// 搜索失败
+    Error,
+    /// 搜索被取消
+    Cancelled,
+}
+
+/// 客户端一次搜索的结果及其来源
+#[derive(Debug, Clone)]
+pub struct SearchResponse {
+    pub items: Vec<MemeItem>,
+    /// 是否命中客户端的搜索缓存
+    pub from_cache: bool,
+    /// 发起搜索的API地址
+    pub api_url: String,
+}
+
+/// 带元数据的搜索结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResult {
+    pub items: Vec<MemeItem>,
+    pub status: SearchStatus,
+    /// 前端是否应显示占位图（无结果或出错时）
+    pub show_placeholder: bool,
+    pub error: Option<String>,
+    /// 各表情包库贡献的结果数量，仅当结果带有来源信息时存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_lib_counts: Option<HashMap<String, usize>>,
+    /// 与缓存结果合并时，前多少项来自本次搜索，其余来自缓存
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fresh_count: Option<usize>,
+    /// 结果是否来自客户端的搜索缓存
+    pub from_cache: bool,
+    /// 实际发起搜索的API地址，未发起搜索时为 `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// 被屏蔽词过滤掉的结果数量
+    pub blocked_count: usize,
+    /// 搜索失败时的错误类别，前端通过 `error` 获取描述
+    #[serde(skip)]
+    pub failure: Option<MemeError>,
+}
+
+impl SearchResult {
+    pub fn new(items: Vec<MemeItem>) -> Self {
+        let status = if items.is_empty() {
+            SearchStatus::Empty
+        } else {
+            SearchStatus::Ok
+        };
+        Self {
+            show_placeholder: items.is_empty(),
+            per_lib_counts: count_per_lib(&items),
+            items,
+            status,
+            error: None,
+            fresh_count: None,
+            from_cache: false,
+            api_url: None,
+            blocked_count: 0,
+            failure: None,
+        }
+    }
+
+    /// 与缓存合并后的结果，前 `fresh_count` 项来自本次搜索
+    pub fn merged(items: Vec<MemeItem>, fresh_count: usize) -> Self {
+        Self {
+            fresh_count: Some(fresh_count),
+            ..Self::new(items)
+        }
+    }
+
+    pub fn with_status(status: SearchStatus, error: Option<String>) -> Self {
+        Self {
+            items: Vec::new(),
+            status,
+            show_placeholder: matches!(status, SearchStatus::Empty | SearchStatus::Error),
+            error,
+            per_lib_counts: None,
+            fresh_count: None,
+            from_cache: false,
+            api_url: None,
+            blocked_count: 0,
+            failure: None,
+        }
+    }
+
+    /// 搜索失败的结果，同时保留错误类别
+    pub fn failed(err: MemeError) -> Self {
+        Self {
+            failure: Some(err.clone()),
+            ..Self::with_status(SearchStatus::Error, Some(err.to_string()))
+        }
+    }
+}
+
+/// 将本次搜索结果与之前的结果合并
+///
+/// 保持本次结果的顺序，再按原顺序追加之前有而本次没有的结果，按URL去重。
+/// 返回合并后的结果以及其中来自本次搜索的数量。
+pub fn merge_cached_results(fresh: Vec<MemeItem>, cached: &[MemeItem]) -> (Vec<MemeItem>, usize) {
+    let mut seen: std::collections::HashSet<String> =
+        fresh.iter().map(|item| item.url.clone()).collect();
+    let fresh_count = fresh.len();
+    let mut merged = fresh;
+    for item in cached {
+        if seen.insert(item.url.clone()) {
+            merged.push(item.clone());
+        }
+    }
+    (merged, fresh_count)
+}
+
+/// 去掉图片地址或描述中包含任一屏蔽词的结果（忽略大小写）
+///
+/// 返回保留的结果和被过滤掉的数量，空白的屏蔽词会被忽略。
+pub fn filter_blocked(items: Vec<MemeItem>, blocklist: &[String]) -> (Vec<MemeItem>, usize) {
+    let terms: Vec<String> = blocklist
+        .iter()
+        .map(|term| term.trim().to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect();
+    if terms.is_empty() {
+        return (items, 0);
+    }
+
+    let total = items.len();
+    let kept: Vec<MemeItem> = items
+        .into_iter()
+        .filter(|item| !is_blocked(item, &terms))
+        .collect();
+    let blocked = total - kept.len();
+    (kept, blocked)
+}
+
+/// 判断结果是否命中屏蔽词，`terms` 需已转为小写
+fn is_blocked(item: &MemeItem, terms: &[String]) -> bool {
+    let url = item.url.to_lowercase();
+    let description = item.description.as_deref().unwrap_or_default().to_lowercase();
+    terms
+        .iter()
+        .any(|term| url.contains(term.as_str()) || description.contains(term.as_str()))
+}
+
+/// 统计各表情包库贡献的结果数量，没有任何结果带来源信息时返回 `None`
+fn count_per_lib(items: &[MemeItem]) -> Option<HashMap<String, usize>> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for source in items.iter().filter_map(|item| item.source.as_ref()) {
+        *counts.entry(source.clone()).or_default() += 1;
+    }
+    if counts.is_empty() {
+        None
+    } else {
+        Some(counts)
+    }
+}
+
+/// 搜索结果的排序方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// 保持服务器返回的顺序
+    #[default]
+    Server,
+    /// 按相关度分数从高到低
+    Score,
+    /// 按表情包库优先级
+    SourcePriority,
+    /// 随机打乱，同一会话内对同一关键词结果稳定
+    Shuffle,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "server" => Ok(SortOrder::Server),
+            "score" => Ok(SortOrder::Score),
+            "source_priority" => Ok(SortOrder::SourcePriority),
+            "shuffle" => Ok(SortOrder::Shuffle),
+            other => Err(format!("无效的排序方式: {}", other)),
+        }
+    }
+}
+
+// 本次会话的随机种子，保证同一会话内打乱结果可复现
+static SESSION_SEED: OnceLock<u64> = OnceLock::new();
+
+fn session_seed() -> u64 {
+    *SESSION_SEED.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    })
+}
+
+// SplitMix64 伪随机数生成器
+fn split_mix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// 随机取样的调用计数，保证同一会话内每次取样结果不同
+static RANDOM_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 在 `0..len` 中随机选取一个下标，`len` 必须大于0
+pub fn random_index(len: usize) -> usize {
+    let counter = RANDOM_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut state = session_seed() ^ counter.wrapping_mul(0x2545f4914f6cdd1d);
+    (split_mix64(&mut state) % len as u64) as usize
+}
+
+/// 按指定方式对搜索结果排序
+///
+/// `source_priority` 为按优先级从高到低排列的表情包库UUID，
+/// 不在列表中或没有来源的结果排在最后。所有排序都是稳定的。
+pub fn sort_memes(
+    items: &mut [MemeItem],
+    order: SortOrder,
+    keyword: &str,
+    source_priority: &[String],
+) {
+    match order {
+        SortOrder::Server => {}
+        SortOrder::Score => {
+            items.sort_by(|a, b| match (a.score, b.score) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+        }
+        SortOrder::SourcePriority => {
+            items.sort_by_key(|item| {
+                item.source
+                    .as_ref()
+                    .and_then(|source| source_priority.iter().position(|p| p == source))
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        SortOrder::Shuffle => {
+            let mut state = keyword
+                .bytes()
+                .fold(session_seed(), |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+                });
+            // Fisher-Yates 洗牌
+            for i in (1..items.len()).rev() {
+                let j = (split_mix64(&mut state) % (i as u64 + 1)) as usize;
+                items.swap(i, j);
+            }
+        }
+    }
 }
 
 /// 服务器返回的表情包搜索结果
@@ -27,13 +314,150 @@ pub struct MemeItem {
 pub struct MemeServerConfig {
     pub api_url: String,
     pub timeout_seconds: u64,
+    pub proxy_url: Option<String>,
+    pub user_agent: Option<String>,
+    /// 测试模式：不发起网络请求，返回由关键词派生的固定结果
+    pub test_mode: bool,
 }
 
+/// 搜索请求可能出现的错误
+#[derive(Debug)]
+pub enum SearchError {
+    /// API地址无法解析为URL
+    InvalidUrl(String, String),
+    /// 网络请求失败
+    Request(Error),
+    /// 服务器返回了非成功状态码
+    Status(StatusCode),
+    /// 响应不是预期的JSON格式
+    Parse(serde_json::Error),
+    /// 搜索配额已用完，未发送请求
+    QuotaExceeded(String),
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::InvalidUrl(url, e) => write!(f, "API地址无效: {} ({})", url, e),
+            SearchError::Request(e) => write!(f, "请求失败: {}", e),
+            SearchError::Status(status) => write!(f, "服务器返回错误状态码: {}", status),
+            SearchError::Parse(e) => write!(f, "无法解析服务器响应: {}", e),
+            SearchError::QuotaExceeded(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<Error> for SearchError {
+    fn from(e: Error) -> Self {
+        SearchError::Request(e)
+    }
+}
+
+/// 不同服务器分支返回的搜索响应格式
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawSearchResponse {
+    /// `{"results": [...]}`
+    Results { results: Vec<SearchResultEntry> },
+    /// `{"data": [...]}`
+    Data { data: Vec<SearchResultEntry> },
+    /// `[...]`
+    Bare(Vec<SearchResultEntry>),
+}
+
+impl RawSearchResponse {
+    fn into_entries(self) -> Vec<SearchResultEntry> {
+        match self {
+            RawSearchResponse::Results { results } => results,
+            RawSearchResponse::Data { data } => data,
+            RawSearchResponse::Bare(entries) => entries,
+        }
+    }
+}
+
+/// 解析搜索响应正文
+///
+/// 指定 `results_key` 时按该路径取出结果数组，否则自动识别已知的响应格式。
+fn parse_search_response(
+    body: &str,
+    results_key: Option<&str>,
+) -> Result<Vec<SearchResultEntry>, serde_json::Error> {
+    let results_key = results_key.map(str::trim).filter(|key| !key.is_empty());
+    let Some(results_key) = results_key else {
+        return serde_json::from_str::<RawSearchResponse>(body)
+            .map(RawSearchResponse::into_entries);
+    };
+
+    let value: serde_json::Value = serde_json::from_str(body)?;
+    let mut current = &value;
+    for segment in results_key.split('.') {
+        let next = match current {
+            serde_json::Value::Array(items) => {
+                segment.parse::<usize>().ok().and_then(|i| items.get(i))
+            }
+            other => other.get(segment),
+        };
+        current = next.ok_or_else(|| {
+            <serde_json::Error as de::Error>::custom(format!("响应中不存在字段: {}", results_key))
+        })?;
+    }
+    Vec::<SearchResultEntry>::deserialize(current)
+}
+
+/// 搜索结果中的单项，兼容旧版纯URL与带ID和描述的对象两种格式
 #[derive(Debug, Deserialize)]
-struct MemeSearchResponse {
-    // code: u32,
-    results: Vec<String>,
-    // msg: String,
+#[serde(untagged)]
+enum SearchResultEntry {
+    Url(String),
+    Item {
+        #[serde(default)]
+        id: Option<String>,
+        url: String,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        score: Option<f64>,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        updated_at: Option<u64>,
+    },
+}
+
+impl SearchResultEntry {
+    /// 转换为表情包项目，服务器未提供ID时生成随机ID
+    fn into_meme_item(self) -> MemeItem {
+        let random_id = || uuid::Uuid::new_v4().to_string();
+        match self {
+            SearchResultEntry::Url(url) => MemeItem {
+                id: random_id(),
+                url,
+                description: None,
+                score: None,
+                source: None,
+                updated_at: None,
+                path: None,
+            },
+            SearchResultEntry::Item {
+                id,
+                url,
+                description,
+                score,
+                source,
+                updated_at,
+            } => MemeItem {
+                id: id.filter(|id| !id.is_empty()).unwrap_or_else(random_id),
+                url,
+                description,
+                score,
+                source,
+                updated_at,
+                path: None,
+            },
+        }
+    }
 }
 
 impl Default for MemeServerConfig {
@@ -42,14 +466,345 @@ impl Default for MemeServerConfig {
             // 示例API URL，实际使用时需要替换为真实的API地址
             api_url: "https://api.example.com/memes".to_string(),
             timeout_seconds: 10,
+            proxy_url: None,
+            user_agent: None,
+            test_mode: test_mode_from_env(),
+        }
+    }
+}
+
+/// 检查环境变量 `MEMEMEOW_TEST_MODE` 是否开启了测试模式
+pub fn test_mode_from_env() -> bool {
+    match std::env::var("MEMEMEOW_TEST_MODE") {
+        Ok(value) => matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"),
+        Err(_) => false,
+    }
+}
+
+/// 测试数据图片的边长（像素）
+const FIXTURE_IMAGE_SIZE: u32 = 64;
+
+/// 生成纯色PNG图片的 data URI，测试数据因此不依赖任何网络服务
+fn fixture_image_uri(seed: u64) -> String {
+    use base64::Engine;
+
+    let [r, g, b, ..] = seed.to_le_bytes();
+    let image = image::RgbaImage::from_pixel(
+        FIXTURE_IMAGE_SIZE,
+        FIXTURE_IMAGE_SIZE,
+        image::Rgba([r, g, b, 0xFF]),
+    );
+    let mut png = std::io::Cursor::new(Vec::new());
+    if let Err(e) = image.write_to(&mut png, image::ImageFormat::Png) {
+        error!("生成测试图片失败: {}", e);
+    }
+    format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(png.into_inner())
+    )
+}
+
+/// 根据关键词生成确定性的测试数据
+///
+/// 相同关键词总是得到相同结果，不同关键词的结果数量和内容各不相同，
+/// 图片以 data URI 内嵌，便于前端在离线环境下进行可复现的端到端测试。
+fn fixture_memes(keyword: &str) -> Vec<MemeItem> {
+    // FNV-1a 哈希，保证跨版本、跨平台结果稳定
+    let seed = keyword.trim().bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    let count = 3 + (seed % 6) as usize;
+
+    (0..count)
+        .map(|i| MemeItem {
+            id: format!("fixture-{:016x}-{}", seed, i),
+            url: fixture_image_uri(seed.rotate_left(i as u32 * 8)),
+            description: Some(format!("{} #{}", keyword.trim(), i + 1)),
+            score: Some(1.0 - i as f64 / count as f64),
+            source: None,
+            updated_at: None,
+            path: None,
+        })
+        .collect()
+}
+
+/// 搜索时实际使用的表情包库
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedLib {
+    pub uuid: String,
+    pub name: Option<String>,
+}
+
+/// 单次搜索的可选参数
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// 仅对本次搜索额外启用的表情包库
+    pub include_libs: Vec<String>,
+    /// 仅对本次搜索排除的表情包库
+    pub exclude_libs: Vec<String>,
+    /// 仅请求该时间（Unix秒）之后更新的表情包，服务器不支持时按普通搜索处理
+    pub since: Option<u64>,
+    /// 不读取搜索缓存，总是向API发送请求；结果仍会写入缓存
+    pub bypass_cache: bool,
+    /// 发送请求前扣除的搜索配额，命中缓存时不扣除
+    pub quota: Option<SearchQuota>,
+}
+
+/// 一次搜索占用的配额
+///
+/// 同一次搜索可能向多个API发送请求，克隆出的副本共享扣除结果，整次搜索只扣除一次。
+#[derive(Clone)]
+pub struct SearchQuota {
+    take: Arc<dyn Fn() -> Result<(), String> + Send + Sync>,
+    charged: Arc<OnceLock<Result<(), String>>>,
+}
+
+impl SearchQuota {
+    /// `take` 扣除一次配额，配额不足时返回错误描述
+    pub fn new(take: impl Fn() -> Result<(), String> + Send + Sync + 'static) -> Self {
+        Self {
+            take: Arc::new(take),
+            charged: Arc::new(OnceLock::new()),
+        }
+    }
+
+    fn charge(&self) -> Result<(), SearchError> {
+        self.charged
+            .get_or_init(|| (self.take)())
+            .clone()
+            .map_err(SearchError::QuotaExceeded)
+    }
+}
+
+impl std::fmt::Debug for SearchQuota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SearchQuota")
+            .field("charged", &self.charged.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// 解析某个关键词的搜索实际会发送的表情包库UUID列表
+///
+/// 搜索请求与预览命令共用此逻辑。在全局启用集合的基础上加入
+/// `include_libs`、去掉 `exclude_libs`，结果按UUID排序以保证稳定。
+pub fn resolve_search_libs(_keyword: &str, options: &SearchOptions) -> Vec<String> {
+    let mut libs = match get_enabled_meme_libs() {
+        Ok(libs) => libs,
+        Err(e) => {
+            error!("获取已启用的表情包库失败: {}", e);
+            Vec::new() // 出错时使用空数组
+        }
+    };
+
+    for uuid in &options.include_libs {
+        if !libs.contains(uuid) {
+            libs.push(uuid.clone());
         }
     }
+    libs.retain(|uuid| !options.exclude_libs.contains(uuid));
+
+    libs.sort();
+    libs
+}
+
+/// 搜索缓存最多保存的条目数
+const SEARCH_CACHE_CAPACITY: usize = 128;
+
+/// API地址对应的缓存命名空间：小写的主机名（含非默认端口），其他字符替换为 `_`
+///
+/// 也接受不带协议的主机名，便于前端直接传入 [`MemeServerClient::cache_namespaces`] 返回的值。
+pub fn cache_namespace(api_url: &str) -> String {
+    let api_url = api_url.trim();
+    let host = Url::parse(api_url)
+        .ok()
+        .and_then(|url| {
+            let host = url.host_str()?.to_string();
+            Some(match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host,
+            })
+        })
+        .unwrap_or_else(|| api_url.to_string());
+    host.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// 搜索缓存的键：同一API、关键词、表情包库组合与请求参数才视为相同的搜索
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SearchCacheKey {
+    api_url: String,
+    keyword: String,
+    libs: Vec<String>,
+    result_limit: usize,
+    since: Option<u64>,
+}
+
+struct SearchCacheEntry {
+    items: Vec<MemeItem>,
+    stored_at: Instant,
+}
+
+/// 按最近使用顺序淘汰的搜索结果缓存
+#[derive(Default)]
+struct SearchCache {
+    entries: HashMap<SearchCacheKey, SearchCacheEntry>,
+    /// 从最久未使用到最近使用排列
+    order: VecDeque<SearchCacheKey>,
+}
+
+impl SearchCache {
+    fn touch(&mut self, key: &SearchCacheKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key.clone());
+    }
+
+    /// 获取未过期的缓存结果，过期的条目会被移除
+    fn get(&mut self, key: &SearchCacheKey, ttl: Duration) -> Option<Vec<MemeItem>> {
+        let fresh = self.entries.get(key)?.stored_at.elapsed() < ttl;
+        if !fresh {
+            self.entries.remove(key);
+            self.order.retain(|k| k != key);
+            return None;
+        }
+        self.touch(key);
+        self.entries.get(key).map(|entry| entry.items.clone())
+    }
+
+    fn insert(&mut self, key: SearchCacheKey, items: Vec<MemeItem>) {
+        self.touch(&key);
+        self.entries.insert(
+            key,
+            SearchCacheEntry {
+                items,
+                stored_at: Instant::now(),
+            },
+        );
+        while self.entries.len() > SEARCH_CACHE_CAPACITY {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) -> usize {
+        let count = self.entries.len();
+        self.entries.clear();
+        self.order.clear();
+        count
+    }
+
+    /// 缓存中出现的命名空间，按字母顺序排列
+    fn namespaces(&self) -> Vec<String> {
+        let mut namespaces: Vec<String> = self
+            .entries
+            .keys()
+            .map(|key| cache_namespace(&key.api_url))
+            .collect();
+        namespaces.sort();
+        namespaces.dedup();
+        namespaces
+    }
+
+    /// 清除属于指定命名空间的条目，返回清除的数量
+    fn clear_namespace(&mut self, namespace: &str) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|key, _| cache_namespace(&key.api_url) != namespace);
+        self.order
+            .retain(|key| cache_namespace(&key.api_url) != namespace);
+        before - self.entries.len()
+    }
 }
 
 /// 表情包服务客户端
 pub struct MemeServerClient {
     client: reqwest::Client,
     config: MemeServerConfig,
+    cache: Mutex<SearchCache>,
+    /// 当前API连续搜索失败的次数
+    consecutive_failures: AtomicU32,
+}
+
+/// 连续搜索失败达到该次数后自动切换到下一个API
+pub const FAILOVER_THRESHOLD: u32 = 3;
+
+/// 由API地址构建搜索接口的完整URL
+fn search_url(api_url: &str) -> Result<Url, SearchError> {
+    let full_url = format!("{}/{}", api_url, "search");
+    Url::parse(&full_url).map_err(|e| {
+        error!("API地址无效: {} - {}", full_url, e);
+        SearchError::InvalidUrl(full_url.clone(), e.to_string())
+    })
+}
+
+/// 构建搜索请求的参数，`since` 未指定时不包含该字段
+fn search_payload(keyword: &str, result_limit: usize, libs: &[String], since: Option<u64>) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "query": keyword,
+        "n_results": result_limit,
+        "resource_pack_uuids": libs
+    });
+    if let Some(since) = since {
+        payload["since"] = serde_json::json!(since);
+    }
+    payload
+}
+
+/// 按API源要求的格式编码搜索请求体，返回 `Content-Type` 与请求体
+///
+/// 表单格式下数组字段展开为重复的键，例如 `resource_pack_uuids=a&resource_pack_uuids=b`。
+pub fn encode_search_body(format: RequestFormat, payload: &serde_json::Value) -> (&'static str, String) {
+    if format == RequestFormat::Json {
+        return ("application/json", payload.to_string());
+    }
+
+    let scalar = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    if let Some(fields) = payload.as_object() {
+        for (key, value) in fields {
+            match value {
+                serde_json::Value::Array(values) => {
+                    pairs.extend(values.iter().map(|v| (key.clone(), scalar(v))));
+                }
+                serde_json::Value::Null => {}
+                other => pairs.push((key.clone(), scalar(other))),
+            }
+        }
+    }
+
+    match serde_urlencoded::to_string(&pairs) {
+        Ok(body) => ("application/x-www-form-urlencoded", body),
+        Err(e) => {
+            error!("表单编码失败，改用JSON: {}", e);
+            ("application/json", payload.to_string())
+        }
+    }
+}
+
+/// 为单个请求设置超时，覆盖客户端构建时的默认值
+fn with_timeout(builder: RequestBuilder, timeout: Option<Duration>) -> RequestBuilder {
+    match timeout {
+        Some(timeout) => builder.timeout(timeout),
+        None => builder,
+    }
+}
+
+/// 附加API源配置的请求头，如认证信息
+fn with_api_headers(builder: RequestBuilder, api: &ApiUrl) -> RequestBuilder {
+    api.headers
+        .iter()
+        .fold(builder, |builder, (name, value)| builder.header(name.as_str(), value.as_str()))
 }
 
 impl MemeServerClient {
@@ -57,90 +812,775 @@ impl MemeServerClient {
     pub fn new(config: Option<MemeServerConfig>) -> Self {
         let config = config.unwrap_or_default();
 
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout_seconds))
-            .build()
-            .expect("Failed to create HTTP client");
+        let mut builder =
+            reqwest::Client::builder().timeout(Duration::from_secs(config.timeout_seconds));
+
+        if let Some(proxy_url) = config.proxy_url.as_deref().filter(|u| !u.trim().is_empty()) {
+            match crate::utils::network::parse_proxy(proxy_url) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(e) => error!("{}，将不使用代理", e),
+            }
+        }
 
-        Self { client, config }
+        if let Some(user_agent) = config.user_agent.as_deref().filter(|u| !u.trim().is_empty()) {
+            match reqwest::header::HeaderValue::from_str(user_agent.trim()) {
+                Ok(user_agent) => builder = builder.user_agent(user_agent),
+                Err(e) => error!("User-Agent 无效: {}，将使用默认值", e),
+            }
+        }
+
+        let client = builder.build().unwrap_or_else(|e| {
+            error!("创建HTTP客户端失败: {}，将使用默认设置", e);
+            reqwest::Client::new()
+        });
+
+        Self {
+            client,
+            config,
+            cache: Mutex::new(SearchCache::default()),
+            consecutive_failures: AtomicU32::new(0),
+        }
     }
 
     /// 更新API URL
     pub fn update_api_url(&mut self, url: String) {
         self.config.api_url = url;
     }
+
+    /// 记录一次搜索失败，返回当前的连续失败次数
+    pub fn record_failure(&self) -> u32 {
+        self.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// 搜索成功或已切换API后清零连续失败次数
+    pub fn reset_failures(&self) {
+        self.consecutive_failures.store(0, Ordering::SeqCst);
+    }
+
+    /// 清空搜索缓存，返回清除的条目数
+    pub fn clear_cache(&self) -> usize {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).clear()
+    }
+
+    /// 搜索缓存中的命名空间（按API主机划分）
+    pub fn cache_namespaces(&self) -> Vec<String> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).namespaces()
+    }
+
+    /// 清除指定API主机的搜索缓存，`host` 可以是主机名或完整的API地址，返回清除的条目数
+    pub fn clear_cache_namespace(&self, host: &str) -> usize {
+        self.cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear_namespace(&cache_namespace(host))
+    }
     
     /// 搜索表情包
-    pub async fn search_memes(&self, keyword: &str) -> Result<Vec<MemeItem>, Error> {
-        // 先获取当前配置中的API URL
-        let api_url = match crate::get_config_manager().get_active_api_url() {
-            Ok(url) => url,
-            Err(_) => self.config.api_url.clone(), // 如果获取失败，则使用默认值
+    pub async fn search_memes(
+        &self,
+        keyword: &str,
+        options: &SearchOptions,
+        timeout_override: Option<Duration>,
+    ) -> Result<Vec<MemeItem>, SearchError> {
+        self.search_memes_traced(keyword, options, timeout_override)
+            .await
+            .map(|response| response.items)
+    }
+
+    /// 搜索表情包，同时返回结果来源（缓存与API地址）
+    pub async fn search_memes_traced(
+        &self,
+        keyword: &str,
+        options: &SearchOptions,
+        timeout_override: Option<Duration>,
+    ) -> Result<SearchResponse, SearchError> {
+        self.search_preview_traced(crate::get_config_manager(), keyword, options, timeout_override)
+            .await
+    }
+
+    /// 在 `config` 配置的预览源上搜索表情包
+    pub async fn search_preview_traced(
+        &self,
+        config: &crate::config_manager::ConfigManager,
+        keyword: &str,
+        options: &SearchOptions,
+        timeout_override: Option<Duration>,
+    ) -> Result<SearchResponse, SearchError> {
+        // 先获取当前配置中用于搜索的预览源
+        let api = match config.get_preview_api() {
+            Ok(api) => api,
+            // 如果获取失败，则使用默认值
+            Err(_) => ApiUrl {
+                name: String::new(),
+                url: self.config.api_url.clone(),
+                request_format: RequestFormat::default(),
+                search_method: SearchMethod::default(),
+                results_key: None,
+                headers: HashMap::new(),
+            },
+        };
+
+        self.search_traced_at(&api, keyword, options, timeout_override)
+            .await
+    }
+
+    /// 在指定的API源上搜索表情包
+    ///
+    /// `timeout_override` 仅作用于本次请求，未指定时使用客户端构建时的超时。
+    pub async fn search_memes_at(
+        &self,
+        api: &ApiUrl,
+        keyword: &str,
+        options: &SearchOptions,
+        timeout_override: Option<Duration>,
+    ) -> Result<Vec<MemeItem>, SearchError> {
+        self.search_traced_at(api, keyword, options, timeout_override)
+            .await
+            .map(|response| response.items)
+    }
+
+    async fn search_traced_at(
+        &self,
+        api: &ApiUrl,
+        keyword: &str,
+        options: &SearchOptions,
+        timeout_override: Option<Duration>,
+    ) -> Result<SearchResponse, SearchError> {
+        let respond = |items: Vec<MemeItem>, from_cache: bool| SearchResponse {
+            items,
+            from_cache,
+            api_url: api.url.clone(),
         };
 
+        if self.config.test_mode {
+            debug!("测试模式已启用，返回固定测试数据，关键词: {}", keyword);
+            return Ok(respond(fixture_memes(keyword), false));
+        }
+
         info!("正在搜索表情包，关键词: {}", keyword);
 
         // 构建请求参数
-        let payload = serde_json::json!({
-            "query": keyword,
-            "n_results": 10,  // 限制返回10个结果
-            "resource_pack_uuids": match get_enabled_meme_libs() {
-                Ok(libs) => libs,
-                Err(e) => {
-                    error!("获取已启用的表情包库失败: {}", e);
-                    Vec::new() // 出错时使用空数组
+        let result_limit = crate::get_config_manager().get_result_limit().unwrap_or(10);
+        let libs = resolve_search_libs(keyword, options);
+
+        // 缓存有效期内的相同搜索直接返回缓存结果
+        let cache_ttl = Duration::from_secs(
+            crate::get_config_manager()
+                .get_preferences()
+                .map(|prefs| prefs.search_cache_ttl_secs)
+                .unwrap_or(60),
+        );
+        let cache_key = SearchCacheKey {
+            api_url: api.url.clone(),
+            keyword: keyword.trim().to_string(),
+            libs: libs.clone(),
+            result_limit,
+            since: options.since,
+        };
+        if let Some(items) = self.cached_search(&cache_key, cache_ttl, options) {
+            debug!("命中搜索缓存，关键词: {}", keyword);
+            return Ok(respond(items, true));
+        }
+
+        let url = search_url(&api.url)?;
+        if let Some(quota) = &options.quota {
+            quota.charge()?;
+        }
+
+        let payload = search_payload(keyword, result_limit, &libs, options.since);
+
+        let json_data = match api.search_method {
+            SearchMethod::Post => {
+                self.send_search_post(api, url, &payload, timeout_override)
+                    .await?
+            }
+            SearchMethod::Get => self.send_search_get(api, url, &payload, timeout_override).await?,
+            SearchMethod::Auto => {
+                match self
+                    .send_search_post(api, url.clone(), &payload, timeout_override)
+                    .await
+                {
+                    Err(SearchError::Status(status))
+                        if status == StatusCode::NOT_FOUND
+                            || status == StatusCode::METHOD_NOT_ALLOWED =>
+                    {
+                        info!("POST 搜索返回 {}，改用 GET 重试", status);
+                        self.send_search_get(api, url, &payload, timeout_override).await?
+                    }
+                    other => other?,
                 }
             }
-        });
+        };
+        let entries = parse_search_response(&json_data, api.results_key.as_deref()).map_err(|e| {
+            error!("JSON解析失败: {}", e);
+            SearchError::Parse(e)
+        })?;
 
-        debug!("发送请求到: {}", api_url);
-        debug!("请求参数: {:?}", payload.to_string());
+        // 将数据转换为 MemeItem 向量
+        let mut meme_items: Vec<MemeItem> = entries
+            .into_iter()
+            .map(SearchResultEntry::into_meme_item)
+            .collect();
 
-        // let query_string = serde_urlencoded::to_string(&payload).unwrap();
+        // 服务器忽略 since 时，对带有更新时间的结果在本地过滤
+        if let Some(since) = options.since {
+            meme_items.retain(|item| item.updated_at.is_none_or(|t| t > since));
+        }
 
-        // 构建完整URL（包括接口）
-        let full_url = format!("{}/{}", api_url, "search");
-        let url = Url::parse(&full_url).map_err(|e| {
-            error!("请求失败: {}", e);
-            e
-        }).unwrap();
-        debug!("完整请求URL: {}", url);
+        debug!("解析得到 {} 个表情包项目", meme_items.len());
+        if !cache_ttl.is_zero() {
+            self.cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(cache_key, meme_items.clone());
+        }
+        Ok(respond(meme_items, false))
+
+        // Ok(vec![])
+        // Ok(json_data)
+    }
+
+    /// 查找有效期内的缓存结果，`ttl` 为零或要求跳过缓存时不查找
+    fn cached_search(
+        &self,
+        key: &SearchCacheKey,
+        ttl: Duration,
+        options: &SearchOptions,
+    ) -> Option<Vec<MemeItem>> {
+        if ttl.is_zero() || options.bypass_cache {
+            return None;
+        }
+        self.cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key, ttl)
+    }
 
-        let request_builder: RequestBuilder = self.client
+    /// 以 POST 方式发送搜索请求，返回响应正文
+    async fn send_search_post(
+        &self,
+        api: &ApiUrl,
+        url: Url,
+        payload: &serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<String, SearchError> {
+        let (content_type, body) = encode_search_body(api.request_format, payload);
+        let builder = self
+            .client
             .request(Method::POST, url)
-            .header("Content-Type", "application/json")
-            .body(payload.to_string());
+            .header("Content-Type", content_type)
+            .body(body);
+        let builder = with_api_headers(builder, api);
+        self.send_search_request(with_timeout(builder, timeout).build()?)
+            .await
+    }
 
-        let response = request_builder.send().await.map_err(|e| {
+    /// 以 GET 方式发送搜索请求，参数以查询字符串形式附加在URL上
+    async fn send_search_get(
+        &self,
+        api: &ApiUrl,
+        mut url: Url,
+        payload: &serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<String, SearchError> {
+        let (_, query) = encode_search_body(RequestFormat::Form, payload);
+        url.set_query(Some(&query));
+        let builder = with_api_headers(self.client.request(Method::GET, url), api);
+        self.send_search_request(with_timeout(builder, timeout).build()?)
+            .await
+    }
+
+    async fn send_search_request(&self, request: Request) -> Result<String, SearchError> {
+        request_trace::trace_request(&request);
+
+        let response = self.client.execute(request).await.map_err(|e| {
             error!("请求失败: {}", e);
             e
         })?;
-        debug!("响应状态: {}", response.status());
-        debug!("响应头: {:?}", response.headers());
+        let status = response.status();
+        let headers = response.headers().clone();
+        let response_url = response.url().to_string();
         let json_data = response.text().await?;
-        let meme_response: MemeSearchResponse = serde_json::from_str(&json_data)
-            .map_err(|e| {
-                error!("JSON解析失败: {}", e);
-                e
-            })
+        request_trace::trace_response(&response_url, status, &headers, &json_data);
+        if !status.is_success() {
+            error!("搜索请求返回错误状态码: {}", status);
+            return Err(SearchError::Status(status));
+        }
+        Ok(json_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockResponse, MockServer};
+
+    fn item(id: &str, url: &str) -> MemeItem {
+        MemeItem {
+            id: id.to_string(),
+            url: url.to_string(),
+            description: None,
+            score: None,
+            source: None,
+            updated_at: None,
+            path: None,
+        }
+    }
+
+    fn cache_key(keyword: &str, libs: &[&str]) -> SearchCacheKey {
+        cache_key_at("https://api.example.com", keyword, libs)
+    }
+
+    fn cache_key_at(api_url: &str, keyword: &str, libs: &[&str]) -> SearchCacheKey {
+        SearchCacheKey {
+            api_url: api_url.to_string(),
+            keyword: keyword.to_string(),
+            libs: libs.iter().map(|lib| lib.to_string()).collect(),
+            result_limit: 10,
+            since: None,
+        }
+    }
+
+    #[test]
+    fn search_cache_hit() {
+        let mut cache = SearchCache::default();
+        cache.insert(cache_key("cat", &["a"]), vec![item("1", "https://x/1.png")]);
+
+        let hit = cache.get(&cache_key("cat", &["a"]), Duration::from_secs(60)).unwrap();
+        assert_eq!(hit.len(), 1);
+        assert_eq!(hit[0].id, "1");
+    }
+
+    #[test]
+    fn search_cache_expires_after_ttl() {
+        let mut cache = SearchCache::default();
+        let key = cache_key("cat", &["a"]);
+        cache.insert(key.clone(), vec![item("1", "https://x/1.png")]);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(cache.get(&key, Duration::from_millis(10)).is_none());
+        // 过期条目已被移除，即使放宽有效期也不再命中
+        assert!(cache.get(&key, Duration::from_secs(60)).is_none());
+        assert!(cache.entries.is_empty() && cache.order.is_empty());
+    }
+
+    #[test]
+    fn search_cache_misses_when_libs_differ() {
+        let mut cache = SearchCache::default();
+        cache.insert(cache_key("cat", &["a"]), vec![item("1", "https://x/1.png")]);
+
+        let ttl = Duration::from_secs(60);
+        assert!(cache.get(&cache_key("cat", &["b"]), ttl).is_none());
+        assert!(cache.get(&cache_key("cat", &["a", "b"]), ttl).is_none());
+        assert!(cache.get(&cache_key("cat", &[]), ttl).is_none());
+    }
+
+    #[test]
+    fn search_cache_evicts_least_recently_used() {
+        let mut cache = SearchCache::default();
+        let ttl = Duration::from_secs(60);
+        for i in 0..SEARCH_CACHE_CAPACITY {
+            cache.insert(cache_key(&i.to_string(), &[]), Vec::new());
+        }
+        // 访问最早的条目后，再插入时应淘汰第二早的条目
+        assert!(cache.get(&cache_key("0", &[]), ttl).is_some());
+        cache.insert(cache_key("new", &[]), Vec::new());
+
+        assert_eq!(cache.entries.len(), SEARCH_CACHE_CAPACITY);
+        assert!(cache.get(&cache_key("0", &[]), ttl).is_some());
+        assert!(cache.get(&cache_key("1", &[]), ttl).is_none());
+    }
+
+    #[test]
+    fn cache_namespace_is_sanitized_host() {
+        assert_eq!(cache_namespace("https://API.Example.com/v1/"), "api.example.com");
+        assert_eq!(cache_namespace("http://127.0.0.1:8000"), "127.0.0.1_8000");
+        assert_eq!(cache_namespace("https://example.com:443/api"), "example.com");
+        // 不带协议的主机名与对应的API地址属于同一命名空间
+        assert_eq!(cache_namespace(" api.example.com "), "api.example.com");
+        assert_eq!(cache_namespace("127.0.0.1_8000"), "127.0.0.1_8000");
+    }
+
+    #[test]
+    fn search_cache_clears_single_namespace() {
+        let mut cache = SearchCache::default();
+        let ttl = Duration::from_secs(60);
+        cache.insert(cache_key_at("https://a.example.com", "cat", &[]), Vec::new());
+        cache.insert(cache_key_at("https://a.example.com/v2", "dog", &[]), Vec::new());
+        cache.insert(cache_key_at("http://localhost:8000", "cat", &[]), Vec::new());
+
+        assert_eq!(cache.namespaces(), ["a.example.com", "localhost_8000"]);
+        assert_eq!(cache.clear_namespace("a.example.com"), 2);
+        assert_eq!(cache.namespaces(), ["localhost_8000"]);
+        assert_eq!(cache.order.len(), 1);
+        assert!(cache.get(&cache_key_at("http://localhost:8000", "cat", &[]), ttl).is_some());
+        assert_eq!(cache.clear_namespace("a.example.com"), 0);
+    }
+
+    #[test]
+    fn bypass_cache_skips_cached_results() {
+        let client = MemeServerClient::new(None);
+        let key = cache_key("cat", &[]);
+        client
+            .cache
+            .lock()
+            .unwrap()
+            .insert(key.clone(), vec![item("1", "https://x/1.png")]);
+        let ttl = Duration::from_secs(60);
+
+        assert!(client.cached_search(&key, ttl, &SearchOptions::default()).is_some());
+        let bypass = SearchOptions {
+            bypass_cache: true,
+            ..SearchOptions::default()
+        };
+        assert!(client.cached_search(&key, ttl, &bypass).is_none());
+        assert!(client.cached_search(&key, Duration::ZERO, &SearchOptions::default()).is_none());
+    }
+
+    #[test]
+    fn invalid_api_url_is_an_error() {
+        for api_url in ["", "not a url", "http://exa mple.com", "://missing-scheme"] {
+            match search_url(api_url) {
+                Err(SearchError::InvalidUrl(url, _)) => assert_eq!(url, format!("{}/search", api_url)),
+                other => panic!("expected InvalidUrl for {:?}, got {:?}", api_url, other),
+            }
+        }
+        assert_eq!(
+            search_url("https://api.example.com").unwrap().as_str(),
+            "https://api.example.com/search"
+        );
+    }
+
+    #[test]
+    fn payload_contains_result_limit() {
+        let libs = vec!["lib-a".to_string()];
+        let payload = search_payload("cat", 25, &libs, None);
+        assert_eq!(payload["n_results"], 25);
+        assert_eq!(payload["query"], "cat");
+        assert_eq!(payload["resource_pack_uuids"], serde_json::json!(["lib-a"]));
+        assert!(payload.get("since").is_none());
+
+        assert_eq!(search_payload("cat", 100, &libs, Some(1700000000))["since"], 1700000000);
+    }
+
+    #[test]
+    fn payload_uses_clamped_configured_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::config_manager::ConfigManager::open(dir.path().join("preferences.json"));
+        config.update_result_limit(1000).unwrap();
+
+        let limit = config.get_result_limit().unwrap();
+        assert_eq!(limit, crate::config_manager::MAX_RESULT_LIMIT);
+        assert_eq!(search_payload("cat", limit, &[], None)["n_results"], limit);
+    }
+
+    #[test]
+    fn json_body_is_payload_as_is() {
+        let payload = serde_json::json!({"query": "猫", "n_results": 10, "resource_pack_uuids": ["a", "b"]});
+        let (content_type, body) = encode_search_body(RequestFormat::Json, &payload);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap(), payload);
+    }
+
+    #[test]
+    fn form_body_repeats_array_keys() {
+        let payload = serde_json::json!({
+            "query": "猫 猫",
+            "n_results": 10,
+            "resource_pack_uuids": ["a", "b"],
+            "since": null
+        });
+        let (content_type, body) = encode_search_body(RequestFormat::Form, &payload);
+        assert_eq!(content_type, "application/x-www-form-urlencoded");
+        assert_eq!(
+            body,
+            "n_results=10&query=%E7%8C%AB+%E7%8C%AB&resource_pack_uuids=a&resource_pack_uuids=b"
+        );
+    }
+
+    fn api(url: &str, search_method: SearchMethod) -> ApiUrl {
+        ApiUrl {
+            name: url.to_string(),
+            url: url.to_string(),
+            request_format: RequestFormat::default(),
+            search_method,
+            results_key: None,
+            headers: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn auto_search_falls_back_to_get_on_405() {
+        let server = MockServer::start(|request| match request.method.as_str() {
+            "POST" => MockResponse::status(405),
+            _ => MockResponse::json(r#"["https://x/1.png"]"#),
+        })
+        .await;
+        let client = MemeServerClient::new(None);
+
+        let items = client
+            .search_memes_at(&api(&server.url, SearchMethod::Auto), "cat", &SearchOptions::default(), None)
+            .await
             .unwrap();
-        debug!("响应体: {:?}", meme_response);
+        assert_eq!(items[0].url, "https://x/1.png");
 
-        // 将数据转换为 MemeItem 向量
-        let meme_items: Vec<MemeItem> = meme_response
-            .results
+        let requests = server.requests();
+        let methods: Vec<_> = requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, ["POST", "GET"]);
+        assert!(requests[0].body.contains(r#""query":"cat""#), "{}", requests[0].body);
+        assert!(requests[1].target.starts_with("/search?"));
+        assert!(requests[1].target.contains("query=cat"), "{}", requests[1].target);
+    }
+
+    #[test]
+    fn per_request_timeout_is_applied() {
+        let client = Client::new();
+        let timeout = Duration::from_secs(3);
+        let request = with_timeout(client.get("https://api.example.com/search"), Some(timeout))
+            .build()
+            .unwrap();
+        assert_eq!(request.timeout(), Some(&timeout));
+
+        let request = with_timeout(client.get("https://api.example.com/search"), None)
+            .build()
+            .unwrap();
+        assert_eq!(request.timeout(), None);
+    }
+
+    #[tokio::test]
+    async fn search_times_out_with_override() {
+        let server = MockServer::start(|_| MockResponse {
+            delay: Duration::from_secs(2),
+            ..MockResponse::json("[]")
+        })
+        .await;
+        let client = MemeServerClient::new(None);
+
+        let started = std::time::Instant::now();
+        let result = client
+            .search_memes_at(
+                &api(&server.url, SearchMethod::Post),
+                "cat",
+                &SearchOptions::default(),
+                Some(Duration::from_millis(100)),
+            )
+            .await;
+        assert!(matches!(result, Err(SearchError::Request(ref e)) if e.is_timeout()), "{:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn forced_post_does_not_fall_back() {
+        let server = MockServer::start(|_| MockResponse::status(405)).await;
+        let client = MemeServerClient::new(None);
+
+        let result = client
+            .search_memes_at(&api(&server.url, SearchMethod::Post), "cat", &SearchOptions::default(), None)
+            .await;
+        assert!(matches!(result, Err(SearchError::Status(StatusCode::METHOD_NOT_ALLOWED))));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    fn parse_urls(body: &str, results_key: Option<&str>) -> Vec<String> {
+        parse_search_response(body, results_key)
+            .unwrap()
             .into_iter()
-            .map(|url| MemeItem {
-                id: uuid::Uuid::new_v4().to_string(), // 生成唯一ID
-                url,
-                description: None,
-            })
+            .map(|entry| entry.into_meme_item().url)
+            .collect()
+    }
+
+    #[test]
+    fn parses_results_field() {
+        let body = r#"{"results": ["https://x/1.png", {"url": "https://x/2.png"}]}"#;
+        assert_eq!(parse_urls(body, None), ["https://x/1.png", "https://x/2.png"]);
+    }
+
+    #[test]
+    fn parses_data_field() {
+        let body = r#"{"data": [{"id": "a", "url": "https://x/1.png"}]}"#;
+        assert_eq!(parse_urls(body, None), ["https://x/1.png"]);
+    }
+
+    #[test]
+    fn parses_bare_array() {
+        let body = r#"["https://x/1.png", "https://x/2.png"]"#;
+        assert_eq!(parse_urls(body, None), ["https://x/1.png", "https://x/2.png"]);
+        // 空白的 results_key 等同于未指定
+        assert_eq!(parse_urls(body, Some("  ")), ["https://x/1.png", "https://x/2.png"]);
+    }
+
+    #[test]
+    fn parses_custom_results_key() {
+        let body = r#"{"payload": {"hits": [{"url": "https://x/1.png"}]}, "results": []}"#;
+        assert_eq!(parse_urls(body, Some("payload.hits")), ["https://x/1.png"]);
+
+        let nested = r#"{"pages": [{"items": ["https://x/2.png"]}]}"#;
+        assert_eq!(parse_urls(nested, Some("pages.0.items")), ["https://x/2.png"]);
+
+        assert!(parse_search_response(body, Some("payload.missing")).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_response_shape() {
+        assert!(parse_search_response(r#"{"memes": []}"#, None).is_err());
+        assert!(parse_search_response("not json", None).is_err());
+    }
+
+    #[test]
+    fn entry_maps_all_item_fields() {
+        let body = r#"[{
+            "id": "a",
+            "url": "https://x/1.png",
+            "description": "cat",
+            "score": 0.75,
+            "source": "lib-1",
+            "updated_at": 1700000000
+        }]"#;
+        let item = parse_search_response(body, None)
+            .unwrap()
+            .remove(0)
+            .into_meme_item();
+        assert_eq!(item.id, "a");
+        assert_eq!(item.description.as_deref(), Some("cat"));
+        assert_eq!(item.score, Some(0.75));
+        assert_eq!(item.source.as_deref(), Some("lib-1"));
+        assert_eq!(item.updated_at, Some(1700000000));
+    }
+
+    #[test]
+    fn entry_without_id_gets_random_id() {
+        let body = r#"["https://x/1.png", {"id": "", "url": "https://x/2.png"}]"#;
+        let items: Vec<MemeItem> = parse_search_response(body, None)
+            .unwrap()
+            .into_iter()
+            .map(SearchResultEntry::into_meme_item)
             .collect();
+        assert!(items.iter().all(|item| uuid::Uuid::parse_str(&item.id).is_ok()));
+        assert_ne!(items[0].id, items[1].id);
+    }
 
-        debug!("解析得到 {} 个表情包项目", meme_items.len());
-        Ok(meme_items)
+    #[test]
+    fn filter_blocked_matches_url_and_description() {
+        let mut described = item("2", "https://x/2.png");
+        described.description = Some("Spoiler alert".to_string());
+        let items = vec![
+            item("1", "https://x/NSFW/1.png"),
+            described,
+            item("3", "https://x/3.png"),
+        ];
+        let blocklist = vec!["nsfw".to_string(), " SPOILER ".to_string(), "  ".to_string()];
 
-        // Ok(vec![])
-        // Ok(json_data)
+        let (kept, blocked) = filter_blocked(items, &blocklist);
+        assert_eq!(blocked, 2);
+        assert_eq!(kept.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), ["3"]);
+    }
+
+    #[test]
+    fn filter_blocked_with_empty_blocklist_keeps_everything() {
+        let items = vec![item("1", "https://x/1.png"), item("2", "https://x/2.png")];
+        let (kept, blocked) = filter_blocked(items, &[" ".to_string()]);
+        assert_eq!((kept.len(), blocked), (2, 0));
+    }
+
+    #[test]
+    fn api_headers_are_attached_to_request() {
+        let mut api = ApiUrl {
+            name: "private".to_string(),
+            url: "https://api.example.com".to_string(),
+            request_format: RequestFormat::default(),
+            search_method: SearchMethod::default(),
+            results_key: None,
+            headers: HashMap::new(),
+        };
+        api.headers.insert("Authorization".to_string(), "Bearer secret".to_string());
+        api.headers.insert("X-Tenant".to_string(), "team-a".to_string());
+
+        let client = Client::new();
+        let builder = client.request(Method::POST, "https://api.example.com/search");
+        let request = with_api_headers(builder, &api).build().unwrap();
+
+        let headers = request.headers();
+        assert_eq!(headers["authorization"], "Bearer secret");
+        assert_eq!(headers["x-tenant"], "team-a");
+    }
+
+    #[test]
+    fn invalid_user_agent_is_ignored() {
+        let config = MemeServerConfig {
+            user_agent: Some("MemeMeow\r\nX-Injected: 1".to_string()),
+            proxy_url: Some("not a proxy".to_string()),
+            ..MemeServerConfig::default()
+        };
+        let client = MemeServerClient::new(Some(config));
+        assert_eq!(client.config.user_agent.as_deref(), Some("MemeMeow\r\nX-Injected: 1"));
+    }
+
+    #[test]
+    fn fixtures_are_deterministic_data_uris() {
+        let first = fixture_memes("猫猫");
+        let again = fixture_memes(" 猫猫 ");
+        assert!((3..=8).contains(&first.len()));
+        assert_eq!(
+            first.iter().map(|item| &item.url).collect::<Vec<_>>(),
+            again.iter().map(|item| &item.url).collect::<Vec<_>>()
+        );
+
+        for item in &first {
+            let data = item.url.strip_prefix("data:image/png;base64,").unwrap();
+            let png = {
+                use base64::Engine;
+                base64::engine::general_purpose::STANDARD.decode(data).unwrap()
+            };
+            let image = image::load_from_memory(&png).unwrap();
+            assert_eq!(image.width(), FIXTURE_IMAGE_SIZE);
+        }
+    }
+
+    // 记录扣除次数的配额，超过 `limit` 次后拒绝
+    fn counting_quota(used: &Arc<AtomicU32>, limit: u32) -> SearchQuota {
+        let used = Arc::clone(used);
+        SearchQuota::new(move || {
+            if used.fetch_add(1, Ordering::SeqCst) < limit {
+                Ok(())
+            } else {
+                Err("配额已用完".to_string())
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn quota_is_charged_only_for_network_requests() {
+        let server = MockServer::start(|_| MockResponse::json(r#"["https://x/1.png"]"#)).await;
+        let api = api(&server.url, SearchMethod::Post);
+        let client = MemeServerClient::new(None);
+        let used = Arc::new(AtomicU32::new(0));
+
+        for _ in 0..2 {
+            let options = SearchOptions {
+                quota: Some(counting_quota(&used, 10)),
+                ..SearchOptions::default()
+            };
+            client.search_memes_at(&api, "cat", &options, None).await.unwrap();
+        }
+        // 第二次命中缓存，不占用配额
+        assert_eq!(used.load(Ordering::SeqCst), 1);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn exhausted_quota_sends_no_request() {
+        let server = MockServer::start(|_| MockResponse::json(r#"["https://x/1.png"]"#)).await;
+        let client = MemeServerClient::new(None);
+        let used = Arc::new(AtomicU32::new(0));
+        let options = SearchOptions {
+            quota: Some(counting_quota(&used, 0)),
+            ..SearchOptions::default()
+        };
+
+        let result = client
+            .search_memes_at(&api(&server.url, SearchMethod::Post), "cat", &options, None)
+            .await;
+        assert!(matches!(result, Err(SearchError::QuotaExceeded(_))), "{:?}", result);
+        assert!(server.requests().is_empty());
     }
 }
@@ -0,0 +1,332 @@
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utils::misc::{app_dirs, write_atomic};
+
+// 一天的秒数
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+// 串行化历史记录文件的读-改-写
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+// 一条搜索记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub keyword: String,
+    // 最近一次搜索的时间（Unix秒）
+    pub searched_at: u64,
+}
+
+// 搜索历史，最近的在前
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchHistory {
+    #[serde(default)]
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl SearchHistory {
+    // 记录一次搜索，重复的关键词移到最前，超出条数上限时淘汰最旧的记录
+    pub fn record(&mut self, keyword: &str, now: u64, limit: usize) {
+        let keyword = keyword.trim();
+        if keyword.is_empty() {
+            return;
+        }
+
+        self.entries.retain(|entry| entry.keyword != keyword);
+        self.entries.insert(
+            0,
+            HistoryEntry {
+                keyword: keyword.to_string(),
+                searched_at: now,
+            },
+        );
+        self.entries.truncate(limit.max(1));
+    }
+
+    // 删除指定关键词的记录，返回是否找到
+    pub fn remove(&mut self, keyword: &str) -> bool {
+        let keyword = keyword.trim();
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.keyword != keyword);
+        self.entries.len() != before
+    }
+
+    // 删除早于截止时间的记录，返回删除的数量
+    pub fn purge_older_than(&mut self, cutoff: u64) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.searched_at >= cutoff);
+        before - self.entries.len()
+    }
+
+    // 最近搜索的 `count` 个关键词，跳过短于 `min_len` 个字符的关键词
+    pub fn top_keywords(&self, count: usize, min_len: usize) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.keyword.chars().count() >= min_len.max(1))
+            .take(count)
+            .map(|entry| entry.keyword.clone())
+            .collect()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// 获取历史记录文件路径
+fn get_history_path() -> Result<PathBuf, String> {
+    let config_dir = app_dirs().config_dir();
+    if !config_dir.exists() {
+        if let Err(e) = fs::create_dir_all(&config_dir) {
+            return Err(format!("创建配置目录失败: {}", e));
+        }
+    }
+
+    Ok(config_dir.join("history.json"))
+}
+
+// 加载搜索历史，文件不存在时返回空历史
+pub fn load_history() -> Result<SearchHistory, String> {
+    load_history_from(&get_history_path()?)
+}
+
+fn load_history_from(file_path: &Path) -> Result<SearchHistory, String> {
+    if !file_path.exists() {
+        return Ok(SearchHistory::default());
+    }
+
+    let content = fs::read_to_string(file_path).map_err(|e| {
+        error!("读取搜索历史失败: {}", e);
+        format!("读取搜索历史失败: {}", e)
+    })?;
+
+    serde_json::from_str(&content).map_err(|e| {
+        error!("解析搜索历史失败: {}", e);
+        format!("解析搜索历史失败: {}", e)
+    })
+}
+
+// 保存搜索历史
+pub fn save_history(history: &SearchHistory) -> Result<(), String> {
+    save_history_to(&get_history_path()?, history)
+}
+
+fn save_history_to(file_path: &Path, history: &SearchHistory) -> Result<(), String> {
+    let json_content = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("序列化搜索历史失败: {}", e))?;
+
+    write_atomic(file_path, json_content.as_bytes()).map_err(|e| {
+        error!("保存搜索历史失败: {}", e);
+        format!("保存搜索历史失败: {}", e)
+    })?;
+
+    debug!("成功保存搜索历史到: {:?}", file_path);
+    Ok(())
+}
+
+// 按保留天数计算截止时间，0 表示永久保留
+fn retention_cutoff(now: u64) -> Option<u64> {
+    let days = crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.search_history_retention_days)
+        .unwrap_or(0);
+    if days == 0 {
+        None
+    } else {
+        Some(now.saturating_sub(days.saturating_mul(SECS_PER_DAY)))
+    }
+}
+
+// 记录一次搜索，并清理超出保留期的记录；关闭历史记录时不做任何事
+pub fn record_search(keyword: &str) -> Result<(), String> {
+    let prefs = crate::get_config_manager()
+        .get_preferences()
+        .map_err(|e| e.to_string())?;
+    if !prefs.record_history {
+        return Ok(());
+    }
+
+    let now = now_secs();
+    record_search_in(
+        &get_history_path()?,
+        keyword,
+        now,
+        prefs.search_history_limit,
+        retention_cutoff(now),
+    )
+}
+
+fn record_search_in(
+    file_path: &Path,
+    keyword: &str,
+    now: u64,
+    limit: usize,
+    cutoff: Option<u64>,
+) -> Result<(), String> {
+    let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut history = load_history_from(file_path)?;
+    history.record(keyword, now, limit);
+    if let Some(cutoff) = cutoff {
+        history.purge_older_than(cutoff);
+    }
+    save_history_to(file_path, &history)
+}
+
+// 清理超出保留期的搜索记录，返回清理的数量
+pub fn purge_expired() -> Result<usize, String> {
+    let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(cutoff) = retention_cutoff(now_secs()) else {
+        return Ok(0);
+    };
+
+    let mut history = load_history()?;
+    let purged = history.purge_older_than(cutoff);
+    if purged > 0 {
+        save_history(&history)?;
+        info!("已清理 {} 条过期的搜索记录", purged);
+    }
+    Ok(purged)
+}
+
+// 手动清理超出保留期的搜索记录
+#[tauri::command]
+pub fn purge_search_history() -> Result<usize, String> {
+    purge_expired()
+}
+
+// 获取搜索历史，最近的在前
+#[tauri::command]
+pub fn get_search_history() -> Result<Vec<HistoryEntry>, String> {
+    let limit = crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.search_history_limit)
+        .map_err(|e| e.to_string())?;
+    let mut history = load_history()?;
+    history.entries.truncate(limit.max(1));
+    Ok(history.entries)
+}
+
+// 清空搜索历史
+#[tauri::command]
+pub fn clear_search_history(app: tauri::AppHandle) -> Result<(), String> {
+    let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    save_history(&SearchHistory::default())?;
+    crate::sys_tray::rebuild_tray_menu(&app);
+    info!("已清空搜索历史");
+    Ok(())
+}
+
+// 删除一条搜索记录，返回是否找到该记录
+#[tauri::command]
+pub fn remove_history_entry(app: tauri::AppHandle, keyword: String) -> Result<bool, String> {
+    let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut history = load_history()?;
+    if !history.remove(&keyword) {
+        return Ok(false);
+    }
+    save_history(&history)?;
+    crate::sys_tray::rebuild_tray_menu(&app);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(keywords: &[&str]) -> SearchHistory {
+        let mut history = SearchHistory::default();
+        for (i, keyword) in keywords.iter().enumerate() {
+            history.record(keyword, i as u64, 100);
+        }
+        history
+    }
+
+    #[test]
+    fn record_moves_repeated_keyword_to_front() {
+        let mut history = history(&["a", "b", "c"]);
+        history.record(" a ", 10, 100);
+        let keywords: Vec<_> = history.entries.iter().map(|e| e.keyword.as_str()).collect();
+        assert_eq!(keywords, ["a", "c", "b"]);
+
+        history.record("d", 11, 2);
+        assert_eq!(history.entries.len(), 2);
+    }
+
+    fn keywords_of(history: &SearchHistory) -> Vec<&str> {
+        history.entries.iter().map(|e| e.keyword.as_str()).collect()
+    }
+
+    #[test]
+    fn evicts_oldest_only_past_the_limit() {
+        let mut history = SearchHistory::default();
+        for (i, keyword) in ["a", "b", "c"].iter().enumerate() {
+            history.record(keyword, i as u64, 3);
+        }
+        // 恰好达到上限时不淘汰
+        assert_eq!(keywords_of(&history), ["c", "b", "a"]);
+
+        // 重复的关键词不占用新位置，也不淘汰其他记录
+        history.record("a", 3, 3);
+        assert_eq!(keywords_of(&history), ["a", "c", "b"]);
+
+        history.record("d", 4, 3);
+        assert_eq!(keywords_of(&history), ["d", "a", "c"]);
+
+        // 上限为0时仍保留最新的一条
+        history.record("e", 5, 0);
+        assert_eq!(keywords_of(&history), ["e"]);
+    }
+
+    #[test]
+    fn top_keywords_are_most_recent_first() {
+        let history = history(&["猫", "dog", "cat", "哈哈哈"]);
+        assert_eq!(history.top_keywords(2, 1), ["哈哈哈", "cat"]);
+        assert_eq!(history.top_keywords(10, 3), ["哈哈哈", "cat", "dog"]);
+        assert!(history.top_keywords(0, 1).is_empty());
+    }
+
+    #[test]
+    fn record_search_persists_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        assert!(load_history_from(&path).unwrap().entries.is_empty());
+
+        record_search_in(&path, "cat", 100, 2, None).unwrap();
+        record_search_in(&path, "dog", 200, 2, None).unwrap();
+        record_search_in(&path, "cat", 300, 2, None).unwrap();
+        let keywords: Vec<_> = load_history_from(&path)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|e| e.keyword)
+            .collect();
+        assert_eq!(keywords, ["cat", "dog"]);
+
+        // 记录时同时清理超出保留期的记录
+        record_search_in(&path, "fox", 400, 10, Some(250)).unwrap();
+        let history = load_history_from(&path).unwrap();
+        assert_eq!(history.top_keywords(10, 1), ["fox", "cat"]);
+    }
+
+    #[test]
+    fn corrupt_history_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        fs::write(&path, "{not json").unwrap();
+        assert!(load_history_from(&path).unwrap_err().contains("解析搜索历史失败"));
+    }
+
+    #[test]
+    fn purge_removes_old_entries() {
+        let mut history = history(&["a", "b", "c"]);
+        assert_eq!(history.purge_older_than(1), 1);
+        assert!(history.entries.iter().all(|e| e.keyword != "a"));
+    }
+}
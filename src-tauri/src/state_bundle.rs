@@ -0,0 +1,259 @@
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config_manager::{ConfigManager, UserPreferences};
+use crate::favorites::{get_favorites_path, load_favorites_from, save_favorites_to, Favorites};
+use crate::meme_community::{
+    get_enabled_libs_path, load_enabled_libs_from, save_enabled_libs_to, EnabledMemeLibs,
+};
+use crate::utils::misc::app_dirs;
+
+// 当前状态包的格式版本，格式变化时递增
+// 版本 2 起包含收藏
+pub const STATE_BUNDLE_VERSION: u32 = 2;
+
+// 完整应用状态的备份包
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateBundle {
+    pub version: u32,
+    pub exported_at: u64,
+    pub preferences: UserPreferences,
+    pub enabled_libs: EnabledMemeLibs,
+    // 旧版本状态包没有收藏，导入时保留本地收藏不变
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favorites: Option<Favorites>,
+}
+
+// 状态包涉及的配置与文件
+struct StateStore<'a> {
+    config_manager: &'a ConfigManager,
+    enabled_libs_path: PathBuf,
+    favorites_path: PathBuf,
+}
+
+impl StateStore<'static> {
+    // 应用实际使用的配置与文件
+    fn current() -> Result<Self, String> {
+        Ok(Self {
+            config_manager: crate::get_config_manager(),
+            enabled_libs_path: get_enabled_libs_path()?,
+            favorites_path: get_favorites_path()?,
+        })
+    }
+}
+
+impl StateStore<'_> {
+    // 采集当前状态
+    fn collect(&self) -> Result<StateBundle, String> {
+        let preferences = self
+            .config_manager
+            .get_preferences()
+            .map_err(|e| format!("读取偏好设置失败: {}", e))?;
+        let enabled_libs = load_enabled_libs_from(&self.enabled_libs_path)?;
+        let favorites = load_favorites_from(&self.favorites_path)?;
+        let exported_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Ok(StateBundle {
+            version: STATE_BUNDLE_VERSION,
+            exported_at,
+            preferences,
+            enabled_libs,
+            favorites: Some(favorites),
+        })
+    }
+
+    // 写入状态包，任一步失败时回滚到 `previous`
+    fn apply(&self, bundle: StateBundle, previous: StateBundle) -> Result<(), String> {
+        let config_manager = self.config_manager;
+
+        config_manager
+            .update_preferences(bundle.preferences)
+            .map_err(|e| format!("写入偏好设置失败: {}", e))?;
+
+        if let Err(e) = save_enabled_libs_to(&self.enabled_libs_path, &bundle.enabled_libs) {
+            error!("写入启用的表情库失败: {}，回滚偏好设置", e);
+            if let Err(rollback) = config_manager.update_preferences(previous.preferences) {
+                error!("回滚偏好设置失败: {}", rollback);
+            }
+            return Err(e);
+        }
+
+        if let Some(favorites) = &bundle.favorites {
+            if let Err(e) = save_favorites_to(&self.favorites_path, favorites) {
+                error!("写入收藏失败: {}，回滚偏好设置与启用的表情库", e);
+                if let Err(rollback) = config_manager.update_preferences(previous.preferences) {
+                    error!("回滚偏好设置失败: {}", rollback);
+                }
+                if let Err(rollback) =
+                    save_enabled_libs_to(&self.enabled_libs_path, &previous.enabled_libs)
+                {
+                    error!("回滚启用的表情库失败: {}", rollback);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+// 采集当前应用状态
+pub fn collect_state() -> Result<StateBundle, String> {
+    StateStore::current()?.collect()
+}
+
+// 解析并校验状态包
+pub fn parse_state(content: &str) -> Result<StateBundle, String> {
+    // 先只读取版本号，以便对未来版本给出明确提示
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("状态包不是有效的JSON: {}", e))?;
+    let version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or("状态包缺少版本号")?;
+    if version == 0 || version > STATE_BUNDLE_VERSION as u64 {
+        return Err(format!(
+            "不支持的状态包版本: {}（当前支持 {}）",
+            version, STATE_BUNDLE_VERSION
+        ));
+    }
+
+    serde_json::from_value(value).map_err(|e| format!("状态包格式错误: {}", e))
+}
+
+// 应用状态包，任一步失败时回滚到应用前的状态
+pub fn apply_state(bundle: StateBundle, previous: StateBundle) -> Result<(), String> {
+    StateStore::current()?.apply(bundle, previous)?;
+
+    // 使网络相关设置立即生效
+    crate::reload_meme_client()?;
+    Ok(())
+}
+
+fn write_state(bundle: &StateBundle, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(bundle).map_err(|e| format!("序列化状态失败: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("写入文件失败: {:?} - {}", path, e))
+}
+
+// 导出完整应用状态到文件
+#[tauri::command]
+pub fn export_state(path: String) -> Result<(), String> {
+    let bundle = collect_state()?;
+    write_state(&bundle, Path::new(&path))?;
+    info!("应用状态已导出到: {}", path);
+    Ok(())
+}
+
+// 备份当前状态后应用状态包
+fn backup_and_apply(bundle: StateBundle) -> Result<(), String> {
+    let previous = collect_state()?;
+    let backup_path = app_dirs().config_dir().join("state_backup.json");
+    write_state(&previous, &backup_path)?;
+    info!("当前应用状态已备份到: {:?}", backup_path);
+
+    apply_state(bundle, previous)
+}
+
+// 从文件导入完整应用状态，导入前会备份当前状态
+#[tauri::command]
+pub fn import_state(path: String) -> Result<(), String> {
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {} - {}", path, e))?;
+    let bundle = parse_state(&content)?;
+
+    backup_and_apply(bundle)?;
+    info!("已从 {} 导入应用状态", path);
+    Ok(())
+}
+
+// 将偏好设置、启用的表情库与收藏导出为JSON字符串，供前端保存或复制
+#[tauri::command]
+pub fn export_config() -> Result<String, String> {
+    let bundle = collect_state()?;
+    serde_json::to_string_pretty(&bundle).map_err(|e| format!("序列化状态失败: {}", e))
+}
+
+// 从JSON字符串导入配置，格式无效时不会修改任何数据
+#[tauri::command]
+pub fn import_config(json: String) -> Result<(), String> {
+    let bundle = parse_state(&json)?;
+    backup_and_apply(bundle)?;
+    info!("已导入配置");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::favorites::FavoriteEntry;
+    use crate::meme_server::MemeItem;
+
+    fn favorite(id: &str) -> FavoriteEntry {
+        FavoriteEntry {
+            item: MemeItem {
+                id: id.to_string(),
+                url: format!("https://x/{}.png", id),
+                description: None,
+                score: None,
+                source: None,
+                updated_at: None,
+                path: None,
+            },
+            added_at: 0,
+            tags: Vec::new(),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn export_then_import_restores_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_manager = ConfigManager::open(dir.path().join("preferences.json"));
+        let store = StateStore {
+            config_manager: &config_manager,
+            enabled_libs_path: dir.path().join("enabled_meme_libs.json"),
+            favorites_path: dir.path().join("favorites.json"),
+        };
+        config_manager
+            .update_preferences(UserPreferences {
+                result_limit: 7,
+                ..UserPreferences::default()
+            })
+            .unwrap();
+        save_enabled_libs_to(
+            &store.enabled_libs_path,
+            &EnabledMemeLibs {
+                enabled_libs: ["lib-a".to_string()].into(),
+            },
+        )
+        .unwrap();
+        save_favorites_to(&store.favorites_path, &Favorites { entries: vec![favorite("cat")] }).unwrap();
+
+        let exported = serde_json::to_string(&store.collect().unwrap()).unwrap();
+
+        // 导出后修改全部三部分状态
+        config_manager
+            .update_preferences(UserPreferences {
+                result_limit: 30,
+                ..UserPreferences::default()
+            })
+            .unwrap();
+        save_enabled_libs_to(&store.enabled_libs_path, &EnabledMemeLibs::default()).unwrap();
+        save_favorites_to(&store.favorites_path, &Favorites { entries: vec![favorite("dog")] }).unwrap();
+
+        let previous = store.collect().unwrap();
+        store.apply(parse_state(&exported).unwrap(), previous).unwrap();
+
+        assert_eq!(config_manager.get_preferences().unwrap().result_limit, 7);
+        let enabled = load_enabled_libs_from(&store.enabled_libs_path).unwrap();
+        assert_eq!(enabled.enabled_libs, ["lib-a".to_string()].into());
+        let favorites = load_favorites_from(&store.favorites_path).unwrap();
+        assert_eq!(favorites.entries.len(), 1);
+        assert_eq!(favorites.entries[0].item.id, "cat");
+    }
+}
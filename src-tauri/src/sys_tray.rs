@@ -1,32 +1,293 @@
-use log::info;
+use log::{error, info};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 use tauri::{
-    menu::{IsMenuItem, MenuBuilder, MenuItemBuilder},
-    tray::{TrayIcon, TrayIconBuilder},
-    Manager,
+    menu::{Menu, MenuBuilder, SubmenuBuilder},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter,
 };
 
-pub fn create_system_tray(app: &tauri::App) -> Result<TrayIcon, tauri::Error> {
-    // 创建菜单项
-    let exit_item = MenuItemBuilder::new("退出")
-        .id("exit")
-        .build(app.handle())?;
+// 系统托盘图标的ID
+pub const TRAY_ID: &str = "main";
+
+// 最近搜索子菜单中显示的条数
+const TRAY_RECENT_SEARCHES: usize = 8;
+
+// 最近搜索菜单项ID的前缀，后接关键词
+const RECENT_SEARCH_PREFIX: &str = "recent_search:";
+
+// 托盘发起的搜索结果，作为 tray-search-results 事件的负载
+#[derive(Debug, Clone, serde::Serialize)]
+struct TraySearchResults {
+    keyword: String,
+    items: Vec<crate::meme_server::MemeItem>,
+    error: Option<crate::error::MemeError>,
+}
+
+// 左键单击托盘图标时是否切换主窗口
+fn tray_click_toggles() -> bool {
+    crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.tray_click_toggles)
+        .unwrap_or(true)
+}
+
+// 判断托盘事件是否为一次完整的左键单击（按键抬起时触发，避免按下和抬起各切换一次）
+fn is_left_click(event: &TrayIconEvent) -> bool {
+    matches!(
+        event,
+        TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            ..
+        }
+    )
+}
+
+// 运行时切换左键单击托盘图标的行为
+//
+// 左键切换窗口时不再弹出菜单，避免一次单击同时切换窗口和打开菜单。
+// 平台差异：Windows 上两种行为都可用；macOS 上左键默认弹出菜单，关闭后才会切换窗口；
+// Linux 的托盘实现不会发送单击事件，左键总是弹出菜单，该设置不起作用。
+pub fn set_left_click_toggles(app: &AppHandle, enabled: bool) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        if let Err(e) = tray.set_show_menu_on_left_click(!enabled) {
+            error!("设置托盘左键行为失败: {}", e);
+        }
+    }
+}
+
+// 根据搜索历史生成最近搜索菜单项的ID与文本
+fn recent_search_items(keywords: &[String]) -> Vec<(String, String)> {
+    keywords
+        .iter()
+        .take(TRAY_RECENT_SEARCHES)
+        .map(|keyword| (format!("{}{}", RECENT_SEARCH_PREFIX, keyword), keyword.clone()))
+        .collect()
+}
+
+// 根据当前的搜索历史生成最近搜索菜单项
+fn load_recent_search_items() -> Vec<(String, String)> {
+    let keywords: Vec<String> = match crate::search_history::load_history() {
+        Ok(history) => history.entries.into_iter().map(|entry| entry.keyword).collect(),
+        Err(e) => {
+            error!("读取搜索历史失败: {}", e);
+            Vec::new()
+        }
+    };
+    recent_search_items(&keywords)
+}
+
+// 托盘菜单中当前显示的最近搜索，内容未变化时跳过重建
+static SHOWN_RECENT: Mutex<Option<Vec<(String, String)>>> = Mutex::new(None);
+
+// 每次请求重建时递增，延迟结束后只有最后一次请求执行重建
+static REBUILD_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// 连续请求重建托盘菜单时合并为一次的等待时间
+const REBUILD_DEBOUNCE: Duration = Duration::from_secs(1);
+
+// 记录即将显示的最近搜索，返回与当前显示的内容是否不同
+fn replace_if_changed(shown: &mut Option<Vec<(String, String)>>, recent: &[(String, String)]) -> bool {
+    if shown.as_deref() == Some(recent) {
+        return false;
+    }
+    *shown = Some(recent.to_vec());
+    true
+}
+
+// 托盘菜单中的一项
+#[derive(Debug, PartialEq)]
+enum TrayEntry<'a> {
+    Item { id: &'static str, text: &'static str },
+    // 最近搜索子菜单，没有搜索历史时整个子菜单被禁用
+    Recent(&'a [(String, String)]),
+    Separator,
+}
+
+// 托盘菜单的结构，最近搜索子菜单的内容来自 `recent`
+fn tray_menu_entries(recent: &[(String, String)]) -> Vec<TrayEntry<'_>> {
+    vec![
+        TrayEntry::Item { id: "open_search", text: "打开搜索" },
+        TrayEntry::Recent(recent),
+        TrayEntry::Item { id: "settings", text: "设置" },
+        TrayEntry::Item { id: "toggle", text: "显示/隐藏" },
+        TrayEntry::Separator,
+        TrayEntry::Item { id: "exit", text: "退出" },
+    ]
+}
 
-    // 将菜单项包装为对 dyn IsMenuItem 的引用
-    let items: [&dyn IsMenuItem<_>; 1] = [&exit_item];
+// 按 `tray_menu_entries` 构建托盘菜单
+fn build_tray_menu(app: &AppHandle, recent: &[(String, String)]) -> Result<Menu<tauri::Wry>, tauri::Error> {
+    let mut builder = MenuBuilder::new(app);
+    for entry in tray_menu_entries(recent) {
+        builder = match entry {
+            TrayEntry::Item { id, text } => builder.text(id, text),
+            TrayEntry::Recent(recent) => {
+                let recent_menu = recent
+                    .iter()
+                    .fold(SubmenuBuilder::new(app, "最近搜索"), |builder, (id, text)| {
+                        builder.text(id.as_str(), text)
+                    })
+                    .enabled(!recent.is_empty())
+                    .build()?;
+                builder.item(&recent_menu)
+            }
+            TrayEntry::Separator => builder.separator(),
+        };
+    }
+    builder.build()
+}
+
+// 重新构建托盘菜单，使最近搜索子菜单与搜索历史保持一致
+//
+// 在后台等待 REBUILD_DEBOUNCE，期间的多次请求只重建一次；最近搜索没有变化时不重建。
+pub fn rebuild_tray_menu(app: &AppHandle) {
+    if app.tray_by_id(TRAY_ID).is_none() {
+        return;
+    }
+    let generation = REBUILD_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(REBUILD_DEBOUNCE).await;
+        if REBUILD_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        let recent = match tokio::task::spawn_blocking(load_recent_search_items).await {
+            Ok(recent) => recent,
+            Err(e) => {
+                error!("读取最近搜索的任务异常退出: {}", e);
+                return;
+            }
+        };
+        if !replace_if_changed(&mut SHOWN_RECENT.lock().unwrap_or_else(|e| e.into_inner()), &recent) {
+            return;
+        }
+        let Some(tray) = app.tray_by_id(TRAY_ID) else {
+            return;
+        };
+        match build_tray_menu(&app, &recent) {
+            Ok(menu) => {
+                if let Err(e) = tray.set_menu(Some(menu)) {
+                    error!("更新托盘菜单失败: {}", e);
+                }
+            }
+            Err(e) => error!("构建托盘菜单失败: {}", e),
+        }
+    });
+}
+
+// 重新执行一次最近搜索，显示主窗口并把结果发送给前端
+fn run_recent_search(app: &AppHandle, keyword: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        info!("通过系统托盘重新搜索: {}", keyword);
+        let (items, error) =
+            match crate::search_memes(app.clone(), keyword.clone(), None, None, None, None).await {
+                Ok(items) => (items, None),
+                Err(e) => (Vec::new(), Some(e)),
+            };
+
+        crate::show_main_window(&app);
+        let payload = TraySearchResults { keyword, items, error };
+        if let Err(e) = app.emit("tray-search-results", &payload) {
+            error!("发送托盘搜索结果失败: {}", e);
+        }
+    });
+}
 
-    // 使用 MenuBuilder 创建菜单并添加菜单项
-    let menu = MenuBuilder::new(app.handle()).items(&items).build()?;
+pub fn create_system_tray(app: &tauri::App) -> Result<TrayIcon, tauri::Error> {
+    let recent = load_recent_search_items();
+    let menu = build_tray_menu(app.handle(), &recent)?;
+    *SHOWN_RECENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(recent);
 
     // 构建系统托盘
-    TrayIconBuilder::new()
+    let click_toggles = tray_click_toggles();
+    TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
+        .show_menu_on_left_click(!click_toggles)
         .tooltip("MemeMeow 表情包助手")
         .icon(app.default_window_icon().unwrap().clone())
-        .on_menu_event(|app, event| {
-            if event.id() == "exit" {
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "open_search" => {
+                crate::show_main_window(app);
+                if let Err(e) = app.emit("focus-search", ()) {
+                    error!("发送聚焦搜索事件失败: {}", e);
+                }
+            }
+            "settings" => {
+                crate::show_main_window(app);
+                if let Err(e) = app.emit("open-settings", ()) {
+                    error!("发送打开设置事件失败: {}", e);
+                }
+            }
+            "toggle" => crate::toggle_main_window(app),
+            "exit" => {
                 info!("用户通过系统托盘菜单退出程序");
                 app.exit(0);
             }
+            id => {
+                if let Some(keyword) = id.strip_prefix(RECENT_SEARCH_PREFIX) {
+                    run_recent_search(app, keyword.to_string());
+                }
+            }
+        })
+        .on_tray_icon_event(|tray, event| {
+            if is_left_click(&event) && tray_click_toggles() {
+                crate::toggle_main_window(tray.app_handle());
+            }
         })
         .build(app)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keywords(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn recent_items_are_capped() {
+        let words: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let items = recent_search_items(&words);
+        assert_eq!(items.len(), TRAY_RECENT_SEARCHES);
+        assert_eq!(items[0], (format!("{}0", RECENT_SEARCH_PREFIX), "0".to_string()));
+    }
+
+    #[test]
+    fn rebuild_only_when_recent_changes() {
+        let mut shown = None;
+        let first = recent_search_items(&keywords(&["cat", "dog"]));
+        assert!(replace_if_changed(&mut shown, &first));
+        assert!(!replace_if_changed(&mut shown, &first));
+
+        // 顺序变化也需要重建
+        let reordered = recent_search_items(&keywords(&["dog", "cat"]));
+        assert!(replace_if_changed(&mut shown, &reordered));
+        assert!(replace_if_changed(&mut shown, &[]));
+        assert!(!replace_if_changed(&mut shown, &[]));
+    }
+
+    #[test]
+    fn tray_menu_has_expected_items() {
+        let recent = recent_search_items(&keywords(&["cat"]));
+        let entries = tray_menu_entries(&recent);
+
+        let ids: Vec<&str> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                TrayEntry::Item { id, .. } => Some(*id),
+                _ => None,
+            })
+            .collect();
+        // on_menu_event 按这些ID分发
+        assert_eq!(ids, ["open_search", "settings", "toggle", "exit"]);
+        assert!(entries.contains(&TrayEntry::Recent(&recent)));
+        assert_eq!(recent[0].0, format!("{}cat", RECENT_SEARCH_PREFIX));
+        assert_eq!(entries.last(), Some(&TrayEntry::Item { id: "exit", text: "退出" }));
+    }
+}
@@ -0,0 +1,131 @@
+use log::{debug, info};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Notify;
+
+/// 可被集中取消的操作类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    /// 表情包搜索
+    Search,
+    /// 用户主动发起的复制，不会被批量取消
+    Copy,
+}
+
+/// 单个操作的取消令牌
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待令牌被取消
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// 进行中操作的集中登记表
+#[derive(Default)]
+pub struct CancelRegistry {
+    next_id: AtomicU64,
+    operations: Mutex<HashMap<u64, (OperationKind, CancelToken)>>,
+}
+
+/// 操作登记凭据，离开作用域时自动从登记表中移除
+pub struct OperationGuard {
+    id: u64,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        registry()
+            .operations
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.id);
+    }
+}
+
+static REGISTRY: OnceLock<CancelRegistry> = OnceLock::new();
+
+fn registry() -> &'static CancelRegistry {
+    REGISTRY.get_or_init(CancelRegistry::default)
+}
+
+/// 登记一个新操作，返回其凭据与取消令牌
+pub fn register(kind: OperationKind) -> (OperationGuard, CancelToken) {
+    let registry = registry();
+    let id = registry.next_id.fetch_add(1, Ordering::SeqCst);
+    let token = CancelToken::default();
+    registry
+        .operations
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, (kind, token.clone()));
+    (OperationGuard { id }, token)
+}
+
+/// 取消所有满足条件的进行中操作，返回被取消的数量
+fn cancel_where<P>(predicate: P) -> usize
+where
+    P: Fn(OperationKind) -> bool,
+{
+    let operations = registry()
+        .operations
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    let mut count = 0;
+    for (kind, token) in operations.values() {
+        if predicate(*kind) && !token.is_cancelled() {
+            token.cancel();
+            count += 1;
+        }
+    }
+
+    if count > 0 {
+        info!("已取消 {} 个进行中的操作", count);
+    }
+    count
+}
+
+/// 取消所有可批量取消的操作（不包括用户主动发起的复制）
+pub fn cancel_background() -> usize {
+    cancel_where(|kind| kind != OperationKind::Copy)
+}
+
+/// 以可取消的方式执行一个异步操作
+///
+/// 操作在执行期间登记在集中登记表中；被取消时返回 `None`。
+pub async fn run_cancellable<F, T>(kind: OperationKind, future: F) -> Option<T>
+where
+    F: Future<Output = T>,
+{
+    let (_guard, token) = register(kind);
+    tokio::select! {
+        output = future => Some(output),
+        _ = token.cancelled() => {
+            debug!("{:?} 操作已被取消", kind);
+            None
+        }
+    }
+}
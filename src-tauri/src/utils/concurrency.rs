@@ -0,0 +1,172 @@
+use log::info;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 允许的并发请求数范围
+pub const MIN_CONCURRENCY: usize = 1;
+pub const MAX_CONCURRENCY: usize = 32;
+
+/// 默认并发请求数
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+struct LimiterState {
+    limit: usize,
+    /// 调低上限时仍被占用、尚未收回的许可数
+    pending_forget: usize,
+}
+
+/// 可在运行时调整上限的并发限制器，始终使用同一个信号量
+struct Limiter {
+    semaphore: Arc<Semaphore>,
+    state: Mutex<LimiterState>,
+}
+
+impl Limiter {
+    fn new(limit: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit)),
+            state: Mutex::new(LimiterState {
+                limit,
+                pending_forget: 0,
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn limit(&self) -> usize {
+        self.state().limit
+    }
+
+    /// 调高上限时增加许可；调低时立即收回空闲的许可，
+    /// 被占用的许可在归还后由 [`Limiter::acquire`] 收回，因此占用数很快降到新上限以内
+    fn set_limit(&self, limit: usize) {
+        let mut state = self.state();
+        if limit > state.limit {
+            let grow = limit - state.limit;
+            // 先抵消尚未收回的许可
+            let offset = grow.min(state.pending_forget);
+            state.pending_forget -= offset;
+            self.semaphore.add_permits(grow - offset);
+        } else {
+            let shrink = state.limit - limit;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            state.pending_forget += shrink - forgotten;
+        }
+        state.limit = limit;
+    }
+
+    async fn acquire(&self) -> OwnedSemaphorePermit {
+        loop {
+            let permit = self
+                .semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("并发信号量不会被关闭");
+            let mut state = self.state();
+            if state.pending_forget == 0 {
+                return permit;
+            }
+            state.pending_forget -= 1;
+            permit.forget();
+        }
+    }
+}
+
+static LIMITER: OnceLock<Limiter> = OnceLock::new();
+
+fn limiter() -> &'static Limiter {
+    LIMITER.get_or_init(|| {
+        let limit = crate::get_config_manager()
+            .get_preferences()
+            .map(|prefs| prefs.concurrency_limit)
+            .unwrap_or(DEFAULT_CONCURRENCY)
+            .clamp(MIN_CONCURRENCY, MAX_CONCURRENCY);
+        Limiter::new(limit)
+    })
+}
+
+/// 当前的并发上限
+pub fn limit() -> usize {
+    limiter().limit()
+}
+
+/// 调整并发上限
+///
+/// 已持有许可的操作照常完成，新上限同时约束正在进行与之后的操作。
+pub fn set_limit(limit: usize) -> Result<(), String> {
+    if !(MIN_CONCURRENCY..=MAX_CONCURRENCY).contains(&limit) {
+        return Err(format!(
+            "并发数必须在{}到{}之间",
+            MIN_CONCURRENCY, MAX_CONCURRENCY
+        ));
+    }
+
+    limiter().set_limit(limit);
+    info!("并发请求上限已调整为 {}", limit);
+    Ok(())
+}
+
+/// 获取一个并发许可，许可在离开作用域时释放
+pub async fn acquire() -> OwnedSemaphorePermit {
+    limiter().acquire().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn acquire_soon(limiter: &Limiter) -> Option<OwnedSemaphorePermit> {
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .ok()
+    }
+
+    #[tokio::test]
+    async fn raising_limit_adds_permits() {
+        let limiter = Limiter::new(1);
+        let _first = limiter.acquire().await;
+        assert!(acquire_soon(&limiter).await.is_none());
+
+        limiter.set_limit(3);
+        let _second = acquire_soon(&limiter).await.unwrap();
+        let _third = acquire_soon(&limiter).await.unwrap();
+        assert!(acquire_soon(&limiter).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn lowering_limit_counts_held_permits() {
+        let limiter = Limiter::new(3);
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+
+        limiter.set_limit(1);
+        assert_eq!(limiter.limit(), 1);
+        // 仍有两个许可被占用，超出新上限
+        assert!(acquire_soon(&limiter).await.is_none());
+        drop(first);
+        assert!(acquire_soon(&limiter).await.is_none());
+        drop(second);
+        let _only = acquire_soon(&limiter).await.unwrap();
+        assert!(acquire_soon(&limiter).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn raising_after_lowering_cancels_pending() {
+        let limiter = Limiter::new(2);
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+
+        limiter.set_limit(1);
+        limiter.set_limit(2);
+        drop(first);
+        drop(second);
+        let _a = acquire_soon(&limiter).await.unwrap();
+        let _b = acquire_soon(&limiter).await.unwrap();
+        assert!(acquire_soon(&limiter).await.is_none());
+    }
+}
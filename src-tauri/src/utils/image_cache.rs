@@ -0,0 +1,198 @@
+use log::{debug, error, info};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::utils::misc::{app_dirs, write_atomic};
+use crate::utils::network::{download_image, DownloadedImage};
+
+/// 图片缓存的总大小上限（字节），超过后按最近使用时间淘汰
+pub const IMAGE_CACHE_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// 串行化缓存目录的写入与淘汰
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+fn cache_dir() -> PathBuf {
+    app_dirs().cache_dir().join("images")
+}
+
+/// 以URL的SHA-256作为缓存文件名
+fn cache_path(dir: &Path, url: &str) -> PathBuf {
+    let hash: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    dir.join(hash)
+}
+
+/// 读取缓存的图片，命中时更新修改时间作为最近使用时间
+///
+/// 文件读写在阻塞线程池中执行，不占用异步运行时的工作线程。
+pub async fn get(url: &str) -> Option<Vec<u8>> {
+    let url = url.to_string();
+    tokio::task::spawn_blocking(move || get_in(&cache_dir(), &url))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// 写入图片缓存，并在超过上限时淘汰最久未使用的图片
+///
+/// 与 [`get`] 相同，在阻塞线程池中执行。
+pub async fn put(url: &str, bytes: Vec<u8>) {
+    let url = url.to_string();
+    if let Err(e) = tokio::task::spawn_blocking(move || {
+        put_in(&cache_dir(), &url, &bytes, IMAGE_CACHE_MAX_BYTES)
+    })
+    .await
+    {
+        error!("写入图片缓存的任务异常退出: {}", e);
+    }
+}
+
+fn get_in(dir: &Path, url: &str) -> Option<Vec<u8>> {
+    let path = cache_path(dir, url);
+    let bytes = fs::read(&path).ok()?;
+    if let Err(e) = File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        debug!("更新图片缓存使用时间失败: {:?} - {}", path, e);
+    }
+    Some(bytes)
+}
+
+fn put_in(dir: &Path, url: &str, bytes: &[u8], max_bytes: u64) {
+    let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = fs::create_dir_all(dir) {
+        error!("创建图片缓存目录失败: {}", e);
+        return;
+    }
+    if let Err(e) = write_atomic(&cache_path(dir, url), bytes) {
+        error!("写入图片缓存失败: {}", e);
+        return;
+    }
+    evict_in(dir, max_bytes);
+}
+
+/// 按最近使用时间从旧到新删除 `dir` 中的缓存，直到总大小不超过上限
+fn evict_in(dir: &Path, max_bytes: u64) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((entry.path(), metadata.len(), modified))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                total -= size;
+                debug!("淘汰图片缓存: {:?}", path);
+            }
+            Err(e) => error!("删除图片缓存失败: {:?} - {}", path, e),
+        }
+    }
+}
+
+/// 清空图片缓存，返回删除的文件数
+pub fn clear() -> Result<usize, String> {
+    let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let entries = match fs::read_dir(cache_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            fs::remove_file(&path).map_err(|e| format!("删除图片缓存失败: {:?} - {}", path, e))?;
+            removed += 1;
+        }
+    }
+    info!("已清空图片缓存，共 {} 个文件", removed);
+    Ok(removed)
+}
+
+/// 获取图片，优先使用缓存，未命中时下载并写入缓存
+///
+/// 命中缓存时没有 `Content-Type`，调用方需根据文件头判断格式。
+pub async fn download_image_cached(url: &str) -> Result<DownloadedImage, String> {
+    if let Some(bytes) = get(url).await {
+        debug!("图片缓存命中: {}", url);
+        return Ok(DownloadedImage {
+            bytes,
+            content_type: None,
+        });
+    }
+
+    let image = download_image(url).await?;
+    put(url, image.bytes.clone()).await;
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn set_age(path: &Path, secs_ago: u64) {
+        let modified = SystemTime::now() - Duration::from_secs(secs_ago);
+        File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified))
+            .unwrap();
+    }
+
+    #[test]
+    fn put_then_get_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        put_in(dir.path(), "https://x/a.png", b"abc", 1024);
+        assert_eq!(get_in(dir.path(), "https://x/a.png").unwrap(), b"abc");
+        assert!(get_in(dir.path(), "https://x/b.png").is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_until_under_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        for (url, age) in [("old", 30), ("middle", 20), ("new", 10)] {
+            let path = cache_path(dir.path(), url);
+            fs::write(&path, [0u8; 10]).unwrap();
+            set_age(&path, age);
+        }
+        // 读取会刷新使用时间，最旧的文件变为最近使用
+        assert!(get_in(dir.path(), "old").is_some());
+
+        evict_in(dir.path(), 20);
+        assert!(cache_path(dir.path(), "old").exists());
+        assert!(!cache_path(dir.path(), "middle").exists());
+        assert!(cache_path(dir.path(), "new").exists());
+
+        evict_in(dir.path(), 20);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
@@ -1,5 +1,6 @@
 use tauri_plugin_global_shortcut::{Code, Modifiers};
 use serde::{de, Deserialize, Serialize};
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ShortcutConfig {
@@ -9,75 +10,482 @@ pub struct ShortcutConfig {
 }
 
 impl ShortcutConfig {
-    // 将配置转换为Tauri快捷键代码
-    pub fn to_tauri_shortcut(&self) -> (Modifiers, Code) {
+    // 将配置转换为Tauri快捷键代码，按键无法识别时返回错误
+    pub fn to_tauri_shortcut(&self) -> Result<(Modifiers, Code), String> {
         let mut modifiers = Modifiers::empty();
         for modifier in &self.modifiers {
-            match modifier.to_lowercase().as_str() {
-                "ctrl" => modifiers.insert(Modifiers::CONTROL),
-                "alt" => modifiers.insert(Modifiers::ALT),
-                "shift" => modifiers.insert(Modifiers::SHIFT),
-                "meta" | "super" | "command" => modifiers.insert(Modifiers::META),
-                _ => continue,
+            match parse_modifier(modifier) {
+                Some(m) => modifiers.insert(m),
+                None => continue,
             }
         }
 
         // 将字符串键转换为Tauri Code
-        let code = match self.key.to_lowercase().as_str() {
-            "a" => Code::KeyA,
-            "b" => Code::KeyB,
-            "c" => Code::KeyC,
-            "d" => Code::KeyD,
-            "e" => Code::KeyE,
-            "f" => Code::KeyF,
-            "g" => Code::KeyG,
-            "h" => Code::KeyH,
-            "i" => Code::KeyI,
-            "j" => Code::KeyJ,
-            "k" => Code::KeyK,
-            "l" => Code::KeyL,
-            "m" => Code::KeyM,
-            "n" => Code::KeyN,
-            "o" => Code::KeyO,
-            "p" => Code::KeyP,
-            "q" => Code::KeyQ,
-            "r" => Code::KeyR,
-            "s" => Code::KeyS,
-            "t" => Code::KeyT,
-            "u" => Code::KeyU,
-            "v" => Code::KeyV,
-            "w" => Code::KeyW,
-            "x" => Code::KeyX,
-            "y" => Code::KeyY,
-            "z" => Code::KeyZ,
-            // 数字键
-            "0" => Code::Digit0,
-            "1" => Code::Digit1,
-            "2" => Code::Digit2,
-            "3" => Code::Digit3,
-            "4" => Code::Digit4,
-            "5" => Code::Digit5,
-            "6" => Code::Digit6,
-            "7" => Code::Digit7,
-            "8" => Code::Digit8,
-            "9" => Code::Digit9,
-            // 功能键
-            "f1" => Code::F1,
-            "f2" => Code::F2,
-            "f3" => Code::F3,
-            "f4" => Code::F4,
-            "f5" => Code::F5,
-            "f6" => Code::F6,
-            "f7" => Code::F7,
-            "f8" => Code::F8,
-            "f9" => Code::F9,
-            "f10" => Code::F10,
-            "f11" => Code::F11,
-            "f12" => Code::F12,
-            // 默认键
-            _ => Code::KeyV, // 默认使用V键
-        };
-
-        (modifiers, code)
+        let code = key_to_code(&self.key).ok_or_else(|| format!("未知的按键: {}", self.key))?;
+
+        Ok((modifiers, code))
+    }
+
+    // 检查配置能否注册为全局快捷键：按键可识别，且至少有一个可识别的修饰键
+    pub fn validate(&self) -> Result<(), String> {
+        if self.key.trim().is_empty() {
+            return Err("快捷键缺少按键".to_string());
+        }
+        if key_to_code(&self.key).is_none() {
+            return Err(format!("未知的按键: {}", self.key));
+        }
+        if self.modifiers.is_empty() {
+            return Err("快捷键至少需要一个修饰键（Ctrl、Alt、Shift 或 Meta）".to_string());
+        }
+        if let Some(unknown) = self.modifiers.iter().find(|m| parse_modifier(m).is_none()) {
+            return Err(format!("未知的修饰键: {}", unknown));
+        }
+        Ok(())
+    }
+
+    // 解析快捷键并记录解析细节，用于排查快捷键不生效的问题
+    pub fn resolve(&self) -> ResolvedShortcut {
+        let code = key_to_code(&self.key);
+
+        let mut modifiers = Modifiers::empty();
+        let mut modifier_names = Vec::new();
+        let mut ignored_modifiers = Vec::new();
+        for modifier in &self.modifiers {
+            match parse_modifier(modifier) {
+                Some(m) => {
+                    modifiers.insert(m);
+                    let name = canonical_modifier(m).to_string();
+                    if !modifier_names.contains(&name) {
+                        modifier_names.push(name);
+                    }
+                }
+                None => ignored_modifiers.push(modifier.clone()),
+            }
+        }
+
+        ResolvedShortcut {
+            modifiers: modifier_names,
+            modifier_bits: modifiers.bits(),
+            code: code.map(|code| format!("{:?}", code)).unwrap_or_default(),
+            unknown_key: code.is_none(),
+            normalized: code.and_then(|code| {
+                let mut config = ShortcutConfig::from_tauri_shortcut(modifiers, code)?;
+                config.action = self.action.clone();
+                Some(config)
+            }),
+            ignored_modifiers,
+        }
+    }
+
+    // 从已注册的快捷键还原配置，按键不在支持范围内时返回None
+    pub fn from_tauri_shortcut(modifiers: Modifiers, code: Code) -> Option<Self> {
+        Some(ShortcutConfig {
+            modifiers: modifiers_to_strings(modifiers),
+            key: code_to_key(&code)?.to_string(),
+            action: String::new(),
+        })
+    }
+
+    // 动作名称，未配置时使用给定的配置项名称
+    pub fn action_name<'a>(&'a self, fallback: &'a str) -> &'a str {
+        if self.action.is_empty() {
+            fallback
+        } else {
+            &self.action
+        }
+    }
+}
+
+// 快捷键配置实际解析得到的结果
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedShortcut {
+    // 识别出的修饰键（规范名称）
+    pub modifiers: Vec<String>,
+    // 修饰键的位标志
+    pub modifier_bits: u32,
+    // 解析得到的Tauri Code，例如 KeyN；按键无法识别时为空
+    pub code: String,
+    // 按键无法识别，该快捷键不会被注册
+    pub unknown_key: bool,
+    // 由解析结果还原出的规范配置，保存时应使用该写法
+    pub normalized: Option<ShortcutConfig>,
+    // 无法识别而被忽略的修饰键
+    pub ignored_modifiers: Vec<String>,
+}
+
+// 将修饰键名称转换为Tauri修饰键
+pub fn parse_modifier(modifier: &str) -> Option<Modifiers> {
+    match modifier.trim().to_lowercase().as_str() {
+        "ctrl" | "control" => Some(Modifiers::CONTROL),
+        "alt" | "option" => Some(Modifiers::ALT),
+        "shift" => Some(Modifiers::SHIFT),
+        "meta" | "super" | "command" | "cmd" => Some(Modifiers::META),
+        _ => None,
+    }
+}
+
+// 修饰键的规范名称，与配置文件中的写法一致
+fn canonical_modifier(modifier: Modifiers) -> &'static str {
+    if modifier == Modifiers::CONTROL {
+        "ctrl"
+    } else if modifier == Modifiers::ALT {
+        "alt"
+    } else if modifier == Modifiers::SHIFT {
+        "shift"
+    } else {
+        "meta"
+    }
+}
+
+// 将修饰键位标志转换为规范名称列表，顺序固定为 ctrl、alt、shift、meta
+pub fn modifiers_to_strings(modifiers: Modifiers) -> Vec<String> {
+    [Modifiers::CONTROL, Modifiers::ALT, Modifiers::SHIFT, Modifiers::META]
+        .into_iter()
+        .filter(|m| modifiers.contains(*m))
+        .map(|m| canonical_modifier(m).to_string())
+        .collect()
+}
+
+// 将Tauri Code转换为显示给用户的按键名称，例如 "1"、"↑"、"Space"
+pub fn code_to_display_string(code: &Code) -> String {
+    let label = match code {
+        Code::ArrowUp => "↑",
+        Code::ArrowDown => "↓",
+        Code::ArrowLeft => "←",
+        Code::ArrowRight => "→",
+        Code::Space => "Space",
+        Code::Enter => "Enter",
+        Code::Tab => "Tab",
+        Code::Escape => "Esc",
+        Code::Backspace => "Backspace",
+        Code::Insert => "Insert",
+        Code::Delete => "Delete",
+        Code::Home => "Home",
+        Code::End => "End",
+        Code::PageUp => "PageUp",
+        Code::PageDown => "PageDown",
+        Code::Numpad0 => "Num0",
+        Code::Numpad1 => "Num1",
+        Code::Numpad2 => "Num2",
+        Code::Numpad3 => "Num3",
+        Code::Numpad4 => "Num4",
+        Code::Numpad5 => "Num5",
+        Code::Numpad6 => "Num6",
+        Code::Numpad7 => "Num7",
+        Code::Numpad8 => "Num8",
+        Code::Numpad9 => "Num9",
+        // 字母、数字、功能键与标点直接使用配置中的写法
+        _ => {
+            return match code_to_key(code) {
+                Some(key) => key.to_uppercase(),
+                None => format!("{:?}", code),
+            }
+        }
+    };
+    label.to_string()
+}
+
+// 将Tauri Code转换为配置文件中的按键写法，是 key_to_code 的逆映射
+pub fn code_to_key(code: &Code) -> Option<&'static str> {
+    let key = match code {
+        Code::KeyA => "a",
+        Code::KeyB => "b",
+        Code::KeyC => "c",
+        Code::KeyD => "d",
+        Code::KeyE => "e",
+        Code::KeyF => "f",
+        Code::KeyG => "g",
+        Code::KeyH => "h",
+        Code::KeyI => "i",
+        Code::KeyJ => "j",
+        Code::KeyK => "k",
+        Code::KeyL => "l",
+        Code::KeyM => "m",
+        Code::KeyN => "n",
+        Code::KeyO => "o",
+        Code::KeyP => "p",
+        Code::KeyQ => "q",
+        Code::KeyR => "r",
+        Code::KeyS => "s",
+        Code::KeyT => "t",
+        Code::KeyU => "u",
+        Code::KeyV => "v",
+        Code::KeyW => "w",
+        Code::KeyX => "x",
+        Code::KeyY => "y",
+        Code::KeyZ => "z",
+        Code::Digit0 => "0",
+        Code::Digit1 => "1",
+        Code::Digit2 => "2",
+        Code::Digit3 => "3",
+        Code::Digit4 => "4",
+        Code::Digit5 => "5",
+        Code::Digit6 => "6",
+        Code::Digit7 => "7",
+        Code::Digit8 => "8",
+        Code::Digit9 => "9",
+        Code::F1 => "f1",
+        Code::F2 => "f2",
+        Code::F3 => "f3",
+        Code::F4 => "f4",
+        Code::F5 => "f5",
+        Code::F6 => "f6",
+        Code::F7 => "f7",
+        Code::F8 => "f8",
+        Code::F9 => "f9",
+        Code::F10 => "f10",
+        Code::F11 => "f11",
+        Code::F12 => "f12",
+        Code::Space => "space",
+        Code::Enter => "enter",
+        Code::Tab => "tab",
+        Code::Escape => "escape",
+        Code::Backspace => "backspace",
+        Code::Insert => "insert",
+        Code::Delete => "delete",
+        Code::ArrowUp => "arrowup",
+        Code::ArrowDown => "arrowdown",
+        Code::ArrowLeft => "arrowleft",
+        Code::ArrowRight => "arrowright",
+        Code::Home => "home",
+        Code::End => "end",
+        Code::PageUp => "pageup",
+        Code::PageDown => "pagedown",
+        Code::Minus => "-",
+        Code::Equal => "=",
+        Code::Comma => ",",
+        Code::Period => ".",
+        Code::Slash => "/",
+        Code::Backslash => "\\",
+        Code::Semicolon => ";",
+        Code::Quote => "'",
+        Code::Backquote => "`",
+        Code::BracketLeft => "[",
+        Code::BracketRight => "]",
+        Code::Numpad0 => "numpad0",
+        Code::Numpad1 => "numpad1",
+        Code::Numpad2 => "numpad2",
+        Code::Numpad3 => "numpad3",
+        Code::Numpad4 => "numpad4",
+        Code::Numpad5 => "numpad5",
+        Code::Numpad6 => "numpad6",
+        Code::Numpad7 => "numpad7",
+        Code::Numpad8 => "numpad8",
+        Code::Numpad9 => "numpad9",
+        _ => return None,
+    };
+    Some(key)
+}
+
+// 将字符串键转换为Tauri Code，无法识别时返回None
+pub fn key_to_code(key: &str) -> Option<Code> {
+    let code = match key.trim().to_lowercase().as_str() {
+        "a" => Code::KeyA,
+        "b" => Code::KeyB,
+        "c" => Code::KeyC,
+        "d" => Code::KeyD,
+        "e" => Code::KeyE,
+        "f" => Code::KeyF,
+        "g" => Code::KeyG,
+        "h" => Code::KeyH,
+        "i" => Code::KeyI,
+        "j" => Code::KeyJ,
+        "k" => Code::KeyK,
+        "l" => Code::KeyL,
+        "m" => Code::KeyM,
+        "n" => Code::KeyN,
+        "o" => Code::KeyO,
+        "p" => Code::KeyP,
+        "q" => Code::KeyQ,
+        "r" => Code::KeyR,
+        "s" => Code::KeyS,
+        "t" => Code::KeyT,
+        "u" => Code::KeyU,
+        "v" => Code::KeyV,
+        "w" => Code::KeyW,
+        "x" => Code::KeyX,
+        "y" => Code::KeyY,
+        "z" => Code::KeyZ,
+        // 数字键
+        "0" => Code::Digit0,
+        "1" => Code::Digit1,
+        "2" => Code::Digit2,
+        "3" => Code::Digit3,
+        "4" => Code::Digit4,
+        "5" => Code::Digit5,
+        "6" => Code::Digit6,
+        "7" => Code::Digit7,
+        "8" => Code::Digit8,
+        "9" => Code::Digit9,
+        // 功能键
+        "f1" => Code::F1,
+        "f2" => Code::F2,
+        "f3" => Code::F3,
+        "f4" => Code::F4,
+        "f5" => Code::F5,
+        "f6" => Code::F6,
+        "f7" => Code::F7,
+        "f8" => Code::F8,
+        "f9" => Code::F9,
+        "f10" => Code::F10,
+        "f11" => Code::F11,
+        "f12" => Code::F12,
+        // 编辑与控制键
+        "space" => Code::Space,
+        "enter" | "return" => Code::Enter,
+        "tab" => Code::Tab,
+        "escape" | "esc" => Code::Escape,
+        "backspace" => Code::Backspace,
+        "insert" | "ins" => Code::Insert,
+        "delete" | "del" => Code::Delete,
+        // 方向与导航键
+        "arrowup" | "up" => Code::ArrowUp,
+        "arrowdown" | "down" => Code::ArrowDown,
+        "arrowleft" | "left" => Code::ArrowLeft,
+        "arrowright" | "right" => Code::ArrowRight,
+        "home" => Code::Home,
+        "end" => Code::End,
+        "pageup" | "pgup" => Code::PageUp,
+        "pagedown" | "pgdn" => Code::PageDown,
+        // 标点符号键
+        "-" | "minus" => Code::Minus,
+        "=" | "equal" => Code::Equal,
+        "," | "comma" => Code::Comma,
+        "." | "period" => Code::Period,
+        "/" | "slash" => Code::Slash,
+        "\\" | "backslash" => Code::Backslash,
+        ";" | "semicolon" => Code::Semicolon,
+        "'" | "quote" => Code::Quote,
+        "`" | "backquote" => Code::Backquote,
+        "[" | "bracketleft" => Code::BracketLeft,
+        "]" | "bracketright" => Code::BracketRight,
+        // 小键盘数字键
+        "numpad0" | "num0" => Code::Numpad0,
+        "numpad1" | "num1" => Code::Numpad1,
+        "numpad2" | "num2" => Code::Numpad2,
+        "numpad3" | "num3" => Code::Numpad3,
+        "numpad4" | "num4" => Code::Numpad4,
+        "numpad5" | "num5" => Code::Numpad5,
+        "numpad6" | "num6" => Code::Numpad6,
+        "numpad7" | "num7" => Code::Numpad7,
+        "numpad8" | "num8" => Code::Numpad8,
+        "numpad9" | "num9" => Code::Numpad9,
+        _ => return None,
+    };
+    Some(code)
+}
+
+impl FromStr for ShortcutConfig {
+    type Err = String;
+
+    // 解析 "Ctrl+Alt+N" 形式的快捷键字符串
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err("快捷键为空".to_string());
+        }
+
+        let parts: Vec<&str> = input.split('+').map(|part| part.trim()).collect();
+        let (key, modifier_parts) = parts.split_last().ok_or("快捷键为空")?;
+
+        if key.is_empty() {
+            return Err("快捷键缺少按键".to_string());
+        }
+        if key_to_code(key).is_none() {
+            return Err(format!("未知的按键: {}", key));
+        }
+
+        let mut modifiers: Vec<String> = Vec::new();
+        for part in modifier_parts {
+            if part.is_empty() {
+                return Err("快捷键中存在空的修饰键".to_string());
+            }
+            let modifier = parse_modifier(part).ok_or_else(|| format!("未知的修饰键: {}", part))?;
+            let name = canonical_modifier(modifier).to_string();
+            if !modifiers.contains(&name) {
+                modifiers.push(name);
+            }
+        }
+
+        Ok(ShortcutConfig {
+            modifiers,
+            key: key.to_lowercase(),
+            action: String::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_keys_to_codes() {
+        assert_eq!(key_to_code("space"), Some(Code::Space));
+        assert_eq!(key_to_code(" Space "), Some(Code::Space));
+        assert_eq!(key_to_code("up"), Some(Code::ArrowUp));
+        assert_eq!(key_to_code("ArrowDown"), Some(Code::ArrowDown));
+        assert_eq!(key_to_code("left"), Some(Code::ArrowLeft));
+        assert_eq!(key_to_code("arrowright"), Some(Code::ArrowRight));
+        assert_eq!(key_to_code(","), Some(Code::Comma));
+        assert_eq!(key_to_code("comma"), Some(Code::Comma));
+        assert_eq!(key_to_code("\\"), Some(Code::Backslash));
+        assert_eq!(key_to_code("`"), Some(Code::Backquote));
+        assert_eq!(key_to_code("numpad0"), Some(Code::Numpad0));
+        assert_eq!(key_to_code("Num9"), Some(Code::Numpad9));
+    }
+
+    #[test]
+    fn unknown_keys_have_no_code() {
+        for key in ["", " ", "f13", "ctrl", "numpad10", "ab", "😺"] {
+            assert_eq!(key_to_code(key), None, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn shortcut_round_trips_through_tauri_codes() {
+        for input in ["Ctrl+Alt+N", "Shift+Space", "Meta+ArrowUp", "Ctrl+Shift+,", "Alt+Num5", "Ctrl+F12"] {
+            let config: ShortcutConfig = input.parse().unwrap();
+            let (modifiers, code) = config.to_tauri_shortcut().unwrap();
+            let restored = ShortcutConfig::from_tauri_shortcut(modifiers, code).unwrap();
+            assert_eq!(restored.to_tauri_shortcut().unwrap(), (modifiers, code), "{}", input);
+        }
+
+        // 别名与修饰键顺序在还原后变为规范写法
+        let config: ShortcutConfig = "Shift+Cmd+pgup".parse().unwrap();
+        let (modifiers, code) = config.to_tauri_shortcut().unwrap();
+        let restored = ShortcutConfig::from_tauri_shortcut(modifiers, code).unwrap();
+        assert_eq!(restored.modifiers, ["shift", "meta"]);
+        assert_eq!(restored.key, "pageup");
+    }
+
+    fn config(modifiers: &[&str], key: &str) -> ShortcutConfig {
+        ShortcutConfig {
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            key: key.to_string(),
+            action: String::new(),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_registrable_shortcut() {
+        assert!(config(&["ctrl", "alt"], "n").validate().is_ok());
+        assert!(config(&["Command"], "space").validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_missing_modifiers() {
+        let err = config(&[], "n").validate().unwrap_err();
+        assert!(err.contains("修饰键"), "{}", err);
+    }
+
+    #[test]
+    fn validate_rejects_unknown_key_and_modifier() {
+        let err = config(&["ctrl"], "f13").validate().unwrap_err();
+        assert!(err.contains("未知的按键"), "{}", err);
+        let err = config(&["ctrl"], " ").validate().unwrap_err();
+        assert!(err.contains("缺少按键"), "{}", err);
+        let err = config(&["ctrl", "hyper"], "n").validate().unwrap_err();
+        assert!(err.contains("hyper"), "{}", err);
     }
 }
@@ -0,0 +1,298 @@
+use env_logger::Env;
+use log::{error, info, LevelFilter};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::utils::misc::app_dirs;
+
+/// 当前日志文件名，轮转后的旧文件依次为 `mememeow.log.1`、`mememeow.log.2` ...
+pub const LOG_FILE_NAME: &str = "mememeow.log";
+
+/// 单个日志文件的大小上限（字节），超过后轮转
+pub const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// 保留的旧日志文件数量
+pub const LOG_KEEP_FILES: usize = 3;
+
+/// 读取日志末尾时最多返回的行数
+pub const TAIL_MAX_LINES: usize = 2000;
+
+// 从文件末尾向前读取时每次读取的字节数
+const TAIL_CHUNK_BYTES: u64 = 8 * 1024;
+
+/// 日志目录
+pub fn log_dir() -> PathBuf {
+    app_dirs().cache_dir().join("logs")
+}
+
+/// 当前日志文件路径
+pub fn log_file_path() -> PathBuf {
+    log_dir().join(LOG_FILE_NAME)
+}
+
+/// 解析日志级别名（off、error、warn、info、debug、trace），忽略大小写
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("无效的日志级别: {}", level))
+}
+
+/// 运行时调整日志级别，立即对之后的日志生效
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+    info!("日志级别已设置为: {}", level);
+}
+
+/// 第 `index` 个轮转文件的路径
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// 按大小轮转的日志文件
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let file = File::options().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_bytes,
+            keep,
+        })
+    }
+
+    /// 将当前文件依次后移为 `.1`、`.2` ...，超出保留数量的最旧文件被覆盖
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for index in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+            self.file = File::options().create(true).append(true).open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            // 轮转失败时继续写入当前文件，不丢失日志
+            if let Err(e) = self.rotate() {
+                eprintln!("轮转日志文件失败: {:?} - {}", self.path, e);
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 初始化日志：写入按大小轮转的日志文件，调试构建同时通过 env_logger 输出到标准错误
+///
+/// 级别由 `level` 控制，可通过 [`set_level`] 在运行时调整；`RUST_LOG` 只影响标准错误输出。
+/// 日志文件无法创建时只输出到标准错误。
+pub fn init(level: LevelFilter) {
+    init_in(&log_dir(), level);
+}
+
+// 在指定目录下初始化日志，日志文件为 `dir` 下的 LOG_FILE_NAME
+fn init_in(dir: &Path, level: LevelFilter) {
+    let stderr = env_logger::Builder::from_env(Env::default().default_filter_or("trace")).build();
+
+    let path = dir.join(LOG_FILE_NAME);
+    let file = fs::create_dir_all(dir)
+        .and_then(|_| RotatingFile::open(path.clone(), LOG_MAX_BYTES, LOG_KEEP_FILES));
+
+    let mut dispatch = fern::Dispatch::new().level(LevelFilter::Trace);
+    let file_error = match file {
+        Ok(file) => {
+            dispatch = dispatch.chain(
+                fern::Dispatch::new()
+                    .format(|out, message, record| {
+                        out.finish(format_args!(
+                            "{} [{}] {}: {}",
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                            record.level(),
+                            record.target(),
+                            message
+                        ))
+                    })
+                    .chain(Box::new(file) as Box<dyn Write + Send>),
+            );
+            None
+        }
+        Err(e) => Some(e),
+    };
+    if cfg!(debug_assertions) || file_error.is_some() {
+        dispatch = dispatch.chain(Box::new(stderr) as Box<dyn log::Log>);
+    }
+
+    if let Err(e) = dispatch.apply() {
+        eprintln!("初始化日志失败: {}", e);
+        return;
+    }
+    log::set_max_level(level);
+
+    match file_error {
+        None => info!("日志文件: {:?}", path),
+        Some(e) => error!("创建日志文件失败: {:?} - {}", path, e),
+    }
+}
+
+/// 读取文件的最后 `lines` 行（最多 [`TAIL_MAX_LINES`] 行），按文件中的顺序返回
+///
+/// 从文件末尾按块向前读取，只读取所需的部分。文件不存在时返回空列表。
+pub fn tail_lines(path: &Path, lines: usize) -> io::Result<Vec<String>> {
+    let lines = lines.min(TAIL_MAX_LINES);
+    if lines == 0 {
+        return Ok(Vec::new());
+    }
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut pos = file.metadata()?.len();
+    // 从后向前读取的块，最后一块位于文件最前面
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    let mut newlines = 0;
+    // 末尾的换行不算作新的一行，因此需要多找到一个换行符
+    while pos > 0 && newlines <= lines {
+        let read = TAIL_CHUNK_BYTES.min(pos);
+        pos -= read;
+        let mut chunk = vec![0; read as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunks.push(chunk);
+    }
+    let buf: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+
+    let text = String::from_utf8_lossy(&buf);
+    let all: Vec<&str> = text.lines().collect();
+    // 没有读到文件开头时，第一行可能不完整
+    let complete = if pos > 0 { &all[1.min(all.len())..] } else { &all[..] };
+    Ok(complete[complete.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_creates_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        init_in(&logs, LevelFilter::Info);
+
+        log::info!("logging test marker");
+        log::debug!("filtered debug marker");
+        log::logger().flush();
+
+        let contents = fs::read_to_string(logs.join(LOG_FILE_NAME)).unwrap();
+        assert!(contents.contains("[INFO]"));
+        assert!(contents.contains("logging test marker"));
+        assert!(!contents.contains("filtered debug marker"));
+    }
+
+    #[test]
+    fn rotates_when_max_bytes_exceeded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        let mut file = RotatingFile::open(path.clone(), 16, 2).unwrap();
+
+        for line in ["first line 0001\n", "second line 002\n", "third line 0003\n", "fourth line 004\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth line 004\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "third line 0003\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "second line 002\n");
+        // 超出保留数量的最旧文件被丢弃
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn reopening_keeps_existing_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        fs::write(&path, "0123456789").unwrap();
+
+        let mut file = RotatingFile::open(path.clone(), 16, 1).unwrap();
+        file.write_all(b"abcdefghij").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abcdefghij");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "0123456789");
+    }
+
+    fn seeded_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn tail_without_trailing_newline() {
+        let file = seeded_file("one\ntwo\nthree");
+        assert_eq!(tail_lines(file.path(), 2).unwrap(), ["two", "three"]);
+    }
+
+    #[test]
+    fn tail_more_lines_than_exist() {
+        let file = seeded_file("one\ntwo\n");
+        assert_eq!(tail_lines(file.path(), 10).unwrap(), ["one", "two"]);
+        assert!(tail_lines(file.path(), 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tail_spanning_chunks() {
+        let contents: String = (0..3000).map(|i| format!("line {:04}\n", i)).collect();
+        assert!(contents.len() as u64 > 3 * TAIL_CHUNK_BYTES);
+        let file = seeded_file(&contents);
+
+        let tail = tail_lines(file.path(), 1500).unwrap();
+        assert_eq!(tail.len(), 1500);
+        assert_eq!(tail[0], "line 1500");
+        assert_eq!(tail[1499], "line 2999");
+
+        // 超过上限时只返回最后 TAIL_MAX_LINES 行
+        let tail = tail_lines(file.path(), 5000).unwrap();
+        assert_eq!(tail.len(), TAIL_MAX_LINES);
+        assert_eq!(tail[0], "line 1000");
+    }
+
+    #[test]
+    fn tail_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(tail_lines(&dir.path().join("missing.log"), 10).unwrap().is_empty());
+    }
+}
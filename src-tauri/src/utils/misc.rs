@@ -1,9 +1,90 @@
+use log::warn;
 use serde::{de, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// 搜索请求体的编码方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestFormat {
+    /// `application/json`
+    #[default]
+    Json,
+    /// `application/x-www-form-urlencoded`
+    Form,
+}
+
+impl std::str::FromStr for RequestFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(RequestFormat::Json),
+            "form" => Ok(RequestFormat::Form),
+            other => Err(format!("无效的请求格式: {}", other)),
+        }
+    }
+}
+
+/// 搜索请求使用的HTTP方法
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMethod {
+    /// 先尝试 POST，服务器返回 404/405 时改用 GET
+    #[default]
+    Auto,
+    Post,
+    /// `GET /search?query=...`，用于只实现了 GET 接口的镜像
+    Get,
+}
+
+impl std::str::FromStr for SearchMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(SearchMethod::Auto),
+            "post" => Ok(SearchMethod::Post),
+            "get" => Ok(SearchMethod::Get),
+            other => Err(format!("无效的搜索方法: {}", other)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ApiUrl {
     pub name: String,
     pub url: String,
+    #[serde(default)]
+    pub request_format: RequestFormat,
+    #[serde(default)]
+    pub search_method: SearchMethod,
+    /// 搜索结果数组在响应中的位置，用 `.` 分隔的路径，如 `payload.items`；
+    /// 未设置时自动识别 `results`、`data` 或纯数组格式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results_key: Option<String>,
+    /// 搜索请求附带的请求头，用于需要 `Authorization` 或 API Key 的私有部署
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+/// 请求头的值可能是密钥，调试输出中只显示名称
+impl std::fmt::Debug for ApiUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut header_names: Vec<&str> = self.headers.keys().map(String::as_str).collect();
+        header_names.sort();
+        f.debug_struct("ApiUrl")
+            .field("name", &self.name)
+            .field("url", &self.url)
+            .field("request_format", &self.request_format)
+            .field("search_method", &self.search_method)
+            .field("results_key", &self.results_key)
+            .field("headers", &header_names)
+            .finish()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,6 +98,173 @@ fn default_api_server_urls() -> Vec<ApiUrl> {
         ApiUrl {
             name: "默认API".to_string(),
             url: "https://mememeow.morami.icu".to_string(),
+            request_format: RequestFormat::default(),
+            search_method: SearchMethod::default(),
+            results_key: None,
+            headers: HashMap::new(),
         },
     ]
-}
\ No newline at end of file
+}
+
+/// 原子地写入文件
+///
+/// 先写入同目录下的 `<文件名>.tmp` 并落盘，再重命名覆盖目标文件。
+/// 写入过程中进程退出时，原文件保持完整。
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = result.and_then(|_| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 本地文件在 WebView 中的 asset 协议地址，与前端 `convertFileSrc` 的结果一致
+///
+/// Windows 与 Android 上为 `http://asset.localhost/<路径>`，其他平台为 `asset://localhost/<路径>`。
+pub fn asset_url(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    // 与 encodeURIComponent 相同，只保留字母、数字与 `-_.!~*'()`
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://asset.localhost/{}", encoded)
+    } else {
+        format!("asset://localhost/{}", encoded)
+    }
+}
+
+/// 应用在系统目录下使用的子目录名
+pub const APP_DIR_NAME: &str = "MemeMeow";
+
+/// 应用使用的配置与缓存根目录
+#[derive(Debug, Clone)]
+pub struct AppDirs {
+    pub config_root: PathBuf,
+    pub cache_root: PathBuf,
+}
+
+impl AppDirs {
+    /// 应用配置目录，如 `~/.config/MemeMeow`
+    pub fn config_dir(&self) -> PathBuf {
+        self.config_root.join(APP_DIR_NAME)
+    }
+
+    /// 应用缓存目录，如 `~/.cache/MemeMeow`
+    pub fn cache_dir(&self) -> PathBuf {
+        self.cache_root.join(APP_DIR_NAME)
+    }
+}
+
+static APP_DIRS: OnceLock<AppDirs> = OnceLock::new();
+
+/// 解析配置与缓存根目录
+///
+/// 当 `dirs` 无法获取系统目录时（如精简/无头环境），统一回退到当前工作目录，
+/// 当前目录也不可用时回退到系统临时目录。结果只解析一次，所有模块共用。
+pub fn app_dirs() -> &'static AppDirs {
+    APP_DIRS.get_or_init(|| {
+        // 测试使用进程独有的临时目录，不会读写用户的配置与缓存
+        if cfg!(test) {
+            let root = std::env::temp_dir().join(format!("mememeow-test-{}", std::process::id()));
+            return AppDirs {
+                config_root: root.join("config"),
+                cache_root: root.join("cache"),
+            };
+        }
+        AppDirs {
+            config_root: dirs::config_dir().unwrap_or_else(|| fallback_dir("配置")),
+            cache_root: dirs::cache_dir().unwrap_or_else(|| fallback_dir("缓存")),
+        }
+    })
+}
+
+fn fallback_dir(kind: &str) -> PathBuf {
+    let dir = std::env::current_dir().unwrap_or_else(|_| std::env::temp_dir());
+    warn!(
+        "!!! 无法获取系统{}目录，将回退使用 {:?}，相关数据可能不会被持久保存 !!!",
+        kind, dir
+    );
+    dir
+}
+
+/// 隐去URL中的用户名和密码，用于日志和诊断输出
+pub fn redact_url(url: &str) -> String {
+    match tauri::Url::parse(url) {
+        Ok(mut parsed) => {
+            if !parsed.username().is_empty() {
+                let _ = parsed.set_username("***");
+            }
+            if parsed.password().is_some() {
+                let _ = parsed.set_password(Some("***"));
+            }
+            parsed.to_string()
+        }
+        Err(_) => "<无效URL>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_url_encodes_like_convert_file_src() {
+        let url = asset_url(Path::new("/home/me/.cache/MemeMeow/libs/a b/猫(1).png"));
+        let encoded = "%2Fhome%2Fme%2F.cache%2FMemeMeow%2Flibs%2Fa%20b%2F%E7%8C%AB(1).png";
+        if cfg!(any(windows, target_os = "android")) {
+            assert_eq!(url, format!("http://asset.localhost/{}", encoded));
+        } else {
+            assert_eq!(url, format!("asset://localhost/{}", encoded));
+        }
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, b"old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(!dir.path().join("config.json.tmp").exists());
+    }
+
+    #[test]
+    fn write_atomic_failure_keeps_old_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, b"old").unwrap();
+        // 临时文件路径被目录占用，写入临时文件失败
+        fs::create_dir(dir.path().join("config.json.tmp")).unwrap();
+
+        assert!(write_atomic(&path, b"new").is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+    }
+
+    #[test]
+    fn write_atomic_ignores_partial_tmp_from_interrupted_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, b"{\"a\": 1}").unwrap();
+        // 上次写入中途退出，只留下不完整的临时文件
+        fs::write(dir.path().join("config.json.tmp"), b"{\"a\":").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"{\"a\": 1}");
+
+        write_atomic(&path, b"{\"a\": 2}").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"{\"a\": 2}");
+    }
+}
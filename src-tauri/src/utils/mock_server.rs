@@ -0,0 +1,145 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 服务器收到的请求
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    /// 请求行中的路径与查询参数，如 `/search?query=cat`
+    pub target: String,
+    pub body: String,
+}
+
+/// 服务器返回的响应
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    /// 发送响应前等待的时间
+    pub delay: Duration,
+    /// 是否发送 `Content-Length`，不发送时以连接关闭表示响应结束
+    pub content_length: bool,
+}
+
+impl MockResponse {
+    pub fn json(body: &str) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: body.as_bytes().to_vec(),
+            delay: Duration::ZERO,
+            content_length: true,
+        }
+    }
+
+    pub fn bytes(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            content_type,
+            body,
+            ..Self::json("")
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            ..Self::json("")
+        }
+    }
+}
+
+/// 按请求决定响应的本地服务器，记录收到的全部请求
+pub struct MockServer {
+    /// 服务器地址，如 `http://127.0.0.1:12345`
+    pub url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockServer {
+    pub async fn start<F>(respond: F) -> Self
+    where
+        F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let respond = Arc::new(respond);
+
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let recorded = Arc::clone(&recorded);
+                let respond = Arc::clone(&respond);
+                tokio::spawn(async move {
+                    let Some((socket, request)) = read_request(socket).await else {
+                        return;
+                    };
+                    let response = respond(&request);
+                    recorded.lock().unwrap().push(request);
+                    write_response(socket, response).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// 已收到的请求，按到达顺序排列
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(mut socket: TcpStream) -> Option<(TcpStream, MockRequest)> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while data.len() < head_end + content_length {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let request = MockRequest {
+        method: request_line.next()?.to_string(),
+        target: request_line.next()?.to_string(),
+        body: String::from_utf8_lossy(&data[head_end..head_end + content_length]).to_string(),
+    };
+    Some((socket, request))
+}
+
+async fn write_response(mut socket: TcpStream, response: MockResponse) {
+    tokio::time::sleep(response.delay).await;
+    let mut head = format!(
+        "HTTP/1.1 {} MOCK\r\nContent-Type: {}\r\nConnection: close\r\n",
+        response.status, response.content_type
+    );
+    if response.content_length {
+        head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    }
+    head.push_str("\r\n");
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(&response.body).await;
+    let _ = socket.shutdown().await;
+}
@@ -1,3 +1,14 @@
 pub mod network;
 pub mod key_map;
-pub mod misc;
\ No newline at end of file
+pub mod misc;
+pub mod cancel;
+pub mod request_trace;
+pub mod concurrency;
+pub mod recent_errors;
+pub mod logging;
+pub mod image_cache;
+// 测试用的本地HTTP服务器
+#[cfg(test)]
+pub mod mock_server;
+#[cfg(desktop)]
+pub mod paste;
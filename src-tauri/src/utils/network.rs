@@ -1,26 +1,307 @@
 use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
 use tauri_plugin_http::reqwest::{self, Client, StatusCode};
 
-/// 尝试从多个URL下载文件，使用渐进式超时策略
+use crate::utils::{concurrency, request_trace};
+
+/// 每轮重试之间超时时间的增长方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimeoutGrowth {
+    /// 每轮增加固定秒数
+    Linear { step_secs: u64 },
+    /// 每轮乘以固定倍数
+    Exponential { factor: u64 },
+    /// 每轮使用相同的超时
+    Fixed,
+}
+
+/// 渐进式超时策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeoutPolicy {
+    /// 第一轮的超时秒数
+    pub initial_secs: u64,
+    /// 超时秒数上限，超过后不再重试
+    pub max_secs: u64,
+    pub growth: TimeoutGrowth,
+    /// 最多尝试的轮数
+    pub max_rounds: u32,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        Self {
+            initial_secs: 3,
+            max_secs: 10,
+            growth: TimeoutGrowth::Exponential { factor: 2 },
+            max_rounds: 3,
+        }
+    }
+}
+
+impl TimeoutPolicy {
+    /// 检查策略是否合理
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_secs == 0 {
+            return Err("初始超时必须大于0秒".to_string());
+        }
+        if self.max_secs < self.initial_secs {
+            return Err("超时上限不能小于初始超时".to_string());
+        }
+        if self.max_rounds == 0 || self.max_rounds > 10 {
+            return Err("重试轮数必须在1到10之间".to_string());
+        }
+        match self.growth {
+            TimeoutGrowth::Linear { step_secs: 0 } => Err("线性增长步长必须大于0秒".to_string()),
+            TimeoutGrowth::Exponential { factor } if factor < 2 => {
+                Err("指数增长倍数必须至少为2".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 按策略计算每一轮使用的超时秒数
+    pub fn timeouts(&self) -> Vec<u64> {
+        let mut timeouts = Vec::new();
+        let mut timeout = self.initial_secs;
+
+        while timeout <= self.max_secs && (timeouts.len() as u32) < self.max_rounds {
+            timeouts.push(timeout);
+            timeout = match self.growth {
+                TimeoutGrowth::Linear { step_secs } => timeout.saturating_add(step_secs),
+                TimeoutGrowth::Exponential { factor } => timeout.saturating_mul(factor),
+                TimeoutGrowth::Fixed => timeout,
+            };
+        }
+
+        timeouts
+    }
+}
+
+/// 代理地址支持的协议
+const PROXY_SCHEMES: [&str; 4] = ["http", "https", "socks5", "socks5h"];
+
+/// 校验并解析代理地址，如 `http://127.0.0.1:7890`
+pub fn parse_proxy(proxy_url: &str) -> Result<reqwest::Proxy, String> {
+    let url = reqwest::Url::parse(proxy_url.trim()).map_err(|e| format!("代理地址无效: {}", e))?;
+    if !PROXY_SCHEMES.contains(&url.scheme()) {
+        return Err(format!("不支持的代理协议: {}", url.scheme()));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("代理地址缺少主机名".to_string());
+    }
+    reqwest::Proxy::all(url.as_str()).map_err(|e| format!("代理地址无效: {}", e))
+}
+
+/// 创建HTTP客户端构建器，并应用偏好设置中的代理
+///
+/// 代理地址无效时记录警告并直连，避免所有网络请求都失败。
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let proxy_url = crate::get_config_manager()
+        .get_preferences()
+        .ok()
+        .and_then(|prefs| prefs.proxy_url);
+    client_builder_with_proxy(proxy_url.as_deref())
+}
+
+/// 创建应用指定代理的HTTP客户端构建器，未设置或地址无效时直连
+fn client_builder_with_proxy(proxy_url: Option<&str>) -> reqwest::ClientBuilder {
+    let builder = Client::builder();
+    match proxy_url.filter(|url| !url.trim().is_empty()) {
+        Some(proxy_url) => match parse_proxy(proxy_url) {
+            Ok(proxy) => builder.proxy(proxy),
+            Err(e) => {
+                warn!("{}，将不使用代理", e);
+                builder
+            }
+        },
+        None => builder,
+    }
+}
+
+/// 尝试从多个URL下载文件，使用偏好设置中的渐进式超时策略与尝试方式
+///
+/// 默认策略：初始超时3秒，每轮翻倍，最大超时10秒，按顺序尝试。
+/// 详见 [`download_with_fallback_urls_mode`]。
+pub async fn download_with_fallback_urls<I, S>(urls: I) -> Result<String, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let (policy, mode) = crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| (prefs.download_timeout, prefs.download_mode))
+        .unwrap_or_default();
+    download_with_fallback_urls_mode(urls, policy, mode).await
+}
+
+/// 尝试从多个URL下载文件，使用给定的渐进式超时策略
+///
+/// 以文本形式返回内容，详见 [`download_bytes_with_fallback_urls_policy`]。
+pub async fn download_with_fallback_urls_policy<I, S>(
+    urls: I,
+    policy: TimeoutPolicy,
+) -> Result<String, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    download_with_fallback_urls_mode(urls, policy, DownloadMode::Sequential).await
+}
+
+/// 多个URL的尝试方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadMode {
+    /// 每一轮按顺序逐个尝试
+    #[default]
+    Sequential,
+    /// 每一轮同时请求所有URL，采用最先成功的响应并取消其余请求
+    Race,
+}
+
+/// 尝试从多个URL下载文件，使用给定的超时策略与尝试方式
+///
+/// 多个镜像中有的可能长时间挂起时，[`DownloadMode::Race`] 比顺序尝试快得多。
+pub async fn download_with_fallback_urls_mode<I, S>(
+    urls: I,
+    policy: TimeoutPolicy,
+    mode: DownloadMode,
+) -> Result<String, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let outcome = download_outcome_with_fallback_urls(urls, policy, mode).await?;
+    Ok(outcome.text())
+}
+
+/// 备用URL下载的结果
+#[derive(Debug, Clone)]
+pub struct DownloadOutcome {
+    /// 成功下载的URL
+    pub url: String,
+    pub bytes: Vec<u8>,
+    /// 从开始尝试到下载完成的总耗时，包括失败的尝试
+    pub elapsed: Duration,
+}
+
+impl DownloadOutcome {
+    /// 以文本形式取出内容，无效的UTF-8字节会被替换
+    pub fn text(self) -> String {
+        match String::from_utf8(self.bytes) {
+            Ok(text) => text,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        }
+    }
+}
+
+/// 尝试从多个URL下载文件，同时返回成功的URL与耗时
+///
+/// 调用方可以据此记录或优先使用可用的镜像。
+pub async fn download_outcome_with_fallback_urls<I, S>(
+    urls: I,
+    policy: TimeoutPolicy,
+    mode: DownloadMode,
+) -> Result<DownloadOutcome, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    download_with_progress_policy(urls, policy, mode, &mut |_| {}).await
+}
+
+/// 尝试从多个URL下载二进制文件（图片、表情包资源包等），使用偏好设置中的渐进式超时策略
+///
+/// 与 [`download_with_fallback_urls`] 相同，但不会把内容按UTF-8解码。
+pub async fn download_bytes_with_fallback_urls<I, S>(urls: I) -> Result<Vec<u8>, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let policy = crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.download_timeout)
+        .unwrap_or_default();
+    download_bytes_with_fallback_urls_policy(urls, policy).await
+}
+
+/// 尝试从多个URL下载二进制文件，使用给定的渐进式超时策略
 /// 
-/// - 初始超时设置为1秒
-/// - 如果所有URL都尝试失败，增加超时时间并重试
-/// - 最大超时时间为10秒
+/// - 每一轮依次尝试所有URL
+/// - 如果所有URL都尝试失败，按策略增加超时时间并重试
+/// - 超时超过上限或达到最大轮数后放弃
 /// 
 /// # 参数
 /// 
 /// * `urls` - 要尝试下载的URL，可以是单个String或Vec<String>
+/// * `policy` - 超时增长策略
 /// 
 /// # 返回值
 /// 
-/// * `Ok(String)` - 下载成功的文件内容
+/// * `Ok(Vec<u8>)` - 下载成功的文件内容
 /// * `Err(String)` - 下载失败的错误信息
-pub async fn download_with_fallback_urls<I, S>(urls: I) -> Result<String, String> 
+pub async fn download_bytes_with_fallback_urls_policy<I, S>(
+    urls: I,
+    policy: TimeoutPolicy,
+) -> Result<Vec<u8>, String>
 where 
     I: IntoIterator<Item = S>,
     S: AsRef<str>,
 {
+    download_with_progress_policy(urls, policy, DownloadMode::Sequential, &mut |_| {})
+        .await
+        .map(|outcome| outcome.bytes)
+}
+
+/// 下载进度，作为 `download-progress` 事件的负载发送给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub url: String,
+    /// 已接收的字节数
+    pub received: u64,
+    /// 响应的 `Content-Length`，服务器未提供时为 `None`
+    pub total: Option<u64>,
+    /// 下载百分比（0-100），总大小未知时为 `None`
+    pub percent: Option<f64>,
+}
+
+/// 两次进度回调之间至少间隔的字节数，避免大文件产生过多事件
+const PROGRESS_STEP_BYTES: u64 = 64 * 1024;
+
+/// 尝试从多个URL下载二进制文件，并在接收数据时回调下载进度
+///
+/// 重试方式与 [`download_bytes_with_fallback_urls`] 相同。切换到下一个URL时
+/// 进度从0重新开始。
+pub async fn download_bytes_with_progress<I, S, F>(urls: I, mut on_progress: F) -> Result<Vec<u8>, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    F: FnMut(DownloadProgress) + Send,
+{
+    let policy = crate::get_config_manager()
+        .get_preferences()
+        .map(|prefs| prefs.download_timeout)
+        .unwrap_or_default();
+    download_with_progress_policy(urls, policy, DownloadMode::Sequential, &mut on_progress)
+        .await
+        .map(|outcome| outcome.bytes)
+}
+
+async fn download_with_progress_policy<I, S>(
+    urls: I,
+    policy: TimeoutPolicy,
+    mode: DownloadMode,
+    on_progress: &mut (dyn FnMut(DownloadProgress) + Send),
+) -> Result<DownloadOutcome, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let started = std::time::Instant::now();
+
     // 收集URL到Vec中以便多次使用
     let urls: Vec<String> = urls
         .into_iter()
@@ -31,41 +312,31 @@ where
         return Err("URL列表为空".to_string());
     }
     
-
-    let mut timeout = 3;
-    let max_timeout = 10;
-    
-    while timeout <= max_timeout {
+    let timeouts = policy.timeouts();
+    for (round, &timeout) in timeouts.iter().enumerate() {
+        if round > 0 {
+            warn!("所有URL下载失败，增加超时时间至{}秒后重试", timeout);
+        }
         debug!("使用{}秒超时尝试下载", timeout);
         
         // 创建带有自定义超时的客户端
-        let client = match Client::builder()
+        let client = match client_builder()
             .timeout(Duration::from_secs(timeout))
             .build() {
                 Ok(client) => client,
                 Err(e) => return Err(format!("创建HTTP客户端失败: {}", e)),
             };
             
-        // 尝试每个URL
-        for (i, url) in urls.iter().enumerate() {
-            debug!("尝试URL {}/{}: {}", i+1, urls.len(), url);
-            
-            match download_single_url(&client, url).await {
-                Ok(content) => {
-                    info!("成功从URL下载内容: {}", url);
-                    return Ok(content);
-                },
-                Err(e) => {
-                    warn!("从URL下载失败: {} - 错误: {}", url, e);
-                    // 继续尝试下一个URL
-                }
-            }
-        }
-        
-        // 如果所有URL都失败了，增加超时时间
-        timeout *= 2;
-        if timeout <= max_timeout {
-            warn!("所有URL下载失败，增加超时时间至{}秒后重试", timeout);
+        let downloaded = match mode {
+            DownloadMode::Sequential => try_urls_in_order(&client, &urls, &mut *on_progress).await,
+            DownloadMode::Race => race_urls(&client, &urls).await,
+        };
+        if let Some((url, bytes)) = downloaded {
+            return Ok(DownloadOutcome {
+                url,
+                bytes,
+                elapsed: started.elapsed(),
+            });
         }
     }
     
@@ -73,24 +344,675 @@ where
     Err("无法从任何提供的URL下载内容".to_string())
 }
 
-/// 从单个URL下载内容
-async fn download_single_url(client: &Client, url: &str) -> Result<String, String> {
+/// 依次尝试每个URL，返回第一个成功的URL及其响应内容
+async fn try_urls_in_order(
+    client: &Client,
+    urls: &[String],
+    on_progress: &mut (dyn FnMut(DownloadProgress) + Send),
+) -> Option<(String, Vec<u8>)> {
+    for (i, url) in urls.iter().enumerate() {
+        debug!("尝试URL {}/{}: {}", i+1, urls.len(), url);
+        
+        match download_single_url(client, url, &mut *on_progress).await {
+            Ok(content) => {
+                info!("成功从URL下载内容: {}", url);
+                return Some((url.clone(), content));
+            },
+            Err(e) => {
+                warn!("从URL下载失败: {} - 错误: {}", url, e);
+                // 继续尝试下一个URL
+            }
+        }
+    }
+    None
+}
+
+/// 同时请求所有URL，返回最先成功的URL及其响应内容
+///
+/// 返回时丢弃 `JoinSet`，其余仍在进行的请求随之被取消。
+async fn race_urls(client: &Client, urls: &[String]) -> Option<(String, Vec<u8>)> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for url in urls {
+        let client = client.clone();
+        let url = url.clone();
+        tasks.spawn(async move {
+            let result = download_single_url(&client, &url, &mut |_| {}).await;
+            (url, result)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((url, Ok(content))) => {
+                info!("竞速下载中最先成功的URL: {}", url);
+                return Some((url, content));
+            }
+            Ok((url, Err(e))) => warn!("从URL下载失败: {} - 错误: {}", url, e),
+            Err(e) => error!("下载任务异常退出: {}", e),
+        }
+    }
+    None
+}
+
+/// 从单个URL下载内容，边接收边回调进度
+async fn download_single_url(
+    client: &Client,
+    url: &str,
+    on_progress: &mut (dyn FnMut(DownloadProgress) + Send),
+) -> Result<Vec<u8>, String> {
+    let request = client
+        .get(url)
+        .build()
+        .map_err(|e| format!("构建请求失败: {}", e))?;
+    request_trace::trace_request(&request);
+
     // 发起请求
-    let response = match client.get(url).send().await {
+    let mut response = match client.execute(request).await {
         Ok(resp) => resp,
         Err(e) => {
             return Err(format!("请求失败: {}", e));
         }
     };
-    
+    let status = response.status();
+    let headers = response.headers().clone();
+
     // 检查状态码
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        request_trace::trace_response(url, status, &headers, &text);
+        return Err(format!("状态码错误: {}", status));
+    }
+
+    // 分块读取响应内容
+    let total = response.content_length();
+    let mut bytes = Vec::new();
+    let mut last_reported = 0u64;
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => return Err(format!("读取响应内容失败: {}", e)),
+        };
+        bytes.extend_from_slice(&chunk);
+
+        let received = bytes.len() as u64;
+        if received - last_reported >= PROGRESS_STEP_BYTES {
+            last_reported = received;
+            on_progress(progress_of(url, received, total));
+        }
+    }
+    on_progress(progress_of(url, bytes.len() as u64, total));
+
+    if request_trace::is_enabled() {
+        request_trace::trace_response(url, status, &headers, &String::from_utf8_lossy(&bytes));
+    }
+
+    Ok(bytes)
+}
+
+fn progress_of(url: &str, received: u64, total: Option<u64>) -> DownloadProgress {
+    let percent = total
+        .filter(|&total| total > 0)
+        .map(|total| (received as f64 / total as f64 * 100.0).min(100.0));
+    DownloadProgress {
+        url: url.to_string(),
+        received,
+        total,
+        percent,
+    }
+}
+
+/// 单张图片下载的大小上限（字节）
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// 下载得到的图片数据
+pub struct DownloadedImage {
+    pub bytes: Vec<u8>,
+    /// 去掉参数后的小写 `Content-Type`，如 `image/png`
+    pub content_type: Option<String>,
+}
+
+/// 下载一张图片，超过 [`MAX_IMAGE_BYTES`] 时返回错误
+///
+/// `data:` 地址（如测试模式的数据）直接在本地解码，不发起请求。
+pub async fn download_image(url: &str) -> Result<DownloadedImage, String> {
+    if let Some(image) = decode_data_uri(url) {
+        return image;
+    }
+
+    let client = client_builder()
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
     if !response.status().is_success() {
         return Err(format!("状态码错误: {}", response.status()));
     }
-    
-    // 获取响应内容
-    match response.text().await {
-        Ok(text) => Ok(text),
-        Err(e) => Err(format!("读取响应内容失败: {}", e))
+
+    // 服务器声明的大小超过上限时不再读取
+    if let Some(length) = response.content_length() {
+        if length as usize > MAX_IMAGE_BYTES {
+            return Err(format!("图片过大: {} 字节，上限为 {} 字节", length, MAX_IMAGE_BYTES));
+        }
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase());
+
+    let bytes = read_body_limited(response, MAX_IMAGE_BYTES).await?;
+
+    Ok(DownloadedImage {
+        bytes,
+        content_type,
+    })
+}
+
+/// 解码 `data:<MIME类型>;base64,<数据>` 形式的图片地址，不是 data URI 时返回 `None`
+fn decode_data_uri(url: &str) -> Option<Result<DownloadedImage, String>> {
+    use base64::Engine;
+
+    let rest = url.trim().strip_prefix("data:")?;
+    let decode = || -> Result<DownloadedImage, String> {
+        let (meta, data) = rest.split_once(',').ok_or("data URI 缺少数据部分")?;
+        let mime = meta
+            .strip_suffix(";base64")
+            .ok_or("仅支持 base64 编码的 data URI")?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| format!("data URI 解码失败: {}", e))?;
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(format!("图片过大: {} 字节，上限为 {} 字节", bytes.len(), MAX_IMAGE_BYTES));
+        }
+        Ok(DownloadedImage {
+            bytes,
+            content_type: Some(mime.trim().to_lowercase()).filter(|mime| !mime.is_empty()),
+        })
+    };
+    Some(decode())
+}
+
+/// 分块读取响应内容，累计超过 `max_bytes` 时立即中止
+///
+/// 服务器未声明 `Content-Length` 或声明的值不准确时，也不会把超出上限的内容读入内存。
+async fn read_body_limited(mut response: reqwest::Response, max_bytes: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("读取响应内容失败: {}", e))?
+    {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(format!("图片过大: 超过上限 {} 字节", max_bytes));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// 根据 `Content-Type` 或文件头推断图片扩展名
+pub fn image_extension(content_type: Option<&str>, bytes: &[u8]) -> Option<&'static str> {
+    let from_content_type = match content_type {
+        Some("image/png") => Some("png"),
+        Some("image/jpeg") | Some("image/jpg") => Some("jpg"),
+        Some("image/gif") => Some("gif"),
+        Some("image/webp") => Some("webp"),
+        Some("image/bmp") => Some("bmp"),
+        Some("image/svg+xml") => Some("svg"),
+        _ => None,
+    };
+
+    from_content_type.or_else(|| {
+        if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+            Some("png")
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some("jpg")
+        } else if bytes.starts_with(b"GIF8") {
+            Some("gif")
+        } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some("webp")
+        } else if bytes.starts_with(b"BM") {
+            Some("bmp")
+        } else {
+            None
+        }
+    })
+}
+
+/// 是否为包含多帧的GIF动图
+///
+/// 只解码前两帧；无法解析或画布过大的数据视为非动图。
+pub fn is_animated_gif(bytes: &[u8]) -> bool {
+    use image::AnimationDecoder;
+
+    if !bytes.starts_with(b"GIF8") {
+        return false;
+    }
+    let Ok(decoder) = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(bytes)) else {
+        return false;
+    };
+    // 每一帧都会合成到完整画布上，画布过大时不解码
+    let (width, height) = image::ImageDecoder::dimensions(&decoder);
+    if u64::from(width) * u64::from(height) * 4 > MAX_IMAGE_BYTES as u64 {
+        return false;
+    }
+    decoder.into_frames().take(2).filter(Result::is_ok).count() > 1
+}
+
+fn parse_content_type(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+}
+
+/// 在不下载完整文件的情况下获取URL的 `Content-Type`
+///
+/// 先发送 HEAD 请求；服务器不支持 HEAD 时改用只请求首字节的 GET。
+/// 服务器未返回该响应头时得到 `Ok(None)`。
+pub async fn fetch_content_type(url: &str) -> Result<Option<String>, String> {
+    let client = client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.status().is_success() {
+        return Ok(parse_content_type(response.headers()));
+    }
+
+    if !matches!(
+        response.status(),
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED | StatusCode::FORBIDDEN
+    ) {
+        return Err(format!("状态码错误: {}", response.status()));
+    }
+
+    debug!("服务器不支持HEAD请求，改用范围GET: {}", url);
+    let response = client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("状态码错误: {}", response.status()));
+    }
+    Ok(parse_content_type(response.headers()))
+}
+
+/// 解析重定向时最多跟随的次数
+pub const MAX_REDIRECTS: usize = 10;
+
+/// 逐跳跟随重定向，返回最终的URL
+///
+/// 超过 [`MAX_REDIRECTS`] 次或出现循环时返回错误。
+pub async fn resolve_redirects(url: &str) -> Result<String, String> {
+    let client = client_builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+
+    let mut current = reqwest::Url::parse(url).map_err(|e| format!("无效的URL: {}", e))?;
+    let mut visited = std::collections::HashSet::new();
+
+    for _ in 0..=MAX_REDIRECTS {
+        if !visited.insert(current.to_string()) {
+            return Err(format!("检测到重定向循环: {}", current));
+        }
+
+        let mut response = client
+            .head(current.clone())
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+        if matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED | StatusCode::FORBIDDEN
+        ) {
+            response = client
+                .get(current.clone())
+                .header(reqwest::header::RANGE, "bytes=0-0")
+                .send()
+                .await
+                .map_err(|e| format!("请求失败: {}", e))?;
+        }
+
+        if !response.status().is_redirection() {
+            return Ok(current.to_string());
+        }
+
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| format!("重定向缺少Location: {}", current))?;
+        // Location 可能是相对地址
+        let next = current
+            .join(location)
+            .map_err(|e| format!("无效的重定向地址: {} - {}", location, e))?;
+        debug!("重定向: {} -> {}", current, next);
+        current = next;
+    }
+
+    Err(format!("重定向次数超过上限（{}次）", MAX_REDIRECTS))
+}
+
+/// 根据URL中的扩展名猜测图片MIME类型
+pub fn guess_mime_from_url(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let ext = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase())?;
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// 探测结果
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+impl ProbeResult {
+    /// 收到响应且不是服务器错误即视为可用
+    pub fn is_reachable(&self) -> bool {
+        self.status < 500
+    }
+}
+
+/// 向URL发送轻量的 HEAD 请求，测量往返耗时
+///
+/// `headers` 为API源配置的请求头，需要认证的API未带认证头时会被误判为不可用。
+pub async fn probe_url(
+    url: &str,
+    headers: &HashMap<String, String>,
+    timeout: Duration,
+) -> Result<ProbeResult, String> {
+    let client = client_builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+
+    // URL探测可能被批量发起，受全局并发上限约束
+    let _permit = concurrency::acquire().await;
+
+    let started = std::time::Instant::now();
+    let response = headers
+        .iter()
+        .fold(client.head(url), |builder, (name, value)| {
+            builder.header(name.as_str(), value.as_str())
+        })
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    Ok(ProbeResult {
+        status: response.status().as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockResponse, MockServer};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn policy(initial_secs: u64, max_secs: u64, growth: TimeoutGrowth, max_rounds: u32) -> TimeoutPolicy {
+        TimeoutPolicy {
+            initial_secs,
+            max_secs,
+            growth,
+            max_rounds,
+        }
+    }
+
+    #[test]
+    fn default_policy_timeouts() {
+        // 第三轮的 12 秒超过 10 秒上限，不再重试
+        assert_eq!(TimeoutPolicy::default().timeouts(), [3, 6]);
+    }
+
+    #[test]
+    fn linear_policy_timeouts() {
+        let linear = TimeoutGrowth::Linear { step_secs: 2 };
+        assert_eq!(policy(3, 10, linear, 10).timeouts(), [3, 5, 7, 9]);
+        assert_eq!(policy(3, 10, linear, 2).timeouts(), [3, 5]);
+    }
+
+    #[test]
+    fn exponential_policy_timeouts() {
+        let exponential = TimeoutGrowth::Exponential { factor: 3 };
+        assert_eq!(policy(1, 30, exponential, 10).timeouts(), [1, 3, 9, 27]);
+        // 溢出时饱和到最大值，随后超过上限而停止
+        assert_eq!(policy(u64::MAX / 2, u64::MAX - 1, exponential, 10).timeouts(), [u64::MAX / 2]);
+    }
+
+    #[test]
+    fn fixed_policy_timeouts() {
+        assert_eq!(policy(5, 5, TimeoutGrowth::Fixed, 3).timeouts(), [5, 5, 5]);
+        assert!(policy(5, 4, TimeoutGrowth::Fixed, 3).timeouts().is_empty());
+    }
+
+    /// 在本地启动只响应一次的HTTP服务器，返回其地址
+    ///
+    /// 不发送 `Content-Length`，以连接关闭表示响应结束。
+    async fn serve_once(body: Vec<u8>, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            tokio::time::sleep(delay).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nConnection: close\r\n\r\n";
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&body).await;
+            let _ = socket.shutdown().await;
+        });
+        format!("http://{}/image.png", addr)
+    }
+
+    async fn get(url: &str) -> reqwest::Response {
+        Client::new().get(url).send().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_body_within_limit() {
+        let url = serve_once(vec![7u8; 300], Duration::ZERO).await;
+        let bytes = read_body_limited(get(&url).await, 300).await.unwrap();
+        assert_eq!(bytes, vec![7u8; 300]);
+    }
+
+    #[tokio::test]
+    async fn aborts_when_body_exceeds_limit() {
+        let url = serve_once(vec![7u8; 64 * 1024], Duration::ZERO).await;
+        let err = read_body_limited(get(&url).await, 1024).await.unwrap_err();
+        assert!(err.contains("1024"));
+    }
+
+    #[tokio::test]
+    async fn slow_url_loses_the_race() {
+        let slow = serve_once(b"slow".to_vec(), Duration::from_secs(2)).await;
+        let fast = serve_once(b"fast".to_vec(), Duration::from_millis(50)).await;
+
+        let started = std::time::Instant::now();
+        let (url, bytes) = race_urls(&Client::new(), &[slow, fast.clone()]).await.unwrap();
+        assert_eq!(url, fast);
+        assert_eq!(bytes, b"fast");
+        // 不等待较慢的请求完成
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn downloads_binary_bytes_unchanged() {
+        // 包含所有字节值，其中大部分不是合法的UTF-8
+        let body: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        let expected = body.clone();
+        let server = MockServer::start(move |_| MockResponse::bytes("image/png", body.clone())).await;
+
+        let url = format!("{}/image.png", server.url);
+        let bytes = download_bytes_with_fallback_urls_policy([url], TimeoutPolicy::default())
+            .await
+            .unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    async fn progress_of_download(content_length: bool) -> Vec<DownloadProgress> {
+        let body = vec![7u8; 5 * PROGRESS_STEP_BYTES as usize + 123];
+        let server = MockServer::start(move |_| MockResponse {
+            content_length,
+            ..MockResponse::bytes("image/png", body.clone())
+        })
+        .await;
+
+        let mut events = Vec::new();
+        let url = format!("{}/image.png", server.url);
+        download_with_progress_policy([url], TimeoutPolicy::default(), DownloadMode::Sequential, &mut |p| {
+            events.push(p)
+        })
+        .await
+        .unwrap();
+        events
+    }
+
+    #[tokio::test]
+    async fn progress_increases_up_to_total() {
+        let size = 5 * PROGRESS_STEP_BYTES + 123;
+        let events = progress_of_download(true).await;
+        assert!(events.len() > 1);
+        assert!(events.windows(2).all(|w| w[0].received < w[1].received));
+        assert!(events.iter().all(|p| p.total == Some(size)));
+        assert!(events.windows(2).all(|w| w[0].percent < w[1].percent));
+
+        let last = events.last().unwrap();
+        assert_eq!(last.received, size);
+        assert_eq!(last.percent, Some(100.0));
+    }
+
+    #[tokio::test]
+    async fn progress_without_content_length_has_no_percent() {
+        let events = progress_of_download(false).await;
+        assert!(events.windows(2).all(|w| w[0].received < w[1].received));
+        assert!(events.iter().all(|p| p.total.is_none() && p.percent.is_none()));
+        assert_eq!(events.last().unwrap().received, 5 * PROGRESS_STEP_BYTES + 123);
+    }
+
+    #[tokio::test]
+    async fn outcome_reports_the_url_that_succeeded() {
+        let server = MockServer::start(|req| match req.target.as_str() {
+            "/broken.png" => MockResponse::status(500),
+            _ => MockResponse::bytes("image/png", b"mirror".to_vec()),
+        })
+        .await;
+
+        let broken = format!("{}/broken.png", server.url);
+        let mirror = format!("{}/mirror.png", server.url);
+        let outcome = download_outcome_with_fallback_urls(
+            [broken, mirror.clone()],
+            TimeoutPolicy::default(),
+            DownloadMode::Sequential,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome.url, mirror);
+        assert_eq!(outcome.bytes, b"mirror");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    /// 1x1 像素、包含 `frames` 帧的最小GIF
+    fn gif(frames: usize) -> Vec<u8> {
+        let mut bytes = b"GIF89a\x01\x00\x01\x00\x80\x00\x00".to_vec();
+        // 两种颜色的全局调色板：黑、白
+        bytes.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+        for _ in 0..frames {
+            bytes.extend_from_slice(b"\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00");
+            bytes.extend_from_slice(&[0x02, 0x02, 0x44, 0x01, 0x00]);
+        }
+        bytes.push(0x3b);
+        bytes
+    }
+
+    #[test]
+    fn detects_animated_gif() {
+        assert!(is_animated_gif(&gif(3)));
+        assert!(!is_animated_gif(&gif(1)));
+        assert!(!is_animated_gif(&gif(2)[..24]));
+        // 画布尺寸超出上限时不解码
+        let mut huge = gif(2);
+        huge[6..10].copy_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+        assert!(!is_animated_gif(&huge));
+        assert!(!is_animated_gif(&[0x89, b'P', b'N', b'G']));
+    }
+
+    #[test]
+    fn parses_proxy_urls() {
+        for proxy in ["http://127.0.0.1:7890", "https://proxy.example.com", " socks5://localhost:1080 "] {
+            assert!(parse_proxy(proxy).is_ok(), "{}", proxy);
+        }
+        assert!(parse_proxy("not a url").unwrap_err().contains("代理地址无效"));
+        assert!(parse_proxy("ftp://127.0.0.1:21").unwrap_err().contains("ftp"));
+        assert!(parse_proxy("socks5://").is_err());
+    }
+
+    #[tokio::test]
+    async fn client_builder_routes_through_proxy() {
+        // 本地服务器充当代理，收到的是发往任意主机的请求
+        let proxy = serve_once(b"via proxy".to_vec(), Duration::ZERO).await;
+        let proxy = proxy.trim_end_matches("/image.png");
+        let client = client_builder_with_proxy(Some(proxy)).build().unwrap();
+        let response = client.get("http://meme.invalid/a.png").send().await.unwrap();
+        assert_eq!(response.bytes().await.unwrap().as_ref(), b"via proxy");
+    }
+
+    #[tokio::test]
+    async fn client_builder_without_valid_proxy_connects_directly() {
+        for proxy in [None, Some(""), Some("garbage")] {
+            let url = serve_once(b"direct".to_vec(), Duration::ZERO).await;
+            let client = client_builder_with_proxy(proxy).build().unwrap();
+            let response = client.get(&url).send().await.unwrap();
+            assert_eq!(response.bytes().await.unwrap().as_ref(), b"direct");
+        }
+    }
+
+    #[test]
+    fn decodes_base64_data_uri() {
+        let image = decode_data_uri("data:image/PNG;base64,iVBORw0KGgo=").unwrap().unwrap();
+        assert_eq!(image.bytes, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+        assert_eq!(image.content_type.as_deref(), Some("image/png"));
+
+        assert!(decode_data_uri("https://example.com/a.png").is_none());
+        assert!(decode_data_uri("data:text/plain,hello").unwrap().is_err());
+        assert!(decode_data_uri("data:image/png;base64,***").unwrap().is_err());
     }
 }
@@ -0,0 +1,25 @@
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use log::debug;
+
+/// 向当前获得焦点的窗口发送粘贴快捷键（macOS 为 Cmd+V，其他平台为 Ctrl+V）
+///
+/// macOS 需要在“系统设置 > 隐私与安全性 > 辅助功能”中授权本应用，否则按键会被系统丢弃；
+/// Linux 仅支持 X11，Wayland 下合成按键通常不会被其他应用接收。
+pub fn send_paste_keystroke() -> Result<(), String> {
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("无法初始化键盘模拟: {}", e))?;
+
+    let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+
+    enigo
+        .key(modifier, Direction::Press)
+        .map_err(|e| format!("模拟按键失败: {}", e))?;
+    let pasted = enigo.key(Key::Unicode('v'), Direction::Click);
+    // 无论粘贴键是否成功都要松开修饰键，避免修饰键卡住
+    let released = enigo.key(modifier, Direction::Release);
+
+    pasted.map_err(|e| format!("模拟按键失败: {}", e))?;
+    released.map_err(|e| format!("模拟按键失败: {}", e))?;
+    debug!("已发送粘贴快捷键");
+    Ok(())
+}
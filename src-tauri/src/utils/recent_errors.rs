@@ -0,0 +1,51 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 最多保留的错误条数
+const MAX_RECENT_ERRORS: usize = 50;
+
+/// 一条需要让用户知晓的近期错误
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentError {
+    /// 发生时间（Unix秒）
+    pub timestamp: u64,
+    /// 出错的模块或功能
+    pub source: String,
+    pub message: String,
+}
+
+static RECENT_ERRORS: OnceLock<Mutex<VecDeque<RecentError>>> = OnceLock::new();
+
+fn recent_errors() -> &'static Mutex<VecDeque<RecentError>> {
+    RECENT_ERRORS.get_or_init(|| Mutex::new(VecDeque::with_capacity(MAX_RECENT_ERRORS)))
+}
+
+/// 记录一条近期错误，超出上限时丢弃最早的记录
+pub fn record(source: &str, message: impl Into<String>) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut errors = recent_errors().lock().unwrap_or_else(|e| e.into_inner());
+    if errors.len() >= MAX_RECENT_ERRORS {
+        errors.pop_front();
+    }
+    errors.push_back(RecentError {
+        timestamp,
+        source: source.to_string(),
+        message: message.into(),
+    });
+}
+
+/// 按时间从新到旧返回近期错误
+pub fn snapshot() -> Vec<RecentError> {
+    recent_errors()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .rev()
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,133 @@
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri_plugin_http::reqwest::header::HeaderMap;
+use tauri_plugin_http::reqwest::{Request, StatusCode};
+
+use crate::utils::misc::redact_url;
+
+// 请求追踪开关，调试构建默认开启，发布构建默认关闭
+static REQUEST_TRACING: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+// 日志中响应体的最大字符数
+const BODY_SNIPPET_CHARS: usize = 2000;
+
+// 值需要隐去的请求/响应头
+const SENSITIVE_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+// 名称包含这些词的请求头同样视为敏感，覆盖各API自定义的认证头
+const SENSITIVE_HEADER_WORDS: [&str; 5] = ["auth", "token", "key", "secret", "password"];
+
+/// 请求头的值是否需要在日志中隐去
+pub fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_HEADERS.contains(&name.as_str())
+        || SENSITIVE_HEADER_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// 开启或关闭请求追踪
+pub fn set_enabled(enabled: bool) {
+    REQUEST_TRACING.store(enabled, Ordering::SeqCst);
+    info!("请求追踪已{}", if enabled { "开启" } else { "关闭" });
+}
+
+/// 请求追踪是否开启
+pub fn is_enabled() -> bool {
+    REQUEST_TRACING.load(Ordering::Relaxed)
+}
+
+/// 格式化请求头，隐去敏感值
+pub fn format_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive_header(name.as_str()) {
+                "***".to_string()
+            } else {
+                value.to_str().unwrap_or("<二进制>").to_string()
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn snippet(body: &str) -> String {
+    if body.chars().count() > BODY_SNIPPET_CHARS {
+        let truncated: String = body.chars().take(BODY_SNIPPET_CHARS).collect();
+        format!("{}…（已截断）", truncated)
+    } else {
+        body.to_string()
+    }
+}
+
+/// 记录即将发送的请求
+pub fn trace_request(request: &Request) {
+    if !is_enabled() {
+        return;
+    }
+
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| snippet(&String::from_utf8_lossy(bytes)))
+        .unwrap_or_default();
+
+    info!(
+        "[请求追踪] {} {} 请求头: [{}] 请求体: {}",
+        request.method(),
+        redact_url(request.url().as_str()),
+        format_headers(request.headers()),
+        body
+    );
+}
+
+/// 记录收到的响应
+pub fn trace_response(url: &str, status: StatusCode, headers: &HeaderMap, body: &str) {
+    if !is_enabled() {
+        return;
+    }
+
+    info!(
+        "[请求追踪] {} 响应状态: {} 响应头: [{}] 响应体: {}",
+        redact_url(url),
+        status,
+        format_headers(headers),
+        snippet(body)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri_plugin_http::reqwest::header::HeaderValue;
+
+    #[test]
+    fn detects_sensitive_headers() {
+        for name in ["Authorization", "cookie", "X-API-Key", "X-Auth-Token", "Client-Secret"] {
+            assert!(is_sensitive_header(name), "{} 应被视为敏感", name);
+        }
+        for name in ["Content-Type", "Accept", "X-Tenant"] {
+            assert!(!is_sensitive_header(name), "{} 不应被视为敏感", name);
+        }
+    }
+
+    #[test]
+    fn masks_sensitive_header_values() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+        headers.insert("x-access-token", HeaderValue::from_static("abc123"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+
+        let formatted = format_headers(&headers);
+        assert!(!formatted.contains("secret") && !formatted.contains("abc123"));
+        assert!(formatted.contains("authorization: ***"));
+        assert!(formatted.contains("x-access-token: ***"));
+        assert!(formatted.contains("content-type: application/json"));
+    }
+}